@@ -0,0 +1,242 @@
+use bevy::prelude::*;
+
+use crate::block::{Block, BlockKind, HandBlock};
+use crate::camera::PlayfieldBackground;
+use crate::*;
+
+// 无障碍设置 生成时应用 修改后实时生效
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct AccessibilitySettings {
+    // 高对比度配色 方块显示种类描边
+    pub high_contrast: bool,
+    // 方块种类描边与字符标识
+    pub kind_overlays: bool,
+    // 背景颜色
+    pub background_color: Color,
+    // HUD 大号字体
+    pub large_text: bool,
+}
+
+impl Default for AccessibilitySettings {
+    fn default() -> Self {
+        Self {
+            high_contrast: false,
+            kind_overlays: false,
+            background_color: Color::rgb_u8(BG_COLOR.0, BG_COLOR.1, BG_COLOR.2),
+            large_text: false,
+        }
+    }
+}
+
+impl AccessibilitySettings {
+    // 实际使用的背景色 高对比度时固定为黑色
+    pub fn effective_background(&self) -> Color {
+        if self.high_contrast {
+            Color::BLACK
+        } else {
+            self.background_color
+        }
+    }
+
+    pub fn text_scale(&self) -> f32 {
+        if self.large_text {
+            LARGE_TEXT_SCALE
+        } else {
+            1.0
+        }
+    }
+
+    // 方块种类描边颜色 高对比度使用色盲友好配色
+    pub fn kind_color(&self, kind: BlockKind) -> Color {
+        let palette = if self.high_contrast {
+            &HIGH_CONTRAST_KIND_COLORS
+        } else {
+            &KIND_COLORS
+        };
+        let index = BlockKind::ALL.iter().position(|k| *k == kind).unwrap_or(0);
+        let (r, g, b) = palette[index];
+
+        Color::rgb_u8(r, g, b)
+    }
+}
+
+// 可缩放文本 记录基础字号
+#[derive(Component)]
+pub struct ScalableText(pub f32);
+
+// 方块种类辅助标识子实体
+#[derive(Component)]
+pub struct KindOverlay {
+    outline: Entity,
+    letter: Entity,
+}
+
+#[derive(Component)]
+struct KindOutline;
+
+#[derive(Component)]
+struct KindLetter;
+
+pub struct AccessibilityPlugin;
+
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AccessibilitySettings>().add_systems(
+            Update,
+            (
+                handle_accessibility_keys,
+                attach_kind_overlays,
+                update_kind_overlays,
+                apply_background_color,
+                apply_text_scale,
+            )
+                .chain(),
+        );
+    }
+}
+
+// 快捷键 F3 高对比度 F4 种类标识 F5 大号字体 F6 切换背景色
+fn handle_accessibility_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<AccessibilitySettings>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F3) {
+        settings.high_contrast = !settings.high_contrast;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::F4) {
+        settings.kind_overlays = !settings.kind_overlays;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        settings.large_text = !settings.large_text;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::F6) {
+        settings.background_color = next_background_color(settings.background_color);
+    }
+}
+
+// 循环背景色预设
+pub fn next_background_color(current: Color) -> Color {
+    let presets = BACKGROUND_COLOR_PRESETS.map(|(r, g, b)| Color::rgb_u8(r, g, b));
+    let index = presets.iter().position(|c| *c == current);
+
+    match index {
+        Some(i) => presets[(i + 1) % presets.len()],
+        None => presets[0],
+    }
+}
+
+// 为新生成的方块添加描边与字符子实体
+#[allow(clippy::type_complexity)]
+fn attach_kind_overlays(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<Entity, (Or<(Added<Block>, Added<HandBlock>)>, Without<KindOverlay>)>,
+) {
+    for entity in query.iter() {
+        let outline = commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::splat(TILES_W as f32 + 2.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, 0.0, -0.05),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                KindOutline,
+            ))
+            .id();
+
+        let letter = commands
+            .spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load(FONT_PATH),
+                            font_size: KIND_LETTER_FONT_SIZE,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    transform: Transform::from_xyz(4.0, -4.0, 0.1),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                KindLetter,
+            ))
+            .id();
+
+        commands
+            .entity(entity)
+            .push_children(&[outline, letter])
+            .insert(KindOverlay { outline, letter });
+    }
+}
+
+// 方块种类或设置变化时刷新标识
+#[allow(clippy::type_complexity)]
+fn update_kind_overlays(
+    settings: Res<AccessibilitySettings>,
+    query: Query<(Ref<TextureAtlas>, Ref<KindOverlay>)>,
+    mut outline_query: Query<(&mut Sprite, &mut Visibility), With<KindOutline>>,
+    mut letter_query: Query<(&mut Text, &mut Visibility), (With<KindLetter>, Without<KindOutline>)>,
+) {
+    for (atlas, overlay) in query.iter() {
+        if !settings.is_changed() && !atlas.is_changed() && !overlay.is_changed() {
+            continue;
+        }
+
+        let kind = BlockKind::from_atlas_index(atlas.index);
+        // 高对比度时描边常显 字符仅在开启种类标识时显示
+        let visible = |shown: bool| match kind {
+            Some(_) if shown => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+
+        if let Ok((mut sprite, mut outline_visibility)) = outline_query.get_mut(overlay.outline) {
+            if let Some(kind) = kind {
+                sprite.color = settings.kind_color(kind);
+            }
+            *outline_visibility = visible(settings.kind_overlays || settings.high_contrast);
+        }
+
+        if let Ok((mut text, mut letter_visibility)) = letter_query.get_mut(overlay.letter) {
+            text.sections[0].value = kind.map(|k| k.letter()).unwrap_or_default().to_string();
+            *letter_visibility = visible(settings.kind_overlays);
+        }
+    }
+}
+
+// 背景色
+fn apply_background_color(
+    settings: Res<AccessibilitySettings>,
+    mut query: Query<&mut Sprite, With<PlayfieldBackground>>,
+) {
+    for mut sprite in query.iter_mut() {
+        if settings.is_changed() || sprite.is_added() {
+            sprite.color = settings.effective_background();
+        }
+    }
+}
+
+// HUD 字号
+fn apply_text_scale(
+    settings: Res<AccessibilitySettings>,
+    mut query: Query<(&mut Text, Ref<ScalableText>)>,
+) {
+    let scale = settings.text_scale();
+
+    for (mut text, scalable_text) in query.iter_mut() {
+        if !settings.is_changed() && !scalable_text.is_added() {
+            continue;
+        }
+
+        for section in text.sections.iter_mut() {
+            section.style.font_size = scalable_text.0 * scale;
+        }
+    }
+}
@@ -0,0 +1,511 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::{BoxedFuture, HashMap};
+
+use crate::board::Board;
+use crate::events::{BlocksRemoved, LightningRescued, StageCleared};
+use crate::gui::CountDown;
+use crate::locale::LocalizedText;
+use crate::player::Controller;
+use crate::state::{GameMode, GameState};
+use crate::stats::Stats;
+use crate::storage::{format_key_values, parse_key_values, read_data, write_data};
+use crate::tween::{Ease, Tween, TweenTarget};
+use crate::*;
+
+// 成就进度存档文件
+const ACHIEVEMENTS_FILE: &str = "achievements.txt";
+// 连续未补给闪电块过关数的存档键
+const NO_LIGHTNING_KEY: &str = "streak.clears_without_lightning";
+
+// 成就目标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AchievementGoal {
+    // 累计投掷次数
+    Throws(u64),
+    // 一次投掷消除的方块数
+    Removal(u64),
+    // 累计消除方块数
+    TotalRemoved(u64),
+    // 累计通过关卡数
+    StagesCleared(u64),
+    // 过关时剩余秒数
+    ClearTimeLeft(u64),
+    // 连续未补给闪电块过关数
+    ClearsWithoutLightning(u64),
+}
+
+impl AchievementGoal {
+    pub fn target(&self) -> u64 {
+        match *self {
+            AchievementGoal::Throws(n)
+            | AchievementGoal::Removal(n)
+            | AchievementGoal::TotalRemoved(n)
+            | AchievementGoal::StagesCleared(n)
+            | AchievementGoal::ClearTimeLeft(n)
+            | AchievementGoal::ClearsWithoutLightning(n) => n,
+        }
+    }
+
+    // `类型 数量`
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let (kind, target) = source
+            .split_once(' ')
+            .ok_or(format!("goal without target: {}", source))?;
+        let target = target
+            .trim()
+            .parse()
+            .map_err(|e| format!("goal target: {}", e))?;
+
+        Ok(match kind {
+            "throws" => AchievementGoal::Throws(target),
+            "removal" => AchievementGoal::Removal(target),
+            "total_removed" => AchievementGoal::TotalRemoved(target),
+            "stages_cleared" => AchievementGoal::StagesCleared(target),
+            "clear_time_left" => AchievementGoal::ClearTimeLeft(target),
+            "clears_without_lightning" => AchievementGoal::ClearsWithoutLightning(target),
+            _ => return Err(format!("unknown goal: {}", kind)),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AchievementDef {
+    pub id: String,
+    pub goal: AchievementGoal,
+}
+
+impl AchievementDef {
+    // 成就名称的本地化键 说明为 `<键>.desc`
+    pub fn title_key(&self) -> String {
+        format!("achievement.{}", self.id)
+    }
+
+    pub fn desc_key(&self) -> String {
+        format!("achievement.{}.desc", self.id)
+    }
+}
+
+// 成就列表 每个成就以 `achievement = id` 开始 随后 `goal = 类型 数量`
+#[derive(Asset, TypePath, Debug, Default)]
+pub struct AchievementList(pub Vec<AchievementDef>);
+
+impl AchievementList {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut defs = vec![];
+        let mut current: Option<String> = None;
+
+        for (key, value) in parse_key_values(source) {
+            match key.as_str() {
+                "achievement" => current = Some(value),
+                "goal" => {
+                    let id = current
+                        .take()
+                        .ok_or(format!("goal before achievement: {}", value))?;
+                    defs.push(AchievementDef {
+                        id,
+                        goal: AchievementGoal::parse(&value)?,
+                    });
+                }
+                _ => return Err(format!("unknown key: {}", key)),
+            }
+        }
+
+        Ok(Self(defs))
+    }
+}
+
+#[derive(Default)]
+pub struct AchievementListLoader;
+
+impl AssetLoader for AchievementListLoader {
+    type Asset = AchievementList;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader.read_to_string(&mut source).await?;
+
+            AchievementList::parse(&source)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["achievements"]
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct AchievementListHandle(pub Handle<AchievementList>);
+
+// 成就进度 以成就 id 保存达到过的最大值 达到目标即解锁
+#[derive(Resource, Debug, Default)]
+pub struct AchievementProgress {
+    pub progress: HashMap<String, u64>,
+    // 连续未补给闪电块过关数 跨局保留
+    pub clears_without_lightning: u64,
+}
+
+impl AchievementProgress {
+    pub fn progress(&self, def: &AchievementDef) -> u64 {
+        self.progress.get(&def.id).copied().unwrap_or(0)
+    }
+
+    pub fn is_unlocked(&self, def: &AchievementDef) -> bool {
+        self.progress(def) >= def.goal.target()
+    }
+
+    // 更新进度 value 返回 None 表示该目标与本次事件无关 返回新解锁的成就
+    pub fn update<'a>(
+        &mut self,
+        defs: &'a [AchievementDef],
+        value: impl Fn(&AchievementGoal) -> Option<u64>,
+    ) -> Vec<&'a AchievementDef> {
+        let mut unlocked = vec![];
+
+        for def in defs {
+            let Some(value) = value(&def.goal) else {
+                continue;
+            };
+
+            let was_unlocked = self.is_unlocked(def);
+            let progress = self.progress.entry(def.id.clone()).or_default();
+            *progress = (*progress).max(value);
+
+            if !was_unlocked && self.is_unlocked(def) {
+                unlocked.push(def);
+            }
+        }
+
+        unlocked
+    }
+
+    pub fn parse(source: &str) -> Self {
+        let mut achievements = Self::default();
+
+        for (key, value) in parse_key_values(source) {
+            let Ok(value) = value.parse() else {
+                continue;
+            };
+
+            if key == NO_LIGHTNING_KEY {
+                achievements.clears_without_lightning = value;
+            } else {
+                achievements.progress.insert(key, value);
+            }
+        }
+
+        achievements
+    }
+
+    pub fn to_source(&self) -> String {
+        let mut pairs = self
+            .progress
+            .iter()
+            .map(|(id, value)| (id.clone(), value.to_string()))
+            .collect::<Vec<_>>();
+        pairs.sort();
+        pairs.push((
+            String::from(NO_LIGHTNING_KEY),
+            self.clears_without_lightning.to_string(),
+        ));
+
+        format_key_values("Smart Block achievements", pairs)
+    }
+}
+
+// 解锁成就
+#[derive(Event, Debug, Clone)]
+pub struct AchievementUnlockedEvent(pub AchievementDef);
+
+// 解锁提示 显示在画面上方 计时结束销毁
+#[derive(Component)]
+struct AchievementToast(Timer);
+
+// 提示容器 多条提示纵向排列
+#[derive(Component)]
+struct AchievementToastRoot;
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<AchievementList>()
+            .init_asset_loader::<AchievementListLoader>()
+            .init_resource::<AchievementListHandle>()
+            .insert_resource(
+                read_data(ACHIEVEMENTS_FILE)
+                    .map(|source| AchievementProgress::parse(&source))
+                    .unwrap_or_default(),
+            )
+            .add_event::<AchievementUnlockedEvent>()
+            .add_systems(
+                Startup,
+                (load_achievement_list, spawn_achievement_toast_root),
+            )
+            .add_systems(
+                Update,
+                (
+                    track_removals,
+                    track_lightning_rescues,
+                    track_stage_clears,
+                    track_stats.run_if(resource_changed::<Stats>),
+                    save_achievements.run_if(on_event::<AchievementUnlockedEvent>()),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(OnExit(GameState::InGame), save_achievements)
+            .add_systems(Update, (spawn_achievement_toast, despawn_achievement_toast));
+    }
+}
+
+fn load_achievement_list(
+    mut handle: ResMut<AchievementListHandle>,
+    asset_server: Res<AssetServer>,
+) {
+    handle.0 = asset_server.load(ACHIEVEMENTS_PATH);
+}
+
+// 是否为键盘玩家的棋盘 演示与 AI 不解锁成就
+fn is_player_board(board: Entity, board_query: &Query<&Controller, With<Board>>) -> bool {
+    board_query
+        .get(board)
+        .is_ok_and(|controller| *controller == Controller::Keyboard)
+}
+
+// 更新进度并发送解锁事件
+fn update_progress(
+    handle: &AchievementListHandle,
+    lists: &Assets<AchievementList>,
+    achievements: &mut AchievementProgress,
+    unlocked_events: &mut EventWriter<AchievementUnlockedEvent>,
+    value: impl Fn(&AchievementGoal) -> Option<u64>,
+) {
+    let Some(list) = lists.get(&handle.0) else {
+        return;
+    };
+
+    for def in achievements.update(&list.0, value) {
+        info!("achievement unlocked: {}", def.id);
+        unlocked_events.send(AchievementUnlockedEvent(def.clone()));
+    }
+}
+
+fn track_removals(
+    mut events: EventReader<BlocksRemoved>,
+    handle: Res<AchievementListHandle>,
+    lists: Res<Assets<AchievementList>>,
+    mut achievements: ResMut<AchievementProgress>,
+    mut unlocked_events: EventWriter<AchievementUnlockedEvent>,
+    board_query: Query<&Controller, With<Board>>,
+) {
+    for event in events.read() {
+        if !is_player_board(event.board, &board_query) {
+            continue;
+        }
+
+        update_progress(
+            &handle,
+            &lists,
+            &mut achievements,
+            &mut unlocked_events,
+            |goal| match goal {
+                AchievementGoal::Removal(_) => Some(event.count as u64),
+                _ => None,
+            },
+        );
+    }
+}
+
+// 补给闪电块 连续过关数清零
+fn track_lightning_rescues(
+    mut events: EventReader<LightningRescued>,
+    mut achievements: ResMut<AchievementProgress>,
+    board_query: Query<&Controller, With<Board>>,
+) {
+    for LightningRescued { board } in events.read() {
+        if is_player_board(*board, &board_query) {
+            achievements.clears_without_lightning = 0;
+        }
+    }
+}
+
+// 过关 剩余时间及连续未补给闪电块过关数
+#[allow(clippy::too_many_arguments)]
+fn track_stage_clears(
+    mut events: EventReader<StageCleared>,
+    mode: Res<GameMode>,
+    handle: Res<AchievementListHandle>,
+    lists: Res<Assets<AchievementList>>,
+    mut achievements: ResMut<AchievementProgress>,
+    mut unlocked_events: EventWriter<AchievementUnlockedEvent>,
+    board_query: Query<&Controller, With<Board>>,
+    count_down_query: Query<&CountDown>,
+) {
+    for event in events.read() {
+        if !is_player_board(event.board, &board_query) {
+            continue;
+        }
+
+        achievements.clears_without_lightning += 1;
+        let clears = achievements.clears_without_lightning;
+        // 只有计时模式的剩余时间有意义
+        let time_left = count_down_query
+            .get(event.board)
+            .ok()
+            .filter(|_| mode.is_timed())
+            .map(|count_down| count_down.0.remaining_secs() as u64);
+
+        update_progress(
+            &handle,
+            &lists,
+            &mut achievements,
+            &mut unlocked_events,
+            |goal| match goal {
+                AchievementGoal::ClearsWithoutLightning(_) => Some(clears),
+                AchievementGoal::ClearTimeLeft(_) => time_left,
+                _ => None,
+            },
+        );
+    }
+}
+
+// 累计类成就 取自统计
+fn track_stats(
+    stats: Res<Stats>,
+    handle: Res<AchievementListHandle>,
+    lists: Res<Assets<AchievementList>>,
+    mut achievements: ResMut<AchievementProgress>,
+    mut unlocked_events: EventWriter<AchievementUnlockedEvent>,
+) {
+    update_progress(
+        &handle,
+        &lists,
+        &mut achievements,
+        &mut unlocked_events,
+        |goal| match goal {
+            AchievementGoal::Throws(_) => Some(stats.throws),
+            AchievementGoal::TotalRemoved(_) => Some(stats.total_removed()),
+            AchievementGoal::StagesCleared(_) => Some(stats.stages_cleared),
+            _ => None,
+        },
+    );
+}
+
+// 解锁时及每关结束时保存
+fn save_achievements(achievements: Res<AchievementProgress>) {
+    if !achievements.is_changed() {
+        return;
+    }
+
+    if let Err(e) = write_data(ACHIEVEMENTS_FILE, &achievements.to_source()) {
+        warn!("failed to save achievements: {}", e);
+    }
+}
+
+fn spawn_achievement_toast_root(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                top: Val::Px(16.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            z_index: ZIndex::Global(ACHIEVEMENT_TOAST_Z),
+            ..default()
+        },
+        AchievementToastRoot,
+    ));
+}
+
+// 生成解锁提示
+fn spawn_achievement_toast(
+    mut commands: Commands,
+    mut unlocked_events: EventReader<AchievementUnlockedEvent>,
+    asset_server: Res<AssetServer>,
+    root_query: Query<Entity, With<AchievementToastRoot>>,
+) {
+    let Ok(root) = root_query.get_single() else {
+        return;
+    };
+
+    for AchievementUnlockedEvent(def) in unlocked_events.read() {
+        let font = asset_server.load(FONT_PATH);
+        let toast = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(12.0)),
+                        border: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                    border_color: Color::YELLOW.into(),
+                    transform: Transform::from_scale(Vec3::ZERO),
+                    ..default()
+                },
+                AchievementToast(Timer::from_seconds(ACHIEVEMENT_TOAST_SEC, TimerMode::Once)),
+                Tween::new(UI_TRANSITION_SEC, Ease::BackOut).with(TweenTarget::Scale {
+                    from: Vec3::ZERO,
+                    to: Vec3::ONE,
+                }),
+            ))
+            .with_children(|parent| {
+                for (key, font_size, color) in [
+                    (String::from("achievement.unlocked"), 20.0, Color::YELLOW),
+                    (def.title_key(), 28.0, Color::WHITE),
+                ] {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: font.clone(),
+                                font_size,
+                                color,
+                            },
+                        ),
+                        LocalizedText::new(key),
+                    ));
+                }
+            })
+            .id();
+
+        commands.entity(root).add_child(toast);
+    }
+}
+
+// 收起解锁提示 缩小后销毁
+fn despawn_achievement_toast(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut toast_query: Query<(Entity, &mut AchievementToast)>,
+) {
+    for (entity, mut toast) in toast_query.iter_mut() {
+        if toast.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).insert(
+                Tween::new(UI_TRANSITION_SEC, Ease::QuadIn)
+                    .with(TweenTarget::Scale {
+                        from: Vec3::ONE,
+                        to: Vec3::ZERO,
+                    })
+                    .despawn_on_complete(),
+            );
+        }
+    }
+}
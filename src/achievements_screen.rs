@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+
+use crate::achievements::{AchievementList, AchievementListHandle, AchievementProgress};
+use crate::locale::LocalizedText;
+use crate::menu::{despawn_screen, menu_root, spawn_back_button, spawn_menu_title, MenuActivated};
+use crate::state::GameState;
+use crate::*;
+
+#[derive(Component)]
+struct AchievementsScreenItem;
+
+// 返回排行榜按钮
+#[derive(Component)]
+struct AchievementsBackButton;
+
+pub struct AchievementsScreenPlugin;
+
+impl Plugin for AchievementsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Achievements), setup_achievements_screen)
+            .add_systems(
+                OnExit(GameState::Achievements),
+                despawn_screen::<AchievementsScreenItem>,
+            )
+            .add_systems(
+                Update,
+                handle_achievements_back_button.run_if(in_state(GameState::Achievements)),
+            );
+    }
+}
+
+// 生成成就界面 已解锁高亮 未解锁显示进度
+fn setup_achievements_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    handle: Res<AchievementListHandle>,
+    lists: Res<Assets<AchievementList>>,
+    achievements: Res<AchievementProgress>,
+) {
+    let font = asset_server.load(FONT_PATH);
+    let defs = lists
+        .get(&handle.0)
+        .map(|list| list.0.as_slice())
+        .unwrap_or_default();
+
+    commands
+        .spawn((menu_root(8.0), AchievementsScreenItem))
+        .with_children(|parent| {
+            let unlocked = defs
+                .iter()
+                .filter(|def| achievements.is_unlocked(def))
+                .count();
+
+            spawn_menu_title(
+                parent,
+                LocalizedText::new("achievement.title")
+                    .with_args(vec![unlocked.to_string(), defs.len().to_string()]),
+            );
+
+            for def in defs {
+                let is_unlocked = achievements.is_unlocked(def);
+                let color = if is_unlocked {
+                    Color::YELLOW
+                } else {
+                    Color::GRAY
+                };
+                let text_style = |font_size| TextStyle {
+                    font: font.clone(),
+                    font_size,
+                    color,
+                };
+
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(ACHIEVEMENT_ROW_W),
+                            justify_content: JustifyContent::SpaceBetween,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    TextBundle::from_section("", text_style(24.0)),
+                                    LocalizedText::new(def.title_key()),
+                                ));
+                                parent.spawn((
+                                    TextBundle::from_section("", text_style(16.0)),
+                                    LocalizedText::new(def.desc_key()),
+                                ));
+                            });
+
+                        let status = if is_unlocked {
+                            LocalizedText::new("achievement.done")
+                        } else {
+                            LocalizedText::new("achievement.progress").with_args(vec![
+                                achievements.progress(def).to_string(),
+                                def.goal.target().to_string(),
+                            ])
+                        };
+                        parent.spawn((TextBundle::from_section("", text_style(24.0)), status));
+                    });
+            }
+
+            spawn_back_button(parent, AchievementsBackButton);
+        });
+}
+
+// 返回排行榜界面
+fn handle_achievements_back_button(
+    mut activated_events: EventReader<MenuActivated>,
+    button_query: Query<(), With<AchievementsBackButton>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in activated_events.read() {
+        if button_query.contains(event.0) {
+            next_state.set(GameState::HighScores);
+        }
+    }
+}
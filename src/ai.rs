@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+
+use crate::block::{grid_from_blocks, Block, HandBlock};
+use crate::board::InBoard;
+use crate::player::{Controller, Player, PlayerInput, PlayerInputSet};
+use crate::rules::pos_to_cell;
+use crate::state::{BlockGroupState, BoardState, GameState, HandBlockState, SettlementState};
+use crate::*;
+
+// AI 操作间隔 操作者为 AI 的棋盘才有
+#[derive(Component)]
+pub struct AiTimer(Timer);
+impl Default for AiTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(AI_STEP_SEC, TimerMode::Repeating))
+    }
+}
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            ai_player
+                .in_set(PlayerInputSet)
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+// AI 玩家 与模拟器使用相同的规则选择行 移动到该行后投掷
+#[allow(clippy::type_complexity)]
+fn ai_player(
+    time: Res<Time>,
+    mut board_query: Query<(
+        Entity,
+        &Controller,
+        &mut AiTimer,
+        &mut PlayerInput,
+        &BoardState<HandBlockState>,
+        &BoardState<BlockGroupState>,
+        &BoardState<SettlementState>,
+    )>,
+    player_query: Query<(&Transform, &InBoard), With<Player>>,
+    block_query: Query<(&Transform, &TextureAtlas, &InBoard), With<Block>>,
+    hand_block_query: Query<(&TextureAtlas, &InBoard), (With<HandBlock>, Without<Block>)>,
+) {
+    for (board, controller, mut timer, mut input, hand_state, group_state, settlement_state) in
+        board_query.iter_mut()
+    {
+        if *controller != Controller::Ai
+            || !hand_state.is(HandBlockState::Idle)
+            || !group_state.is(BlockGroupState::Static)
+            || !settlement_state.is(SettlementState::Not)
+        {
+            continue;
+        }
+
+        if !timer.0.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let Some((player_transform, _)) = player_query.iter().find(|(_, b)| b.0 == board) else {
+            continue;
+        };
+        let Some((hand_block_atlas, _)) = hand_block_query.iter().find(|(_, b)| b.0 == board)
+        else {
+            continue;
+        };
+
+        let player_row = pos_to_cell(player_transform.translation.truncate()).y;
+        let hand_index = hand_block_atlas.index;
+
+        let Some(target_row) =
+            grid_from_blocks(board, &block_query).bot_row(hand_index, player_row)
+        else {
+            continue;
+        };
+
+        if target_row > player_row {
+            input.up = true;
+        } else if target_row < player_row {
+            input.down = true;
+        } else {
+            input.throw = true;
+        }
+    }
+}
@@ -1,86 +1,97 @@
 use bevy::prelude::*;
 
+use crate::board::InBoard;
+use crate::locale::LocalizedText;
 use crate::state::GameState;
+use crate::tween::{Ease, Tween, TweenTarget};
+use crate::world::GameEntity;
+use crate::*;
 
 pub struct AlertPlugin;
 
 impl Plugin for AlertPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<DespawnAlertTextTimer>()
-            .add_event::<AlertEvent>()
-            .add_systems(
-                Update,
-                (spawn_game_alert, despawn_alert_text).run_if(in_state(GameState::InGame)),
-            );
+        app.add_event::<AlertEvent>().add_systems(
+            Update,
+            (spawn_game_alert, despawn_alert_text).run_if(in_state(GameState::InGame)),
+        );
     }
 }
 
+// 提示事件 内容为本地化键 显示在所属棋盘
 #[derive(Event)]
-pub struct AlertEvent(pub Option<String>);
-impl Default for AlertEvent {
-    fn default() -> Self {
-        Self(None)
-    }
+pub struct AlertEvent {
+    pub board: Entity,
+    pub key: Option<String>,
 }
 
+// 提示文案 3s后淡出销毁
 #[derive(Component)]
-struct AlertText;
-
-#[derive(Resource)]
-struct DespawnAlertTextTimer(Timer);
-impl Default for DespawnAlertTextTimer {
+struct AlertText(Timer);
+impl Default for AlertText {
     fn default() -> Self {
-        Self(Timer::from_seconds(3.0, TimerMode::Repeating))
+        Self(Timer::from_seconds(3.0, TimerMode::Once))
     }
 }
 
-// 生成提示文案
+// 生成提示文案 同一棋盘只保留最新一条
 fn spawn_game_alert(
     mut commands: Commands,
     mut event: EventReader<AlertEvent>,
-    query: Query<Entity, With<AlertText>>,
+    query: Query<(Entity, &InBoard), With<AlertText>>,
 ) {
     for e in event.read() {
-        if !query.is_empty() {
-            commands.entity(query.single()).despawn();
+        for (entity, in_board) in query.iter() {
+            if in_board.0 == e.board {
+                commands.entity(entity).despawn();
+            }
         }
 
-        if let Some(text) = &e.0 {
+        if let Some(key) = &e.key {
             // 生成提示文案
-            commands.spawn((
-                Text2dBundle {
-                    text: Text::from_section(
-                        text,
-                        TextStyle {
-                            font_size: 24.0,
-                            color: Color::RED,
-                            ..default()
-                        },
-                    ),
-                    transform: Transform::from_xyz(0.0, 200.0, 1.0),
-                    ..default()
-                },
-                AlertText,
-            ));
+            let alert = commands
+                .spawn((
+                    Text2dBundle {
+                        text: Text::from_section(
+                            "",
+                            TextStyle {
+                                font_size: 24.0,
+                                color: Color::RED,
+                                ..default()
+                            },
+                        ),
+                        transform: Transform::from_xyz(0.0, 200.0, 1.0).with_scale(Vec3::ZERO),
+                        ..default()
+                    },
+                    AlertText::default(),
+                    Tween::new(UI_TRANSITION_SEC, Ease::BackOut).with(TweenTarget::Scale {
+                        from: Vec3::ZERO,
+                        to: Vec3::ONE,
+                    }),
+                    InBoard(e.board),
+                    GameEntity,
+                    LocalizedText::new(key.clone()),
+                ))
+                .id();
+            commands.entity(e.board).add_child(alert);
         }
     }
 }
 
-// 销毁提示文案  1s延时
+// 淡出提示文案
 fn despawn_alert_text(
     time: Res<Time>,
     mut commands: Commands,
-    mut timer: ResMut<DespawnAlertTextTimer>,
-    mut text_query: Query<Entity, With<AlertText>>,
+    mut text_query: Query<(Entity, &mut AlertText)>,
 ) {
-    if text_query.is_empty() {
-        return;
-    }
-
-    let entity = text_query.single_mut();
-
-    // 计时器
-    if timer.0.tick(time.delta()).just_finished() {
-        commands.entity(entity).despawn();
+    for (entity, mut alert) in text_query.iter_mut() {
+        // 计时器
+        if alert.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).insert(
+                Tween::new(UI_TRANSITION_SEC, Ease::Linear)
+                    .with(TweenTarget::Alpha { from: 1.0, to: 0.0 })
+                    .despawn_on_complete(),
+            );
+        }
     }
 }
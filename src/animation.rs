@@ -1,83 +1,356 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
 use bevy::prelude::*;
+use bevy::utils::{BoxedFuture, HashMap};
 
-use crate::{
-    gui::HighScore,
-    player::Player,
-    state::{GameState, PlayerState},
-    HIGH_SCORE_ANIMATION_SPEED,
-};
+use crate::block::{Block, HandBlock};
+use crate::events::BlockHit;
+use crate::player::Player;
+use crate::resources::GlobalTextAtlas;
+use crate::rules::HitResult;
+use crate::state::{GameState, PlayerState};
+use crate::storage::parse_key_values;
+use crate::world::GameEntity;
+use crate::*;
 
+// 动画片段 按帧率依次显示图集下标
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteClip {
+    pub frames: Vec<usize>,
+    pub fps: f32,
+    pub looping: bool,
+}
+
+impl SpriteClip {
+    // 帧列表 空格分隔 a-b 为连续范围
+    fn parse_frames(source: &str) -> Result<Vec<usize>, String> {
+        let mut frames = vec![];
+        for item in source.split_whitespace() {
+            let (start, end) = item.split_once('-').unwrap_or((item, item));
+            let start: usize = start.parse().map_err(|e| format!("frame: {}", e))?;
+            let end: usize = end.parse().map_err(|e| format!("frame: {}", e))?;
+            if start > end || end >= SPRITE_SHEET_W * SPRITE_SHEET_H {
+                return Err(format!("frame out of range: {}", item));
+            }
+            frames.extend(start..=end);
+        }
+
+        Ok(frames)
+    }
+}
+
+// 精灵清单 片段名到片段
+#[derive(Asset, TypePath, Debug, Default)]
+pub struct SpriteClips(pub HashMap<String, SpriteClip>);
+
+impl SpriteClips {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut clips = HashMap::new();
+        let mut current: Option<(String, SpriteClip)> = None;
+
+        for (key, value) in parse_key_values(source) {
+            if key == "clip" {
+                clips.extend(current.take());
+                current = Some((
+                    value,
+                    SpriteClip {
+                        frames: vec![],
+                        fps: 1.0,
+                        looping: true,
+                    },
+                ));
+                continue;
+            }
+
+            let (_, clip) = current
+                .as_mut()
+                .ok_or(format!("{} before clip: {}", key, value))?;
+            match key.as_str() {
+                "frames" => clip.frames = SpriteClip::parse_frames(&value)?,
+                "fps" => {
+                    clip.fps = value.parse().map_err(|e| format!("fps: {}", e))?;
+                    if clip.fps <= 0.0 {
+                        return Err(format!("fps must be positive: {}", value));
+                    }
+                }
+                "mode" => {
+                    clip.looping = match value.as_str() {
+                        "loop" => true,
+                        "once" => false,
+                        _ => return Err(format!("unknown mode: {}", value)),
+                    }
+                }
+                _ => return Err(format!("unknown key: {}", key)),
+            }
+        }
+        clips.extend(current);
+
+        if let Some(name) = clips
+            .iter()
+            .find(|(_, clip)| clip.frames.is_empty())
+            .map(|(name, _)| name)
+        {
+            return Err(format!("clip without frames: {}", name));
+        }
+
+        Ok(Self(clips))
+    }
+}
+
+#[derive(Default)]
+pub struct SpriteClipsLoader;
+
+impl AssetLoader for SpriteClipsLoader {
+    type Asset = SpriteClips;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader.read_to_string(&mut source).await?;
+
+            SpriteClips::parse(&source)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sprites"]
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SpriteClipsHandle(pub Handle<SpriteClips>);
+
+// 精灵动画 播放清单中的片段 写入 TextureAtlas 下标
+#[derive(Component, Debug, Clone)]
+pub struct SpriteAnimation {
+    clip: String,
+    elapsed: f32,
+    finished: bool,
+    despawn: bool,
+}
+
+impl SpriteAnimation {
+    pub fn new(clip: &str) -> Self {
+        Self {
+            clip: clip.to_string(),
+            elapsed: 0.0,
+            finished: false,
+            despawn: false,
+        }
+    }
+
+    // 从头播放片段
+    pub fn play(&mut self, clip: &str) {
+        self.clip = clip.to_string();
+        self.elapsed = 0.0;
+        self.finished = false;
+    }
+
+    // 单次片段结束后销毁实体
+    pub fn despawn_on_finish(mut self) -> Self {
+        self.despawn = true;
+        self
+    }
+
+    pub fn clip(&self) -> &str {
+        &self.clip
+    }
+}
+
+// 单次片段播放结束
+#[derive(Event, Debug, Clone)]
+pub struct AnimationFinished {
+    pub entity: Entity,
+    pub clip: String,
+}
+
+// 闪电块上的闪烁 手里方块的子实体
 #[derive(Component)]
-pub struct AnimationTimer(pub Timer);
+struct LightningSparkle;
 
 pub struct AnimationPlugin;
 
 impl Plugin for AnimationPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (animate_timer_tick, player_animation).run_if(in_state(GameState::InGame)),
-        )
-        .add_systems(
-            Update,
-            (high_score_animation).run_if(in_state(GameState::InGame)),
-        );
+        app.init_asset::<SpriteClips>()
+            .init_asset_loader::<SpriteClipsLoader>()
+            .init_resource::<SpriteClipsHandle>()
+            .add_event::<AnimationFinished>()
+            .add_systems(Startup, load_sprite_clips)
+            .add_systems(
+                Update,
+                (
+                    (
+                        player_animation,
+                        spawn_removal_pop,
+                        update_lightning_sparkle,
+                    ),
+                    animate_sprites,
+                    finish_player_animation,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::InGame)),
+            );
     }
 }
 
-// 动画计时器
-fn animate_timer_tick(
+fn load_sprite_clips(mut handle: ResMut<SpriteClipsHandle>, asset_server: Res<AssetServer>) {
+    handle.0 = asset_server.load(SPRITE_CLIPS_PATH);
+}
+
+// 推进精灵动画 清单未加载或片段不存在时保持当前帧
+fn animate_sprites(
     time: Res<Time>,
-    mut query: Query<&mut AnimationTimer, With<AnimationTimer>>,
+    mut commands: Commands,
+    handle: Res<SpriteClipsHandle>,
+    clips: Res<Assets<SpriteClips>>,
+    mut finished_events: EventWriter<AnimationFinished>,
+    mut query: Query<(Entity, &mut SpriteAnimation, &mut TextureAtlas)>,
 ) {
-    for mut timer in query.iter_mut() {
-        timer.0.tick(time.delta());
+    let Some(clips) = clips.get(&handle.0) else {
+        return;
+    };
+
+    for (entity, mut animation, mut atlas) in query.iter_mut() {
+        if animation.finished {
+            continue;
+        }
+        let Some(clip) = clips.0.get(&animation.clip) else {
+            continue;
+        };
+
+        let duration = clip.frames.len() as f32 / clip.fps;
+        animation.elapsed += time.delta_seconds();
+        if animation.elapsed >= duration {
+            if clip.looping {
+                animation.elapsed %= duration;
+            } else {
+                animation.finished = true;
+                finished_events.send(AnimationFinished {
+                    entity,
+                    clip: animation.clip.clone(),
+                });
+                if animation.despawn {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+        }
+
+        let frame = ((animation.elapsed * clip.fps) as usize).min(clip.frames.len() - 1);
+        if atlas.index != clip.frames[frame] {
+            atlas.index = clip.frames[frame];
+        }
     }
 }
 
-// 玩家动画  变更上下或者抛出方块
+// 玩家动画 变更上下或者抛出方块时切换片段
+#[allow(clippy::type_complexity)]
 fn player_animation(
-    mut query: Query<(&mut TextureAtlas, &mut PlayerState, &mut AnimationTimer), With<Player>>,
+    mut query: Query<(&PlayerState, &mut SpriteAnimation), (With<Player>, Changed<PlayerState>)>,
 ) {
-    if query.is_empty() {
-        return;
+    for (player_state, mut animation) in query.iter_mut() {
+        animation.play(match player_state {
+            PlayerState::Idle => "player.idle",
+            PlayerState::Moving => "player.move",
+            PlayerState::Throwing => "player.throw",
+        });
     }
+}
 
-    let (mut atlas, mut player_state, mut timer) = query.single_mut();
-
-    match *player_state {
-        PlayerState::Idle => atlas.index = 0,
-        PlayerState::Moving => atlas.index = 1,
-        PlayerState::Throwing => atlas.index = 2,
-    };
-
-    if timer.0.finished() {
-        timer.0.reset();
-        match *player_state {
-            PlayerState::Idle => {}
-            PlayerState::Moving => *player_state = PlayerState::Idle,
-            PlayerState::Throwing => *player_state = PlayerState::Idle,
+// 移动与投掷片段播放结束后回到空闲
+fn finish_player_animation(
+    mut events: EventReader<AnimationFinished>,
+    mut query: Query<&mut PlayerState, With<Player>>,
+) {
+    for event in events.read() {
+        if let Ok(mut player_state) = query.get_mut(event.entity) {
+            *player_state = PlayerState::Idle;
         }
     }
 }
 
-// 生成惊艳分数动画
-fn high_score_animation(
-    time: Res<Time>,
+// 消除时在方块位置弹出闪光 播放结束后销毁
+fn spawn_removal_pop(
     mut commands: Commands,
-    mut high_socre_query: Query<(&mut Transform, Entity, &mut HighScore), With<HighScore>>,
+    mut hit_events: EventReader<BlockHit>,
+    handle: Res<GlobalTextAtlas>,
+    block_query: Query<&Transform, With<Block>>,
 ) {
-    if high_socre_query.is_empty() {
-        return;
-    }
+    for event in hit_events.read() {
+        if event.result != HitResult::Remove {
+            continue;
+        }
+        let Ok(transform) = block_query.get(event.block) else {
+            continue;
+        };
 
-    let (mut transform, entity, mut high_score) = high_socre_query.single_mut();
+        let pop = commands
+            .spawn((
+                SpriteSheetBundle {
+                    texture: handle.image.clone().unwrap(),
+                    atlas: TextureAtlas {
+                        layout: handle.layout.clone().unwrap(),
+                        index: BLOCK_BEFORE_REMOVE_INDEX,
+                    },
+                    transform: Transform::from_translation(
+                        transform.translation.truncate().extend(1.0),
+                    )
+                    .with_scale(transform.scale),
+                    ..default()
+                },
+                SpriteAnimation::new("block.pop").despawn_on_finish(),
+                GameEntity,
+            ))
+            .id();
+        commands.entity(event.board).add_child(pop);
+    }
+}
 
-    high_score.animation_timer.tick(time.delta());
+// 手里方块为闪电块时显示闪烁
+#[allow(clippy::type_complexity)]
+fn update_lightning_sparkle(
+    mut commands: Commands,
+    handle: Res<GlobalTextAtlas>,
+    hand_block_query: Query<
+        (Entity, &TextureAtlas, Option<&Children>),
+        (With<HandBlock>, Changed<TextureAtlas>),
+    >,
+    sparkle_query: Query<Entity, With<LightningSparkle>>,
+) {
+    for (hand_block, atlas, children) in hand_block_query.iter() {
+        let sparkle = children
+            .into_iter()
+            .flatten()
+            .find(|child| sparkle_query.contains(**child));
 
-    // 动画结束时销毁
-    if high_score.animation_timer.just_finished() {
-        commands.entity(entity).despawn_recursive();
+        match (atlas.index == LIGHT_BLOCK_INDEX, sparkle) {
+            (true, None) => {
+                commands.entity(hand_block).with_children(|parent| {
+                    parent.spawn((
+                        SpriteSheetBundle {
+                            texture: handle.image.clone().unwrap(),
+                            atlas: TextureAtlas {
+                                layout: handle.layout.clone().unwrap(),
+                                index: BLOCK_BEFORE_REMOVE_INDEX,
+                            },
+                            transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                            ..default()
+                        },
+                        SpriteAnimation::new("lightning.sparkle"),
+                        LightningSparkle,
+                    ));
+                });
+            }
+            (false, Some(sparkle)) => commands.entity(*sparkle).despawn_recursive(),
+            _ => {}
+        }
     }
-    transform.translation.y += HIGH_SCORE_ANIMATION_SPEED * time.delta_seconds();
 }
@@ -3,15 +3,15 @@ use std::f32::consts::PI;
 use bevy::math::vec3;
 use bevy::prelude::*;
 
-use crate::player::Player;
-use crate::*;
-use crate::{resources::GlobalTextAtlas, state::GameState};
-
+use crate::animation::SpriteAnimation;
 use crate::block::Block;
-use crate::state::HandBlockState;
-use crate::state::SettlementState;
+use crate::board::{Board, InBoard};
+use crate::player::Player;
+use crate::resources::GlobalTextAtlas;
+use crate::state::{BoardState, BoardTransition, GameState, HandBlockState, SettlementState};
 use crate::wall::Wall;
 use crate::world::GameEntity;
+use crate::*;
 
 pub struct ArrowPlugin;
 
@@ -23,72 +23,90 @@ impl Plugin for ArrowPlugin {
         app.add_systems(OnEnter(GameState::InGame), spawn_arrow)
             .add_systems(
                 Update,
-                update_arrow
-                    .run_if(in_state(HandBlockState::Idle))
-                    .run_if(in_state(GameState::InGame)),
-            )
-            .add_systems(
-                OnEnter(HandBlockState::Idle),
-                handle_arrow_show
-                    .run_if(in_state(GameState::InGame))
-                    .run_if(in_state(SettlementState::Not)),
-            )
-            .add_systems(
-                OnEnter(HandBlockState::Moving),
-                handle_arrow_hide.run_if(in_state(GameState::InGame)),
-            )
-            .add_systems(OnEnter(SettlementState::Start), handle_arrow_hide);
+                (update_arrow, handle_arrow_visibility).run_if(in_state(GameState::InGame)),
+            );
     }
 }
 
-// 生成箭头
+// 每个棋盘生成一个箭头
 fn spawn_arrow(
     mut commands: Commands,
     handle: ResMut<GlobalTextAtlas>,
-    player_query: Query<&Transform, With<Player>>,
+    board_query: Query<Entity, With<Board>>,
 ) {
-    // 生成箭头
     let (x, y) = PLAYER_INIT_POS;
 
-    if player_query.is_empty() {
-        return;
+    for board in board_query.iter() {
+        commands.entity(board).with_children(|parent| {
+            parent.spawn((
+                SpriteSheetBundle {
+                    texture: handle.image.clone().unwrap(),
+                    atlas: TextureAtlas {
+                        layout: handle.layout.clone().unwrap(),
+                        index: ARROW_TEXTATLAS_INDEX,
+                    },
+                    transform: Transform::from_translation(vec3(x, y, 1.0))
+                        .with_scale(Vec3::splat(SPRITE_SCALE_FACTOR)),
+                    ..default()
+                },
+                SpriteAnimation::new("arrow.pulse"),
+                Arrow,
+                InBoard(board),
+                GameEntity,
+            ));
+        });
     }
-
-    commands.spawn((
-        SpriteSheetBundle {
-            texture: handle.image.clone().unwrap(),
-            atlas: TextureAtlas {
-                layout: handle.layout.clone().unwrap(),
-                index: ARROW_TEXTATLAS_INDEX,
-            },
-            transform: Transform::from_translation(vec3(x, y, 1.0))
-                .with_scale(Vec3::splat(SPRITE_SCALE_FACTOR)),
-            ..default()
-        },
-        Arrow,
-        GameEntity,
-    ));
 }
 
 // 更新箭头 变换箭头在方块上的指向
+#[allow(clippy::type_complexity)]
 fn update_arrow(
-    player_query: Query<&Transform, With<Player>>,
-    block_query: Query<&Transform, (With<Block>, Without<Player>)>,
-    wall_query: Query<&Transform, (With<Wall>, Without<Player>, Without<Block>)>,
+    board_query: Query<&BoardState<HandBlockState>>,
+    player_query: Query<(&Transform, &InBoard), With<Player>>,
+    block_query: Query<(&Transform, &InBoard), (With<Block>, Without<Player>)>,
+    wall_query: Query<(&Transform, &InBoard), (With<Wall>, Without<Player>, Without<Block>)>,
     mut arrow_query: Query<
-        (&mut Transform, &mut Visibility),
+        (&mut Transform, &mut Visibility, &InBoard),
         (With<Arrow>, Without<Player>, Without<Block>, Without<Wall>),
     >,
 ) {
-    if player_query.is_empty() || arrow_query.is_empty() || wall_query.is_empty() {
-        return;
-    }
-    let (mut arrow_transform, mut arrow_visibility) = arrow_query.single_mut();
+    for (mut arrow_transform, mut arrow_visibility, in_board) in arrow_query.iter_mut() {
+        if !board_query
+            .get(in_board.0)
+            .is_ok_and(|state| state.is(HandBlockState::Idle))
+        {
+            continue;
+        }
+
+        let Some((player_transform, _)) = player_query.iter().find(|(_, b)| *b == in_board) else {
+            continue;
+        };
+        let blocks = block_query
+            .iter()
+            .filter(|(_, b)| *b == in_board)
+            .map(|(t, _)| t)
+            .collect::<Vec<_>>();
+        let walls = wall_query
+            .iter()
+            .filter(|(_, b)| *b == in_board)
+            .map(|(t, _)| t)
+            .collect::<Vec<_>>();
+
+        if blocks.is_empty() {
+            *arrow_visibility = Visibility::Hidden;
+        }
 
-    if block_query.is_empty() {
-        *arrow_visibility = Visibility::Hidden;
+        update_arrow_transform(&mut arrow_transform, player_transform, &blocks, &walls);
     }
-    let player_transform = player_query.single();
+}
+
+// 箭头指向 同行最右侧方块 或左侧墙体所在列最上方方块
+fn update_arrow_transform(
+    arrow_transform: &mut Transform,
+    player_transform: &Transform,
+    blocks: &[&Transform],
+    walls: &[&Transform],
+) {
     // 基于y
     let base_y = player_transform.translation.y;
     // 基于block
@@ -98,7 +116,7 @@ fn update_arrow(
     let mut target_block = Vec2::NEG_INFINITY;
 
     // 方块位置
-    for block_transform in block_query.iter() {
+    for block_transform in blocks {
         let block_y = block_transform.translation.y;
         if base_y == block_y {
             target_block.y = block_y;
@@ -112,7 +130,7 @@ fn update_arrow(
 
     if !base_block {
         // 墙体位置
-        for wall_transform in wall_query.iter() {
+        for wall_transform in walls {
             let wall_y = wall_transform.translation.y;
             let wall_x = wall_transform.translation.x;
             // wall_x < 0.0 表示墙体在左侧
@@ -126,7 +144,7 @@ fn update_arrow(
 
                 let base_x = wall_x + STEP_SIZE as f32;
                 target_block.y = Vec2::NEG_INFINITY.y;
-                for block_transform in block_query.iter() {
+                for block_transform in blocks {
                     let block_x = block_transform.translation.x;
 
                     if block_x == base_x {
@@ -145,24 +163,37 @@ fn update_arrow(
     }
 }
 
-// 隐藏箭头
-fn handle_arrow_hide(mut arrow_query: Query<&mut Visibility, (With<Arrow>, Without<Player>)>) {
-    if arrow_query.is_empty() {
-        return;
-    }
-
-    let mut visibility = arrow_query.single_mut();
+// 显示或隐藏箭头 手里方块回到手中时显示 抛出或进入结算时隐藏
+fn handle_arrow_visibility(
+    mut hand_events: EventReader<BoardTransition<HandBlockState>>,
+    mut settlement_events: EventReader<BoardTransition<SettlementState>>,
+    board_query: Query<&BoardState<SettlementState>>,
+    mut arrow_query: Query<(&mut Visibility, &InBoard), With<Arrow>>,
+) {
+    let mut set_visibility = |board: Entity, visibility: Visibility| {
+        for (mut arrow_visibility, in_board) in arrow_query.iter_mut() {
+            if in_board.0 == board {
+                *arrow_visibility = visibility;
+            }
+        }
+    };
 
-    *visibility = Visibility::Hidden;
-}
+    for event in hand_events.read() {
+        let settling = !board_query
+            .get(event.board)
+            .is_ok_and(|state| state.is(SettlementState::Not));
 
-// 显示箭头
-fn handle_arrow_show(mut arrow_query: Query<&mut Visibility, (With<Arrow>, Without<Player>)>) {
-    if arrow_query.is_empty() {
-        return;
+        if event.entered(HandBlockState::Idle) && !settling {
+            set_visibility(event.board, Visibility::Visible);
+        } else if event.entered(HandBlockState::Moving) {
+            set_visibility(event.board, Visibility::Hidden);
+        }
     }
 
-    let mut visibility = arrow_query.single_mut();
-
-    *visibility = Visibility::Visible;
+    for event in settlement_events
+        .read()
+        .filter(|e| e.entered(SettlementState::Start))
+    {
+        set_visibility(event.board, Visibility::Hidden);
+    }
 }
@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+
+use crate::state::{GameMode, GameState};
+use crate::*;
+
+// 演示模式 主菜单闲置后由AI自动游戏 棋盘操作者均为 AI
+#[derive(Resource, Default)]
+pub struct AttractMode(pub bool);
+
+// 主菜单闲置计时
+#[derive(Resource)]
+struct MenuIdleTimer(Timer);
+impl Default for MenuIdleTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(ATTRACT_IDLE_SEC, TimerMode::Once))
+    }
+}
+
+pub struct AttractPlugin;
+
+impl Plugin for AttractPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AttractMode>()
+            .init_resource::<MenuIdleTimer>()
+            .add_systems(OnEnter(GameState::MainMenu), reset_menu_idle_timer)
+            .add_systems(
+                Update,
+                start_attract_mode.run_if(in_state(GameState::MainMenu)),
+            )
+            .add_systems(
+                Update,
+                exit_attract_mode_on_input
+                    .run_if(is_attract_mode)
+                    .run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+// 是否处于演示模式
+pub fn is_attract_mode(attract_mode: Res<AttractMode>) -> bool {
+    attract_mode.0
+}
+
+// 是否有任意按键输入
+fn has_any_input(
+    keyboard_input: &ButtonInput<KeyCode>,
+    mouse_input: &ButtonInput<MouseButton>,
+) -> bool {
+    keyboard_input.get_just_pressed().next().is_some()
+        || mouse_input.get_just_pressed().next().is_some()
+}
+
+fn reset_menu_idle_timer(mut timer: ResMut<MenuIdleTimer>, mut attract_mode: ResMut<AttractMode>) {
+    timer.0.reset();
+    attract_mode.0 = false;
+}
+
+// 主菜单闲置一段时间后开始演示
+#[allow(clippy::too_many_arguments)]
+fn start_attract_mode(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut cursor_events: EventReader<CursorMoved>,
+    mut timer: ResMut<MenuIdleTimer>,
+    mut attract_mode: ResMut<AttractMode>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let cursor_moved = cursor_events.read().count() > 0;
+
+    if has_any_input(&keyboard_input, &mouse_input) || cursor_moved {
+        timer.0.reset();
+        return;
+    }
+
+    if timer.0.tick(time.delta()).just_finished() {
+        attract_mode.0 = true;
+        *mode = GameMode::Single;
+        next_state.set(GameState::GameInit);
+    }
+}
+
+// 演示中任意输入返回主菜单
+fn exit_attract_mode_on_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut attract_mode: ResMut<AttractMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if has_any_input(&keyboard_input, &mouse_input) {
+        attract_mode.0 = false;
+        next_state.set(GameState::MainMenu);
+    }
+}
@@ -0,0 +1,244 @@
+use bevy::audio::{PlaybackMode, Volume};
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy::window::WindowFocused;
+
+use crate::state::{BoardState, GameState, SettlementState};
+use crate::storage::{format_key_values, parse_key_values, read_data, write_data};
+use crate::*;
+
+// 音频设置存档文件
+const AUDIO_FILE: &str = "audio.txt";
+
+// 音频总线 音乐与音效分别调节音量
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioBus {
+    Music,
+    Sfx,
+}
+
+// 音量设置 跨局保存
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct AudioSettings {
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    // 总线实际音量 静音时为 0
+    pub fn volume(&self, bus: AudioBus) -> f32 {
+        if self.muted {
+            return 0.0;
+        }
+
+        match bus {
+            AudioBus::Music => self.music_volume,
+            AudioBus::Sfx => self.sfx_volume,
+        }
+    }
+
+    pub fn parse(source: &str) -> Self {
+        let mut settings = Self::default();
+
+        for (key, value) in parse_key_values(source) {
+            match key.as_str() {
+                "music_volume" => {
+                    if let Ok(volume) = value.parse::<f32>() {
+                        settings.music_volume = volume.clamp(0.0, 1.0);
+                    }
+                }
+                "sfx_volume" => {
+                    if let Ok(volume) = value.parse::<f32>() {
+                        settings.sfx_volume = volume.clamp(0.0, 1.0);
+                    }
+                }
+                "muted" => settings.muted = value == "true",
+                _ => {}
+            }
+        }
+
+        settings
+    }
+
+    pub fn to_source(&self) -> String {
+        format_key_values(
+            "Smart Block audio",
+            vec![
+                (
+                    String::from("music_volume"),
+                    format!("{:.2}", self.music_volume),
+                ),
+                (
+                    String::from("sfx_volume"),
+                    format!("{:.2}", self.sfx_volume),
+                ),
+                (String::from("muted"), self.muted.to_string()),
+            ],
+        )
+    }
+}
+
+// 下一档音量 最大后回到 0
+pub fn next_volume(volume: f32) -> f32 {
+    let steps = (1.0 / AUDIO_VOLUME_STEP).round() as u32;
+    let step = (volume / AUDIO_VOLUME_STEP).round() as u32;
+    ((step + 1) % (steps + 1)) as f32 * AUDIO_VOLUME_STEP
+}
+
+// 混音状态 窗口失去焦点视为暂停 结算时压低音乐
+#[derive(Resource, Debug, Default)]
+pub struct AudioMix {
+    pub paused: bool,
+    pub ducked: bool,
+}
+
+impl AudioMix {
+    // 背景音乐音量比例
+    pub fn music_scale(&self) -> f32 {
+        if self.ducked {
+            AUDIO_DUCK_VOLUME
+        } else {
+            1.0
+        }
+    }
+}
+
+// 播放一次性音效 speed 同时改变音高
+#[derive(Event, Debug, Clone)]
+pub struct PlaySfx {
+    pub source: Option<Handle<AudioSource>>,
+    pub speed: f32,
+}
+
+impl PlaySfx {
+    pub fn new(source: &Option<Handle<AudioSource>>) -> Self {
+        Self {
+            source: source.clone(),
+            speed: 1.0,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+pub struct AudioManagerPlugin;
+
+impl Plugin for AudioManagerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(
+            read_data(AUDIO_FILE)
+                .map(|source| AudioSettings::parse(&source))
+                .unwrap_or_default(),
+        )
+        .init_resource::<AudioMix>()
+        .add_event::<PlaySfx>()
+        .add_systems(
+            Update,
+            (
+                toggle_mute,
+                track_window_focus,
+                update_duck,
+                play_sfx,
+                save_audio_settings.run_if(resource_changed::<AudioSettings>),
+            )
+                .chain(),
+        );
+    }
+}
+
+// 快捷键 F7 静音
+fn toggle_mute(keyboard_input: Res<ButtonInput<KeyCode>>, mut settings: ResMut<AudioSettings>) {
+    if keyboard_input.just_pressed(KeyCode::F7) {
+        settings.muted = !settings.muted;
+    }
+}
+
+fn track_window_focus(mut events: EventReader<WindowFocused>, mut mix: ResMut<AudioMix>) {
+    if let Some(event) = events.read().last() {
+        mix.paused = !event.focused;
+    }
+}
+
+// 任一棋盘在结算时压低音乐
+fn update_duck(
+    mut mix: ResMut<AudioMix>,
+    state: Res<State<GameState>>,
+    board_query: Query<&BoardState<SettlementState>>,
+) {
+    let ducked = *state.get() == GameState::InGame
+        && board_query
+            .iter()
+            .any(|settlement| !settlement.is(SettlementState::Not));
+    if mix.ducked != ducked {
+        mix.ducked = ducked;
+    }
+}
+
+// 播放音效 同一帧相同素材与音高只播放一次 超过同时播放上限时丢弃
+// 播放结束后实体自动销毁
+fn play_sfx(
+    mut commands: Commands,
+    mut events: EventReader<PlaySfx>,
+    settings: Res<AudioSettings>,
+    mix: Res<AudioMix>,
+    voice_query: Query<&AudioBus>,
+) {
+    let volume = settings.volume(AudioBus::Sfx);
+    if mix.paused || volume <= 0.0 {
+        events.clear();
+        return;
+    }
+
+    let mut voices = voice_query
+        .iter()
+        .filter(|bus| **bus == AudioBus::Sfx)
+        .count();
+    let mut played = HashSet::new();
+
+    for event in events.read() {
+        let Some(source) = event.source.clone() else {
+            continue;
+        };
+        if voices >= AUDIO_MAX_VOICES || !played.insert((source.id(), event.speed.to_bits())) {
+            continue;
+        }
+
+        commands.spawn((
+            AudioBundle {
+                source,
+                settings: PlaybackSettings {
+                    mode: PlaybackMode::Despawn,
+                    volume: Volume::new(volume),
+                    speed: event.speed,
+                    ..default()
+                },
+            },
+            AudioBus::Sfx,
+        ));
+        voices += 1;
+    }
+}
+
+fn save_audio_settings(settings: Res<AudioSettings>) {
+    if settings.is_added() {
+        return;
+    }
+
+    if let Err(e) = write_data(AUDIO_FILE, &settings.to_source()) {
+        warn!("failed to save audio settings: {}", e);
+    }
+}
@@ -0,0 +1,176 @@
+// 关卡平衡模拟器 无窗口批量生成关卡并由机器人游玩 输出 CSV 统计
+// 用法: smart-block-sim [--stages N] [--seed S] [--max-throws N] [--out FILE]
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+
+use bevy_smart_block::rules::{generate_stage, removal_score, Grid, LADDER_FIRST_ROW};
+use bevy_smart_block::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+struct Options {
+    stages: u64,
+    seed: u64,
+    max_throws: u32,
+    out: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            stages: 1000,
+            seed: 0,
+            max_throws: 200,
+            out: None,
+        }
+    }
+}
+
+// 单个关卡的模拟结果
+struct StageResult {
+    seed: u64,
+    cleared: bool,
+    throws: u32,
+    rescues: u32,
+    remaining: usize,
+    score: u32,
+}
+
+impl StageResult {
+    // 不使用闪电块补给即可通关
+    fn solved(&self) -> bool {
+        self.cleared && self.rescues == 0
+    }
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--stages" => options.stages = value()?.parse().map_err(|e| format!("{}", e))?,
+            "--seed" => options.seed = value()?.parse().map_err(|e| format!("{}", e))?,
+            "--max-throws" => {
+                options.max_throws = value()?.parse().map_err(|e| format!("{}", e))?
+            }
+            "--out" => options.out = Some(value()?),
+            "-h" | "--help" => {
+                return Err(String::from(
+                    "usage: smart-block-sim [--stages N] [--seed S] [--max-throws N] [--out FILE]",
+                ))
+            }
+            _ => return Err(format!("unknown argument: {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+// 按游戏流程模拟 初始手里为闪电块 无可消除方块且未通关时补给闪电块
+fn simulate(seed: u64, max_throws: u32) -> StageResult {
+    let group = generate_stage(&mut StdRng::seed_from_u64(seed));
+    let mut board = Grid::from_block_group(&group);
+
+    let mut hand = HAND_BLOCK_INDEX;
+    let mut row = LADDER_FIRST_ROW;
+    let mut result = StageResult {
+        seed,
+        cleared: false,
+        throws: 0,
+        rescues: 0,
+        remaining: board.blocks.len(),
+        score: 0,
+    };
+
+    while result.throws < max_throws {
+        let Some(target_row) = board.bot_row(hand, row) else {
+            break;
+        };
+
+        let outcome = board.resolve_throw(target_row, hand);
+        board.apply_throw(&outcome);
+
+        result.throws += 1;
+        result.score += removal_score(outcome.removed.len() as u32);
+        hand = outcome.hand;
+        row = target_row;
+
+        if !board.can_remove(hand) {
+            if board.is_cleared() {
+                result.cleared = true;
+                break;
+            }
+            result.rescues += 1;
+            hand = LIGHT_BLOCK_INDEX;
+        }
+    }
+
+    result.cleared |= board.blocks.is_empty();
+    result.remaining = board.blocks.len();
+    result
+}
+
+fn write_csv(out: &mut impl Write, results: &[StageResult]) -> io::Result<()> {
+    writeln!(out, "seed,cleared,solved,throws,rescues,remaining,score")?;
+    for r in results {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            r.seed,
+            r.cleared as u8,
+            r.solved() as u8,
+            r.throws,
+            r.rescues,
+            r.remaining,
+            r.score
+        )?;
+    }
+    out.flush()
+}
+
+fn print_summary(results: &[StageResult]) {
+    let n = results.len().max(1) as f64;
+    let rate =
+        |f: &dyn Fn(&StageResult) -> bool| results.iter().filter(|r| f(r)).count() as f64 / n;
+    let mean = |f: &dyn Fn(&StageResult) -> f64| results.iter().map(f).sum::<f64>() / n;
+
+    eprintln!("stages:          {}", results.len());
+    eprintln!("solvable rate:   {:.3}", rate(&|r| r.solved()));
+    eprintln!("cleared rate:    {:.3}", rate(&|r| r.cleared));
+    eprintln!("avg throws:      {:.2}", mean(&|r| r.throws as f64));
+    eprintln!("avg rescues:     {:.2}", mean(&|r| r.rescues as f64));
+    eprintln!("avg remaining:   {:.2}", mean(&|r| r.remaining as f64));
+    eprintln!("expected score:  {:.1}", mean(&|r| r.score as f64));
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let results = (0..options.stages)
+        .map(|i| simulate(options.seed.wrapping_add(i), options.max_throws))
+        .collect::<Vec<_>>();
+
+    let written = match &options.out {
+        Some(path) => {
+            File::create(path).and_then(|file| write_csv(&mut BufWriter::new(file), &results))
+        }
+        None => write_csv(&mut io::stdout().lock(), &results),
+    };
+    if let Err(e) = written {
+        eprintln!("failed to write csv: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    print_summary(&results);
+    ExitCode::SUCCESS
+}
@@ -1,12 +1,17 @@
 use bevy::math::vec3;
 use bevy::prelude::*;
-use std::collections::HashSet;
 
-use crate::gui::{ClearNum, Score};
-use crate::player::{Ladder, Player};
-use crate::resources::GlobalAudio;
-use crate::state::{BlockGroupState, GameState, HandBlockState, SettlementState};
-use crate::wall::Wall;
+use crate::board::InBoard;
+use crate::events::{BlocksRemoved, LightningRescued, PlayerStuck, StageCleared};
+use crate::player::Player;
+use crate::resources::GlobalTextAtlas;
+use crate::rules::{fall_distance, pos_to_cell, Grid};
+use crate::state::{
+    BlockGroupState, BoardState, BoardTransition, GameMode, GameState, HandBlockState,
+    SettlementState,
+};
+use crate::tween::{Ease, Tween, TweenTarget};
+use crate::world::GameEntity;
 use crate::*;
 
 use self::alert::AlertEvent;
@@ -18,6 +23,74 @@ pub struct Block {
     pub pos: Vec2,
 }
 
+// 方块种类 对应精灵图索引
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockKind {
+    Circle,
+    Triangle,
+    Star,
+    Square,
+    Umbrella,
+    Cross,
+    Lightning,
+}
+
+impl BlockKind {
+    pub const ALL: [BlockKind; 7] = [
+        BlockKind::Circle,
+        BlockKind::Triangle,
+        BlockKind::Star,
+        BlockKind::Square,
+        BlockKind::Umbrella,
+        BlockKind::Cross,
+        BlockKind::Lightning,
+    ];
+
+    pub fn from_atlas_index(index: usize) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.atlas_index() == index)
+    }
+
+    pub fn atlas_index(&self) -> usize {
+        match self {
+            BlockKind::Circle => 8,
+            BlockKind::Triangle => 9,
+            BlockKind::Star => 10,
+            BlockKind::Square => 11,
+            BlockKind::Umbrella => 12,
+            BlockKind::Cross => 13,
+            BlockKind::Lightning => LIGHT_BLOCK_INDEX,
+        }
+    }
+
+    // 存档键名
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlockKind::Circle => "circle",
+            BlockKind::Triangle => "triangle",
+            BlockKind::Star => "star",
+            BlockKind::Square => "square",
+            BlockKind::Umbrella => "umbrella",
+            BlockKind::Cross => "cross",
+            BlockKind::Lightning => "lightning",
+        }
+    }
+
+    // 辅助标识 与精灵形状相近的字符
+    pub fn letter(&self) -> &'static str {
+        match self {
+            BlockKind::Circle => "O",
+            BlockKind::Triangle => "^",
+            BlockKind::Star => "*",
+            BlockKind::Square => "#",
+            BlockKind::Umbrella => "U",
+            BlockKind::Cross => "X",
+            BlockKind::Lightning => "!",
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct HandBlock {
     pub index: usize,
@@ -51,366 +124,342 @@ pub struct RemoveBlock {
     pub pos: Vec2,
 }
 
-#[derive(Resource)]
-pub struct RemoveBlockResource {
+// 本次消除的方块 每个棋盘一份
+#[derive(Component, Default)]
+pub struct RemoveBlocks {
     blocks: Option<Vec<RemoveBlock>>,
-    fall_down_timer: Timer,
-}
-impl Default for RemoveBlockResource {
-    fn default() -> Self {
-        Self {
-            blocks: None,
-            fall_down_timer: Timer::from_seconds(FALL_DOWN_TIMER, TimerMode::Once),
-        }
-    }
 }
-#[derive(Event)]
-struct NoRemoveEvent;
 
 pub struct BlockPlugin;
 
 impl Plugin for BlockPlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<HandBlockState>()
-            .init_state::<BlockGroupState>()
-            .init_resource::<RemoveBlockResource>()
-            .add_event::<NoRemoveEvent>()
-            .add_systems(
-                Update,
-                (
-                    handle_hand_block_move.run_if(in_state(HandBlockState::Moving)),
-                    handle_block_movement.run_if(in_state(HandBlockState::Idle)),
-                )
-                    .run_if(in_state(GameState::InGame)),
-            )
-            .add_systems(
-                OnExit(HandBlockState::Moving),
+        app.add_systems(
+            Update,
+            (
+                handle_hand_block_move,
+                handle_block_movement,
                 (handle_block_remove, handle_reset_hand_block),
+                handle_block_fall_down,
+                handle_no_remove_block_by_player,
+                (handle_stage_cleared, handle_player_stuck).after(handle_no_remove_block_by_player),
             )
-            .add_systems(
-                Update,
-                handle_block_fall_down.run_if(in_state(BlockGroupState::FallDown)),
-            )
-            .add_systems(OnExit(BlockGroupState::FallDown), block_fall_down_sound)
-            .add_systems(Update, handle_game_over.run_if(in_state(GameState::InGame)))
-            .add_systems(
-                OnExit(HandBlockState::Backing),
-                handle_no_remove_block_by_player.run_if(in_state(GameState::InGame)),
-            );
+                .run_if(in_state(GameState::InGame)),
+        );
     }
 }
 
-// 处理方块消除
+// 方块实体 坐标相对所属棋盘
+pub fn block_bundle(
+    handle: &GlobalTextAtlas,
+    board: Entity,
+    pos: Vec2,
+    index: usize,
+) -> impl Bundle {
+    (
+        SpriteSheetBundle {
+            texture: handle.image.clone().unwrap(),
+            atlas: TextureAtlas {
+                layout: handle.layout.clone().unwrap(),
+                index,
+            },
+            transform: Transform::from_translation(pos.extend(0.0))
+                .with_scale(Vec3::splat(SPRITE_SCALE_FACTOR)),
+            ..default()
+        },
+        Block { show: true, pos },
+        GameEntity,
+        InBoard(board),
+    )
+}
+
+// 处理方块消除 手里方块停止移动时
 fn handle_block_remove(
     mut commands: Commands,
-    audio_handles: Res<GlobalAudio>,
-    mut score: ResMut<Score>,
-    mut remove_block_resource: ResMut<RemoveBlockResource>,
-    mut query: Query<(&mut Transform, &mut Block, Entity), With<Block>>,
-    hand_block_query: Query<&Transform, (With<HandBlock>, Without<Block>)>,
-    mut next_state: ResMut<NextState<BlockGroupState>>,
+    mut events: EventReader<BoardTransition<HandBlockState>>,
+    mut removed_events: EventWriter<BlocksRemoved>,
+    mut board_query: Query<(&mut RemoveBlocks, &mut BoardState<BlockGroupState>)>,
+    query: Query<(&Transform, &Block, &TextureAtlas, &InBoard, Entity)>,
 ) {
-    if query.is_empty() || hand_block_query.is_empty() {
-        return;
-    }
-
-    // 消除块数
-    let mut remove_blocks = vec![];
+    for event in events.read().filter(|e| e.exited(HandBlockState::Moving)) {
+        let Ok((mut remove_blocks_state, mut group_state)) = board_query.get_mut(event.board)
+        else {
+            continue;
+        };
+
+        // 消除块数
+        let mut remove_blocks = vec![];
+        let mut kinds = vec![];
+
+        // 消除方块
+        for (transform, block, atlas, in_board, entity) in query.iter() {
+            if in_board.0 != event.board || block.show {
+                continue;
+            }
 
-    // 消除方块
-    for (transform, block, entity) in query.iter_mut() {
-        if !block.show {
-            commands.entity(entity).despawn();
+            commands.entity(entity).despawn_recursive();
 
             remove_blocks.push(RemoveBlock {
                 pos: transform.translation.truncate(),
             });
+            kinds.extend(BlockKind::from_atlas_index(atlas.index));
 
-            // 生成消除声效
-            if let Some(hand_block_hit_block_sound) = audio_handles.hand_block_hit_block.clone() {
-                commands.spawn(AudioBundle {
-                    source: hand_block_hit_block_sound,
-                    ..default()
-                });
-            }
+            group_state.set(BlockGroupState::FallDown);
+        }
 
-            next_state.set(BlockGroupState::FallDown);
+        // 移除方块
+        if remove_blocks.is_empty() {
+            remove_blocks_state.blocks = None;
+        } else {
+            let center = remove_blocks
+                .iter()
+                .map(|remove_block| remove_block.pos)
+                .sum::<Vec2>()
+                / remove_blocks.len() as f32;
+            removed_events.send(BlocksRemoved {
+                board: event.board,
+                count: remove_blocks.len(),
+                kinds,
+                center,
+            });
+            remove_blocks_state.blocks = Some(remove_blocks);
         }
     }
-    // 分数计算
-    score.once_remove_block = remove_blocks.len() as u32;
-
-    // 移除方块resource
-    if remove_blocks.len() > 0 {
-        remove_block_resource.blocks = Some(remove_blocks);
-    } else {
-        remove_block_resource.blocks = None;
-    }
 }
 
 // 处理手里方块移动
 fn handle_hand_block_move(
     time: Res<Time>,
-    mut hand_block_query: Query<(&mut Transform, &HandBlock), With<HandBlock>>,
+    board_query: Query<&BoardState<HandBlockState>>,
+    mut hand_block_query: Query<(&mut Transform, &HandBlock, &InBoard)>,
 ) {
-    if hand_block_query.is_empty() {
-        return;
-    }
-
-    let (mut hand_block_transform, hand_block) = hand_block_query.single_mut();
-    match hand_block.direction {
-        Direction::Left => {
-            hand_block_transform.translation.x -= HAND_BLOCK_SPEED * time.delta_seconds();
+    for (mut hand_block_transform, hand_block, in_board) in hand_block_query.iter_mut() {
+        if !board_query
+            .get(in_board.0)
+            .is_ok_and(|state| state.is(HandBlockState::Moving))
+        {
+            continue;
         }
-        Direction::Down => {
-            hand_block_transform.translation.y -= HAND_BLOCK_SPEED * time.delta_seconds();
+
+        match hand_block.direction {
+            Direction::Left => {
+                hand_block_transform.translation.x -= HAND_BLOCK_SPEED * time.delta_seconds();
+            }
+            Direction::Down => {
+                hand_block_transform.translation.y -= HAND_BLOCK_SPEED * time.delta_seconds();
+            }
         }
     }
 }
 
 // 方块跟随玩家移动
+#[allow(clippy::type_complexity)]
 fn handle_block_movement(
-    player_query: Query<&Transform, With<Player>>,
-    mut hand_block_query: Query<&mut Transform, (With<HandBlock>, Without<Player>)>,
+    board_query: Query<&BoardState<HandBlockState>>,
+    player_query: Query<(&Transform, &InBoard), With<Player>>,
+    mut hand_block_query: Query<(&mut Transform, &InBoard), (With<HandBlock>, Without<Player>)>,
 ) {
-    if player_query.is_empty() || hand_block_query.is_empty() {
-        return;
-    }
-
-    let player_transform = player_query.single();
+    for (player_transform, in_board) in player_query.iter() {
+        if !board_query
+            .get(in_board.0)
+            .is_ok_and(|state| state.is(HandBlockState::Idle))
+        {
+            continue;
+        }
 
-    let mut hand_block_transform = hand_block_query.single_mut();
+        for (mut hand_block_transform, hand_in_board) in hand_block_query.iter_mut() {
+            if hand_in_board != in_board {
+                continue;
+            }
 
-    hand_block_transform.translation.y = player_transform.translation.y;
-    hand_block_transform.translation.x = player_transform.translation.x - STEP_SIZE as f32;
+            hand_block_transform.translation.y = player_transform.translation.y;
+            hand_block_transform.translation.x = player_transform.translation.x - STEP_SIZE as f32;
+        }
+    }
 }
 
 // 重置手里方块移动方向
-fn handle_reset_hand_block(mut query: Query<&mut HandBlock, With<HandBlock>>) {
-    if query.is_empty() {
-        return;
+fn handle_reset_hand_block(
+    mut events: EventReader<BoardTransition<HandBlockState>>,
+    mut query: Query<(&mut HandBlock, &InBoard)>,
+) {
+    for event in events.read().filter(|e| e.exited(HandBlockState::Moving)) {
+        for (mut hand_block, in_board) in query.iter_mut() {
+            if in_board.0 == event.board {
+                hand_block.direction = Direction::Left;
+            }
+        }
     }
-
-    let mut hand_block = query.single_mut();
-
-    hand_block.direction = Direction::Left;
 }
 
-// 下落动画
+// 下落动画 每个方块下落的格数由 rules::fall_distance 计算
+// 下落交给补间 所有方块落地后恢复静止
+#[allow(clippy::type_complexity)]
 fn handle_block_fall_down(
-    time: Res<Time>,
-    mut remove_block_resource: ResMut<RemoveBlockResource>,
-    mut next_state: ResMut<NextState<BlockGroupState>>,
-    mut query: Query<(&mut Transform, &mut Block), With<Block>>,
+    mut commands: Commands,
+    mut board_query: Query<(Entity, &mut RemoveBlocks, &mut BoardState<BlockGroupState>)>,
+    mut query: Query<(Entity, &Transform, &mut Block, &InBoard)>,
+    falling_query: Query<&InBoard, (With<Block>, With<Tween>)>,
 ) {
-    if remove_block_resource.blocks.is_none() {
-        return;
-    }
-    remove_block_resource.fall_down_timer.tick(time.delta());
-    let finished: bool = remove_block_resource.fall_down_timer.finished();
-
-    if let Some(remove_blocks) = remove_block_resource.blocks.as_mut() {
-        // 方块下落
-        // 1. 获取到所有下落的方块
-        // 2. 遍历所有方块，将所有下落的方块的上方方块下移
-        for remove_block in remove_blocks {
-            let pos = remove_block.pos;
-
-            for (mut transform, mut block) in query.iter_mut() {
-                if block.show
-                    && transform.translation.x == pos.x
-                    && transform.translation.y >= pos.y
-                {
-                    // 根据方块的y值判断是否需要下移
-                    if transform.translation.y == block.pos.y {
-                        // 将下移位置保存
-                        block.pos.y -= STEP_SIZE as f32;
-                    }
-
-                    transform.translation.y -=
-                        STEP_SIZE as f32 * time.delta_seconds() / FALL_DOWN_TIMER;
-
-                    if finished {
-                        // 下移完成，重置方块的y值
-                        transform.translation.y = block.pos.y;
-                    }
+    for (board, mut remove_blocks_state, mut group_state) in board_query.iter_mut() {
+        if !group_state.is(BlockGroupState::FallDown) {
+            continue;
+        }
+
+        // 计算下落目标位置 开始补间
+        if let Some(remove_blocks) = remove_blocks_state.blocks.take() {
+            let removed = remove_blocks
+                .iter()
+                .map(|remove_block| pos_to_cell(remove_block.pos))
+                .collect::<Vec<_>>();
+
+            for (entity, transform, mut block, in_board) in query.iter_mut() {
+                if in_board.0 != board || !block.show {
+                    continue;
+                }
+                let distance =
+                    fall_distance(pos_to_cell(transform.translation.truncate()), &removed);
+                if distance == 0 {
+                    continue;
                 }
+
+                block.pos.y = transform.translation.y - (distance * STEP_SIZE as i32) as f32;
+                let to = block.pos.extend(transform.translation.z);
+                commands
+                    .entity(entity)
+                    .insert(Tween::new(FALL_DOWN_TIMER, Ease::QuadIn).with(
+                        TweenTarget::Translation {
+                            from: transform.translation,
+                            to,
+                        },
+                    ));
             }
+            continue;
         }
-    }
 
-    // 下落完成
-    if finished {
-        remove_block_resource.fall_down_timer.reset();
-        remove_block_resource.blocks = None;
-        next_state.set(BlockGroupState::Static);
+        // 下落完成
+        if !falling_query.iter().any(|in_board| in_board.0 == board) {
+            group_state.set(BlockGroupState::Static);
+        }
     }
 }
 
-// 处理游戏结束
-fn handle_game_over(
-    mut no_remove_event: EventReader<NoRemoveEvent>,
-    mut alert_event: EventWriter<AlertEvent>,
-    mut hand_block_query: Query<(&mut Transform, &mut TextureAtlas), With<HandBlock>>,
-    mut player_query: Query<&mut Transform, (With<Player>, Without<HandBlock>)>,
-    block_query: Query<(), With<Block>>,
-    clear_query: Query<&Text, With<ClearNum>>,
-    mut next_state: ResMut<NextState<SettlementState>>,
+// 过关 开始结算
+fn handle_stage_cleared(
+    mut events: EventReader<StageCleared>,
+    mut board_query: Query<&mut BoardState<SettlementState>>,
 ) {
-    if block_query.is_empty()
-        || clear_query.is_empty()
-        || hand_block_query.is_empty()
-        || player_query.is_empty()
-    {
-        return;
-    }
-
-    let mut player_transform = player_query.single_mut();
-    let (mut hand_block_transform, mut hand_block_atlas) = hand_block_query.single_mut();
-
-    let block_number = block_query.iter().count();
-    let clear_number = clear_query.single().sections[0]
-        .value
-        .parse::<usize>()
-        .unwrap();
-
-    for _e in no_remove_event.read() {
-        // 如果方块数量小于等于消除数量，则获胜
-        if block_number <= clear_number {
-            println!("游戏胜利");
-
-            // 开始结算
-            next_state.set(SettlementState::Start);
-        } else {
-            alert_event.send(AlertEvent(Some(String::from(
-                "Can't Remove\nGive You A LightningBlock.",
-            ))));
-            let (player_x, player_y) = PLAYER_INIT_POS;
-            player_transform.translation = vec3(player_x, player_y, 1.0);
-            hand_block_transform.translation = vec3(player_x - STEP_SIZE as f32, player_y, 1.0);
-            hand_block_atlas.index = LIGHT_BLOCK_INDEX;
+    for event in events.read() {
+        let Ok(mut settlement_state) = board_query.get_mut(event.board) else {
+            continue;
+        };
+        if !settlement_state.is(SettlementState::Not) {
+            continue;
         }
+
+        debug!("stage clear");
+        settlement_state.set(SettlementState::Start);
     }
 }
 
-// 判断是否有方块可以消除
-fn handle_no_remove_block_by_player(
-    mut no_remove_event: EventWriter<NoRemoveEvent>,
-    ladder_query: Query<&Transform, With<Ladder>>,
-    block_query: Query<(&Transform, &Block, &TextureAtlas), With<Block>>,
-    wall_query: Query<&Transform, (With<Wall>, Without<Block>)>,
-    hand_block_query: Query<&TextureAtlas, With<HandBlock>>,
+// 无法消除 补给闪电块
+#[allow(clippy::type_complexity)]
+fn handle_player_stuck(
+    mode: Res<GameMode>,
+    mut events: EventReader<PlayerStuck>,
+    mut alert_event: EventWriter<AlertEvent>,
+    mut rescue_event: EventWriter<LightningRescued>,
+    mut hand_block_query: Query<(&mut Transform, &mut TextureAtlas, &InBoard), With<HandBlock>>,
+    mut player_query: Query<(&mut Transform, &InBoard), (With<Player>, Without<HandBlock>)>,
 ) {
-    if block_query.is_empty() || wall_query.is_empty() || ladder_query.is_empty() {
+    // 解谜不补给闪电块 由 puzzle 重试本关
+    if *mode == GameMode::Puzzle {
+        events.clear();
         return;
     }
 
-    let hand_block_atlas = hand_block_query.single();
-
-    let res_index = get_target_block_by_player_position(ladder_query, block_query, wall_query);
-
-    if res_index.contains(&hand_block_atlas.index) {
-        // 有可消除的方块
-    } else {
-        no_remove_event.send(NoRemoveEvent);
+    for PlayerStuck { board } in events.read() {
+        alert_event.send(AlertEvent {
+            board: *board,
+            key: Some(String::from("alert.lightning")),
+        });
+        rescue_event.send(LightningRescued { board: *board });
+        let (player_x, player_y) = PLAYER_INIT_POS;
+        for (mut player_transform, in_board) in player_query.iter_mut() {
+            if in_board.0 == *board {
+                player_transform.translation = vec3(player_x, player_y, 1.0);
+            }
+        }
+        for (mut hand_block_transform, mut hand_block_atlas, in_board) in
+            hand_block_query.iter_mut()
+        {
+            if in_board.0 == *board {
+                hand_block_transform.translation = vec3(player_x - STEP_SIZE as f32, player_y, 1.0);
+                hand_block_atlas.index = LIGHT_BLOCK_INDEX;
+            }
+        }
     }
 }
 
-// 根据玩家位置判断目标方块
-fn get_target_block_by_player_position(
-    ladder_query: Query<&Transform, With<Ladder>>,
-    block_query: Query<(&Transform, &Block, &TextureAtlas), With<Block>>,
-    wall_query: Query<&Transform, (With<Wall>, Without<Block>)>,
-) -> HashSet<usize> {
-    let mut res_hashset = HashSet::new();
-
-    for ladder_transform in ladder_query.iter() {
-        let ladder_position = ladder_transform.translation.truncate();
-        let mut res_index = None;
-
-        // 基于y
-        let base_y = ladder_position.y;
-        // 基于 block
-        let mut base_block = false;
-
-        // 方块坐标
-        let mut block_x = None;
-        let mut block_y = None;
-
-        // 方块位置
-        for (block_transform, _block, _) in block_query.iter() {
-            if base_y == block_transform.translation.y {
-                block_y = Some(block_transform.translation.y);
-
-                if let Some(x) = block_x {
-                    block_x = Some(block_transform.translation.x.max(x));
-                } else {
-                    block_x = Some(block_transform.translation.x);
-                }
-
-                base_block = true;
-            }
+// 手里方块返回后 判断是否有方块可以消除
+// 没有时剩余方块达到过关数则过关 否则卡住
+#[allow(clippy::type_complexity)]
+fn handle_no_remove_block_by_player(
+    mode: Res<GameMode>,
+    mut events: EventReader<BoardTransition<HandBlockState>>,
+    mut cleared_event: EventWriter<StageCleared>,
+    mut stuck_event: EventWriter<PlayerStuck>,
+    board_query: Query<&BoardState<SettlementState>>,
+    block_query: Query<(&Transform, &TextureAtlas, &InBoard), With<Block>>,
+    hand_block_query: Query<(&TextureAtlas, &InBoard), (With<HandBlock>, Without<Block>)>,
+) {
+    for event in events.read().filter(|e| e.exited(HandBlockState::Backing)) {
+        if !board_query
+            .get(event.board)
+            .is_ok_and(|state| state.is(SettlementState::Not))
+        {
+            continue;
         }
 
-        if !base_block {
-            let mut wall_x = None;
-            let mut _wall_y = None;
-            // 获取墙体位置
-            for wall_transform in wall_query.iter() {
-                // wall_transform.translation.x < 0.0 表示墙体在左侧
-                if base_y == wall_transform.translation.y && wall_transform.translation.x < 0.0 {
-                    _wall_y = Some(wall_transform.translation.y);
-
-                    if let Some(x) = wall_x {
-                        wall_x = Some(wall_transform.translation.x.max(x));
-                    } else {
-                        wall_x = Some(wall_transform.translation.x);
-                    }
-                }
-            }
+        let Some((hand_block_atlas, _)) = hand_block_query
+            .iter()
+            .find(|(_, in_board)| in_board.0 == event.board)
+        else {
+            continue;
+        };
 
-            // 基于x
-            let base_x = wall_x.map(|x| x + STEP_SIZE as f32).unwrap_or(0.0);
+        if grid_from_blocks(event.board, &block_query).can_remove(hand_block_atlas.index) {
+            continue;
+        }
 
-            // 遍历所有方块，获取最外层方块的坐标
-            for (block_transform, _, _) in block_query.iter() {
-                if base_x == block_transform.translation.x {
-                    block_x = Some(block_transform.translation.x);
+        let block_number = block_query
+            .iter()
+            .filter(|(_, _, in_board)| in_board.0 == event.board)
+            .count();
 
-                    if let Some(y) = block_y {
-                        block_y = Some(block_transform.translation.y.max(y));
-                    } else {
-                        block_y = Some(block_transform.translation.y);
-                    }
-                }
-            }
-        }
-        // 获取最外层方块
-        for (block_transform, _, texture_atlas) in block_query.iter() {
-            match (block_x, block_y) {
-                (Some(x), Some(y)) => {
-                    if block_transform.translation.x == x && block_transform.translation.y == y {
-                        res_index = Some(texture_atlas.index)
-                    }
-                }
-                _ => {}
+        // 无尽模式没有过关 方块清空时等待新列推入
+        if *mode == GameMode::Endless {
+            if block_number > 0 {
+                stuck_event.send(PlayerStuck { board: event.board });
             }
-        }
-
-        if let Some(index) = res_index {
-            res_hashset.insert(index);
+        } else if block_number <= CLEAR_NUM {
+            // 如果方块数量小于等于消除数量，则获胜
+            cleared_event.send(StageCleared { board: event.board });
+        } else {
+            stuck_event.send(PlayerStuck { board: event.board });
         }
     }
-
-    return res_hashset;
 }
 
-// 播放下落方块音效
-fn block_fall_down_sound(audio_handles: Res<GlobalAudio>, mut commands: Commands) {
-    if let Some(block_fall_down_sound) = audio_handles.block_fall_down.clone() {
-        commands.spawn(AudioBundle {
-            source: block_fall_down_sound,
-            ..default()
-        });
-    }
+// 棋盘当前方块生成规则网格
+pub fn grid_from_blocks(
+    board: Entity,
+    block_query: &Query<(&Transform, &TextureAtlas, &InBoard), With<Block>>,
+) -> Grid {
+    Grid::from_positions(
+        block_query
+            .iter()
+            .filter(|(_, _, in_board)| in_board.0 == board)
+            .map(|(transform, atlas, _)| (transform.translation.truncate(), atlas.index)),
+    )
 }
@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+
+use crate::ai::AiTimer;
+use crate::attract::AttractMode;
+use crate::block::RemoveBlocks;
+use crate::camera::{fit_camera_to_window, PlayfieldSize, PlayfieldViewport};
+use crate::collision::{BlockKdTree, CurrentThrow, GroundKdTree, WallKdTree};
+use crate::gui::{CountDown, LastScorePopup, Score};
+use crate::player::{Controller, InputLocked, KeyBindings, PlayerInput};
+use crate::settlement::Settlement;
+use crate::stage::Stage;
+use crate::state::{
+    apply_board_transitions, BlockGroupState, BoardState, BoardTransition, GameMode, GameState,
+    HandBlockState, SettlementState,
+};
+use crate::versus::PendingGarbage;
+use crate::*;
+
+// 棋盘 玩家 梯子 手里方块 方块 墙体均为其子实体 并带有 InBoard
+// 棋盘内坐标与单人模式相同 棋盘根节点的位移决定显示位置
+#[derive(Component, Debug)]
+pub struct Board {
+    // 玩家序号 从 0 开始
+    pub index: usize,
+    // 对战胜场
+    pub wins: u32,
+}
+
+// 所属棋盘
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InBoard(pub Entity);
+
+// 棋盘 UI 根节点 HUD 与结算界面挂在其下
+#[derive(Component)]
+pub struct BoardUi;
+
+// 每关重置的棋盘状态
+#[derive(Bundle, Default)]
+pub struct BoardStageBundle {
+    pub hand_block_state: BoardState<HandBlockState>,
+    pub block_group_state: BoardState<BlockGroupState>,
+    pub settlement_state: BoardState<SettlementState>,
+    pub input: PlayerInput,
+    pub current_throw: CurrentThrow,
+    pub remove_blocks: RemoveBlocks,
+    pub block_tree: BlockKdTree,
+    pub wall_tree: WallKdTree,
+    pub ground_tree: GroundKdTree,
+    pub settlement: Settlement,
+    pub pending_garbage: PendingGarbage,
+    pub last_score_popup: LastScorePopup,
+}
+
+// 整局保留的棋盘状态
+#[derive(Bundle)]
+pub struct BoardBundle {
+    pub board: Board,
+    pub spatial: SpatialBundle,
+    pub score: Score,
+    pub count_down: CountDown,
+    pub stage: Stage,
+    pub bindings: KeyBindings,
+    pub controller: Controller,
+    pub stage_state: BoardStageBundle,
+}
+
+pub struct BoardPlugin;
+
+impl Plugin for BoardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .add_event::<BoardTransition<HandBlockState>>()
+            .add_event::<BoardTransition<BlockGroupState>>()
+            .add_event::<BoardTransition<SettlementState>>()
+            .add_systems(
+                PreUpdate,
+                (
+                    apply_board_transitions::<HandBlockState>,
+                    apply_board_transitions::<BlockGroupState>,
+                    apply_board_transitions::<SettlementState>,
+                ),
+            )
+            .add_systems(Update, fit_board_ui.after(fit_camera_to_window))
+            .add_systems(OnEnter(GameState::GameInit), spawn_boards)
+            .add_systems(OnEnter(GameState::MainMenu), despawn_boards)
+            .add_systems(OnEnter(GameState::LevelSelect), despawn_boards);
+    }
+}
+
+// 棋盘在世界中的偏移 多个棋盘横向并排居中
+pub fn board_offset(index: usize, count: usize) -> Vec2 {
+    let x = (index as f32 - (count as f32 - 1.0) / 2.0) * PLAYFIELD_W;
+    Vec2::new(x, 0.0)
+}
+
+// 进入游戏生成棋盘 已存在则重置每关状态
+pub fn spawn_boards(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    attract_mode: Res<AttractMode>,
+    mut playfield: ResMut<PlayfieldSize>,
+    board_query: Query<Entity, With<Board>>,
+) {
+    if !board_query.is_empty() {
+        for entity in board_query.iter() {
+            commands
+                .entity(entity)
+                .insert(BoardStageBundle::default())
+                .remove::<InputLocked>();
+        }
+        return;
+    }
+
+    let count = mode.board_count();
+    playfield.0 = Vec2::new(PLAYFIELD_W * count as f32, PLAYFIELD_H);
+
+    for index in 0..count {
+        let bindings = match mode.as_ref() {
+            GameMode::Versus => KeyBindings::versus(index),
+            _ => KeyBindings::default(),
+        };
+        // 演示模式全部由 AI 操作 人机对战时 AI 为玩家二
+        let controller = match mode.as_ref() {
+            _ if attract_mode.0 => Controller::Ai,
+            GameMode::VersusAi if index > 0 => Controller::Ai,
+            _ => Controller::Keyboard,
+        };
+
+        let board = commands
+            .spawn(BoardBundle {
+                board: Board { index, wins: 0 },
+                spatial: SpatialBundle::from_transform(Transform::from_translation(
+                    board_offset(index, count).extend(0.0),
+                )),
+                score: Score::default(),
+                count_down: CountDown::default(),
+                stage: Stage::default(),
+                bindings,
+                controller,
+                stage_state: BoardStageBundle::default(),
+            })
+            .id();
+
+        if controller == Controller::Ai {
+            commands.entity(board).insert(AiTimer::default());
+        }
+
+        let width = 100.0 / count as f32;
+        commands.spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(width * index as f32),
+                    width: Val::Percent(width),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                ..default()
+            },
+            BoardUi,
+            InBoard(board),
+        ));
+    }
+}
+
+// 棋盘 UI 根节点按游戏区域矩形均分 避免 HUD 跑进黑边
+// UI 相机渲染在视口内 故偏移从视口左上角算起
+fn fit_board_ui(
+    playfield_viewport: Res<PlayfieldViewport>,
+    ui_scale: Res<UiScale>,
+    board_query: Query<&Board>,
+    mut ui_query: Query<(Ref<BoardUi>, &InBoard, &mut Style)>,
+) {
+    let count = board_query.iter().count().max(1);
+    let size = playfield_viewport.0.size() / ui_scale.0;
+    let width = size.x / count as f32;
+
+    for (ui, in_board, mut style) in ui_query.iter_mut() {
+        if !playfield_viewport.is_changed() && !ui_scale.is_changed() && !ui.is_added() {
+            continue;
+        }
+        let Ok(board) = board_query.get(in_board.0) else {
+            continue;
+        };
+
+        style.left = Val::Px(width * board.index as f32);
+        style.top = Val::Px(0.0);
+        style.width = Val::Px(width);
+        style.height = Val::Px(size.y);
+    }
+}
+
+// 回到主菜单 销毁棋盘 整局状态随之清空
+#[allow(clippy::type_complexity)]
+pub fn despawn_boards(
+    mut commands: Commands,
+    mut playfield: ResMut<PlayfieldSize>,
+    query: Query<Entity, Or<(With<Board>, With<BoardUi>)>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    playfield.0 = Vec2::new(PLAYFIELD_W, PLAYFIELD_H);
+}
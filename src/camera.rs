@@ -1,15 +1,176 @@
 use bevy::prelude::*;
+use bevy::render::camera::{ScalingMode, Viewport};
+use bevy::window::{PrimaryWindow, WindowMode};
 
 use crate::state::GameState;
+use crate::*;
+
+#[derive(Component)]
+pub struct MainCamera;
+
+// 游戏区域背景 窗口多余部分由清屏色填充形成黑边
+#[derive(Component)]
+pub struct PlayfieldBackground;
+
+// 游戏区域逻辑尺寸 多棋盘时横向扩展
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct PlayfieldSize(pub Vec2);
+
+impl Default for PlayfieldSize {
+    fn default() -> Self {
+        Self(Vec2::new(PLAYFIELD_W, PLAYFIELD_H))
+    }
+}
+
+// 游戏区域在窗口中的矩形 逻辑像素 由 fit_camera_to_window 计算 HUD 根节点据此定位
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct PlayfieldViewport(pub Rect);
 
 pub struct MyCameraPlugin;
 
 impl Plugin for MyCameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Loading), setup_camera);
+        app.init_resource::<PlayfieldSize>()
+            .init_resource::<PlayfieldViewport>()
+            .add_systems(OnEnter(GameState::Loading), setup_camera)
+            .add_systems(
+                Update,
+                (
+                    apply_playfield_size,
+                    fit_camera_to_window.after(apply_playfield_size),
+                    toggle_fullscreen,
+                ),
+            );
     }
 }
 
 fn setup_camera(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
+    let mut camera = Camera2dBundle::default();
+    // 固定逻辑分辨率 与窗口大小无关
+    camera.projection.scaling_mode = ScalingMode::Fixed {
+        width: PLAYFIELD_W,
+        height: PLAYFIELD_H,
+    };
+
+    commands.spawn((camera, MainCamera, IsDefaultUiCamera));
+
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb_u8(BG_COLOR.0, BG_COLOR.1, BG_COLOR.2),
+                custom_size: Some(Vec2::new(PLAYFIELD_W, PLAYFIELD_H)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 0.0, -1.0),
+            ..default()
+        },
+        PlayfieldBackground,
+    ));
+}
+
+// 游戏区域尺寸变化时更新投影与背景
+fn apply_playfield_size(
+    playfield: Res<PlayfieldSize>,
+    mut camera_query: Query<&mut OrthographicProjection, With<MainCamera>>,
+    mut background_query: Query<&mut Sprite, With<PlayfieldBackground>>,
+) {
+    if !playfield.is_changed() {
+        return;
+    }
+
+    for mut projection in camera_query.iter_mut() {
+        projection.scaling_mode = ScalingMode::Fixed {
+            width: playfield.0.x,
+            height: playfield.0.y,
+        };
+    }
+
+    for mut sprite in background_query.iter_mut() {
+        sprite.custom_size = Some(playfield.0);
+    }
+}
+
+// 根据窗口大小计算缩放 整数倍像素对齐 居中留黑边
+pub fn fit_camera_to_window(
+    playfield: Res<PlayfieldSize>,
+    mut playfield_viewport: ResMut<PlayfieldViewport>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<&mut Camera, With<MainCamera>>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    if window_query.is_empty() || camera_query.is_empty() {
+        return;
+    }
+
+    let window = window_query.single();
+    let mut camera = camera_query.single_mut();
+
+    let window_size = Vec2::new(
+        window.physical_width() as f32,
+        window.physical_height() as f32,
+    );
+    if window_size.x <= 0.0 || window_size.y <= 0.0 {
+        return;
+    }
+
+    let scale = playfield_scale(window_size, playfield.0);
+    let viewport_size = (playfield.0 * scale).floor();
+    let viewport_position = ((window_size - viewport_size) / 2.0).floor();
+
+    let viewport = Viewport {
+        physical_position: viewport_position.as_uvec2(),
+        physical_size: viewport_size.as_uvec2(),
+        ..default()
+    };
+
+    let changed = camera.viewport.as_ref().is_none_or(|current| {
+        current.physical_position != viewport.physical_position
+            || current.physical_size != viewport.physical_size
+    });
+    if changed {
+        camera.viewport = Some(viewport);
+    }
+
+    let rect = Rect::from_corners(viewport_position, viewport_position + viewport_size);
+    let logical_rect = Rect {
+        min: rect.min / window.scale_factor(),
+        max: rect.max / window.scale_factor(),
+    };
+    if playfield_viewport.0 != logical_rect {
+        playfield_viewport.0 = logical_rect;
+    }
+
+    // UI 与游戏区域同比例缩放 保证 HUD 锚定在游戏区域内
+    let target_ui_scale = scale / window.scale_factor();
+    if ui_scale.0 != target_ui_scale {
+        ui_scale.0 = target_ui_scale;
+    }
+}
+
+// 游戏区域缩放倍数 窗口足够大时取整数倍 否则按比例缩小
+pub fn playfield_scale(window_size: Vec2, playfield_size: Vec2) -> f32 {
+    let scale = (window_size.x / playfield_size.x).min(window_size.y / playfield_size.y);
+
+    if scale >= 1.0 {
+        scale.floor()
+    } else {
+        scale
+    }
+}
+
+// 切换全屏 F11
+fn toggle_fullscreen(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if window_query.is_empty() || !keyboard_input.just_pressed(KeyCode::F11) {
+        return;
+    }
+
+    let mut window = window_query.single_mut();
+
+    window.mode = match window.mode {
+        WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+        _ => WindowMode::Windowed,
+    };
 }
@@ -4,9 +4,14 @@ use kd_tree::{KdPoint, KdTree};
 use std::time::Duration;
 
 use crate::block::{Block, Direction, HandBlock};
+use crate::board::InBoard;
+use crate::events::{
+    BlockHit, GroundHit, HandBlockReturned, HandBlockSwapped, ThrowStarted, WallBounce,
+};
 use crate::player::Player;
-use crate::resources::GlobalAudio;
-use crate::state::{GameState, HandBlockState};
+use crate::rules::{HitResult, ThrowState};
+use crate::state::{BoardState, BoardTransition, GameState, HandBlockState};
+use crate::tween::{Ease, Tween, TweenCompleted, TweenTarget};
 use crate::wall::{Ground, Wall};
 use crate::*;
 
@@ -16,6 +21,16 @@ pub struct Collidable {
     entity: Entity,
 }
 
+impl Collidable {
+    pub fn pos(&self) -> Vec2 {
+        self.pos
+    }
+
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+}
+
 impl KdPoint for Collidable {
     type Scalar = f32;
 
@@ -29,8 +44,8 @@ impl KdPoint for Collidable {
     }
 }
 
-// 方块树
-#[derive(Resource)]
+// 方块树 每个棋盘一份 坐标相对棋盘
+#[derive(Component)]
 pub struct BlockKdTree(pub KdTree<Collidable>);
 
 impl Default for BlockKdTree {
@@ -40,7 +55,7 @@ impl Default for BlockKdTree {
 }
 
 // 墙面树
-#[derive(Resource)]
+#[derive(Component)]
 pub struct WallKdTree(pub KdTree<Collidable>);
 
 impl Default for WallKdTree {
@@ -50,7 +65,7 @@ impl Default for WallKdTree {
 }
 
 // 地面树
-#[derive(Resource)]
+#[derive(Component)]
 pub struct GroundKdTree(pub KdTree<Collidable>);
 
 impl Default for GroundKdTree {
@@ -59,33 +74,12 @@ impl Default for GroundKdTree {
     }
 }
 
-// 贝塞尔曲线点
-#[derive(Resource)]
-pub struct BezierPoints(pub Option<[[Vec3; 4]; 1]>);
-impl Default for BezierPoints {
-    fn default() -> Self {
-        Self(None)
-    }
-}
-
-#[derive(Resource)]
-pub struct CollisionBackTimer(Timer);
-impl Default for CollisionBackTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(1.0 / 3.0, TimerMode::Once))
-    }
-}
-
-// 是否消除
-#[derive(Resource)]
-struct IsEliminate(bool);
-
-// 闪电块首次碰触的方块
-#[derive(Resource)]
-struct LightFirstRemoveBlock(Option<usize>);
-impl Default for LightFirstRemoveBlock {
+// 当前投掷状态 消除与交换规则见 rules::ThrowState
+#[derive(Component)]
+pub struct CurrentThrow(pub ThrowState);
+impl Default for CurrentThrow {
     fn default() -> Self {
-        Self(None)
+        Self(ThrowState::new(HAND_BLOCK_INDEX))
     }
 }
 
@@ -93,289 +87,306 @@ pub struct CollisionPlugin;
 
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<BezierPoints>()
-            .init_resource::<CollisionBackTimer>()
-            .init_resource::<LightFirstRemoveBlock>()
-            .insert_resource(BlockKdTree::default())
-            .insert_resource(WallKdTree::default())
-            .insert_resource(GroundKdTree::default())
-            .insert_resource(IsEliminate(false))
-            .add_systems(
-                OnEnter(GameState::InGame),
-                (spawn_wall_kd_tree, spawn_ground_kd_tree),
-            )
-            .add_systems(
-                Update,
-                (
-                    (
-                        handle_block_collision,
-                        handle_lightning_block_collision,
-                        handle_block_wall_collision,
-                        handle_block_ground_collision,
-                    )
-                        .run_if(in_state(HandBlockState::Moving)),
-                    handle_collision_back_animation.run_if(in_state(HandBlockState::Backing)),
-                    update_block_kd_tree
-                        .run_if(in_state(HandBlockState::Idle))
-                        .run_if(on_timer(Duration::from_secs_f32(KD_TREE_REFRESH_RATE))),
-                )
-                    .run_if(in_state(GameState::InGame)),
-            )
-            .add_systems(
-                OnEnter(HandBlockState::Backing),
+        app.add_systems(
+            OnEnter(GameState::InGame),
+            (spawn_wall_kd_tree, spawn_ground_kd_tree),
+        )
+        .add_systems(
+            Update,
+            (
+                start_throw,
                 (
-                    lighting_first_remove_block_index_reset,
-                    hand_block_back_sound,
+                    handle_block_collision,
+                    handle_block_wall_collision,
+                    handle_block_ground_collision,
                 ),
-            );
+                handle_collision_back_animation,
+                apply_returned_hand_block,
+                update_block_kd_tree
+                    .run_if(on_timer(Duration::from_secs_f32(KD_TREE_REFRESH_RATE))),
+            )
+                .run_if(in_state(GameState::InGame)),
+        );
     }
 }
 
+// 开始投掷 记录手里方块
+fn start_throw(
+    mut events: EventReader<BoardTransition<HandBlockState>>,
+    mut throw_events: EventWriter<ThrowStarted>,
+    mut board_query: Query<&mut CurrentThrow>,
+    hand_block_query: Query<(&TextureAtlas, &InBoard), With<HandBlock>>,
+) {
+    for event in events.read().filter(|e| e.entered(HandBlockState::Moving)) {
+        let Ok(mut current_throw) = board_query.get_mut(event.board) else {
+            continue;
+        };
+
+        for (atlas, in_board) in hand_block_query.iter() {
+            if in_board.0 == event.board {
+                current_throw.0 = ThrowState::new(atlas.index);
+                throw_events.send(ThrowStarted {
+                    board: event.board,
+                    hand: atlas.index,
+                });
+            }
+        }
+    }
+}
+
+// 生成kd tree
+fn build_kd_tree<'a>(items: impl Iterator<Item = (&'a Transform, Entity)>) -> KdTree<Collidable> {
+    KdTree::build_by_ordered_float(
+        items
+            .map(|(t, e)| Collidable {
+                pos: t.translation.truncate(),
+                entity: e,
+            })
+            .collect(),
+    )
+}
+
 // 生成墙面kd tree
 fn spawn_wall_kd_tree(
-    mut tree: ResMut<WallKdTree>,
-    wall_query: Query<(&Transform, Entity), With<Wall>>,
+    mut board_query: Query<(Entity, &mut WallKdTree)>,
+    wall_query: Query<(&Transform, Entity, &InBoard), With<Wall>>,
 ) {
-    let mut items = Vec::new();
-
-    for (t, e) in wall_query.iter() {
-        items.push(Collidable {
-            pos: t.translation.truncate(),
-            entity: e,
-        });
+    for (board, mut tree) in board_query.iter_mut() {
+        tree.0 = build_kd_tree(
+            wall_query
+                .iter()
+                .filter(|(_, _, in_board)| in_board.0 == board)
+                .map(|(t, e, _)| (t, e)),
+        );
     }
-
-    tree.0 = KdTree::build_by_ordered_float(items);
 }
 
 // 生成地面kd tree
 fn spawn_ground_kd_tree(
-    mut tree: ResMut<GroundKdTree>,
-    ground_query: Query<(&Transform, Entity), With<Ground>>,
+    mut board_query: Query<(Entity, &mut GroundKdTree)>,
+    ground_query: Query<(&Transform, Entity, &InBoard), With<Ground>>,
 ) {
-    let mut items = Vec::new();
-
-    for (t, e) in ground_query.iter() {
-        items.push(Collidable {
-            pos: t.translation.truncate(),
-            entity: e,
-        });
+    for (board, mut tree) in board_query.iter_mut() {
+        tree.0 = build_kd_tree(
+            ground_query
+                .iter()
+                .filter(|(_, _, in_board)| in_board.0 == board)
+                .map(|(t, e, _)| (t, e)),
+        );
     }
-
-    tree.0 = KdTree::build_by_ordered_float(items);
 }
 
-// 更新方块kd tree
+// 更新方块kd tree 手里方块静止时
 fn update_block_kd_tree(
-    mut tree: ResMut<BlockKdTree>,
-    block_query: Query<(&Transform, Entity, &Block), With<Block>>,
+    mut board_query: Query<(Entity, &mut BlockKdTree, &BoardState<HandBlockState>)>,
+    block_query: Query<(&Transform, Entity, &Block, &InBoard)>,
 ) {
-    let mut items = Vec::new();
-
-    for (t, e, block) in block_query.iter() {
-        if block.show {
-            items.push(Collidable {
-                pos: t.translation.truncate(),
-                entity: e,
-            });
+    for (board, mut tree, state) in board_query.iter_mut() {
+        if !state.is(HandBlockState::Idle) {
+            continue;
         }
-    }
 
-    tree.0 = KdTree::build_by_ordered_float(items);
+        tree.0 = build_kd_tree(
+            block_query
+                .iter()
+                .filter(|(_, _, block, in_board)| in_board.0 == board && block.show)
+                .map(|(t, e, _, _)| (t, e)),
+        );
+    }
 }
 
 // 处理方块碰撞
+#[allow(clippy::type_complexity)]
 fn handle_block_collision(
-    tree: ResMut<BlockKdTree>,
-    mut is_eliminate: ResMut<IsEliminate>,
-    mut hand_block_query: Query<(&mut Transform, &mut TextureAtlas), With<HandBlock>>,
+    mut hit_events: EventWriter<BlockHit>,
+    mut swap_events: EventWriter<HandBlockSwapped>,
+    mut board_query: Query<(
+        &BlockKdTree,
+        &mut CurrentThrow,
+        &mut BoardState<HandBlockState>,
+    )>,
+    mut hand_block_query: Query<(&Transform, &mut TextureAtlas, &InBoard), With<HandBlock>>,
     mut block_query: Query<
         (&mut Block, &mut Visibility, &mut TextureAtlas),
         (With<Block>, Without<HandBlock>),
     >,
-    mut next_state: ResMut<NextState<HandBlockState>>,
 ) {
-    if hand_block_query.is_empty() || block_query.is_empty() {
-        return;
-    }
-
-    let (transform, mut hand_block_text_atlas) = hand_block_query.single_mut();
-
-    // 如果是闪电块 则返回 由闪电碰撞逻辑处理
-    if hand_block_text_atlas.index == LIGHT_BLOCK_INDEX {
-        return;
-    }
-    let pos = transform.translation.truncate();
-    let blocks = tree.0.within_radius(&[pos.x, pos.y], 48.0);
-
-    for b_e in blocks {
-        if let Ok((mut b_b, mut b_visible, mut block_text_atlas)) = block_query.get_mut(b_e.entity)
-        {
-            if !b_b.show {
-                continue;
-            }
-            // 判断方块碰撞后是否可消除
-            // 种类相同 可消除
-            if block_text_atlas.index == hand_block_text_atlas.index {
-                b_b.show = false;
-                is_eliminate.0 = true;
-                *b_visible = Visibility::Hidden;
-            } else {
-                // 不同种类方块 不可消除 且之前有消除过 则交换方块种类
-                if is_eliminate.0 {
-                    (hand_block_text_atlas.index, block_text_atlas.index) =
-                        (block_text_atlas.index, hand_block_text_atlas.index);
-                    is_eliminate.0 = false;
-                }
-                next_state.set(HandBlockState::Backing);
-            }
+    for (transform, mut hand_block_text_atlas, in_board) in hand_block_query.iter_mut() {
+        let Ok((tree, mut current_throw, mut state)) = board_query.get_mut(in_board.0) else {
+            continue;
+        };
+        if !state.is(HandBlockState::Moving) {
+            continue;
         }
-    }
-}
 
-// 处理万能方块碰撞
-fn handle_lightning_block_collision(
-    tree: ResMut<BlockKdTree>,
-    mut lighting_first_remove_block: ResMut<LightFirstRemoveBlock>,
-    mut hand_block_query: Query<(&mut Transform, &mut TextureAtlas), With<HandBlock>>,
-    mut block_query: Query<
-        (&mut Block, &mut Visibility, &mut TextureAtlas),
-        (With<Block>, Without<HandBlock>),
-    >,
-    mut next_state: ResMut<NextState<HandBlockState>>,
-) {
-    if hand_block_query.is_empty() || block_query.is_empty() {
-        return;
-    }
+        let pos = transform.translation.truncate();
+        let blocks = tree.0.within_radius(&[pos.x, pos.y], 48.0);
 
-    let (transform, hand_block_text_atlas) = hand_block_query.single_mut();
-    // 如果非闪电块 则直接返回
-    if hand_block_text_atlas.index != LIGHT_BLOCK_INDEX {
-        return;
-    }
-
-    let pos = transform.translation.truncate();
-    let blocks = tree.0.within_radius(&[pos.x, pos.y], 48.0);
-
-    for b_e in blocks {
-        if let Ok((mut b_b, mut b_visible, block_text_atlas)) = block_query.get_mut(b_e.entity) {
-            if !b_b.show {
-                continue;
-            }
+        for b_e in blocks {
+            if let Ok((mut b_b, mut b_visible, mut block_text_atlas)) =
+                block_query.get_mut(b_e.entity)
+            {
+                if !b_b.show {
+                    continue;
+                }
 
-            if let Some(index) = lighting_first_remove_block.0 {
-                if block_text_atlas.index == index {
-                    b_b.show = false;
-                    *b_visible = Visibility::Hidden;
-                } else {
-                    next_state.set(HandBlockState::Backing);
+                let result = current_throw.0.hit_block(block_text_atlas.index);
+                hit_events.send(BlockHit {
+                    board: in_board.0,
+                    block: b_e.entity,
+                    result,
+                });
+
+                match result {
+                    HitResult::Remove => {
+                        b_b.show = false;
+                        *b_visible = Visibility::Hidden;
+                    }
+                    // 交换方块种类后返回
+                    HitResult::Swap => {
+                        swap_events.send(HandBlockSwapped {
+                            board: in_board.0,
+                            block: b_e.entity,
+                            from: hand_block_text_atlas.index,
+                            to: current_throw.0.hand,
+                        });
+                        block_text_atlas.index = hand_block_text_atlas.index;
+                        hand_block_text_atlas.index = current_throw.0.hand;
+                        state.set(HandBlockState::Backing);
+                        break;
+                    }
+                    HitResult::Return => {
+                        state.set(HandBlockState::Backing);
+                        break;
+                    }
                 }
-            } else {
-                b_b.show = false;
-                *b_visible = Visibility::Hidden;
-                lighting_first_remove_block.0 = Some(block_text_atlas.index);
             }
         }
     }
 }
 
-// 闪电方块返回重置
-fn lighting_first_remove_block_index_reset(
-    mut lighting_first_remove_block: ResMut<LightFirstRemoveBlock>,
-    mut hand_block_query: Query<&mut TextureAtlas, With<HandBlock>>,
+// 返回时更新手里方块 闪电块变为首次消除的种类
+fn apply_returned_hand_block(
+    mut events: EventReader<BoardTransition<HandBlockState>>,
+    mut returned_events: EventWriter<HandBlockReturned>,
+    board_query: Query<&CurrentThrow>,
+    mut hand_block_query: Query<(&mut TextureAtlas, &InBoard), With<HandBlock>>,
 ) {
-    if hand_block_query.is_empty() {
-        return;
-    }
-
-    if let Some(index) = lighting_first_remove_block.0 {
-        hand_block_query.single_mut().index = index;
+    for event in events.read().filter(|e| e.entered(HandBlockState::Backing)) {
+        let Ok(current_throw) = board_query.get(event.board) else {
+            continue;
+        };
+
+        let index = current_throw.0.returned_hand();
+        for (mut atlas, in_board) in hand_block_query.iter_mut() {
+            if in_board.0 == event.board && atlas.index != index {
+                atlas.index = index;
+            }
+        }
+        returned_events.send(HandBlockReturned {
+            board: event.board,
+            hand: index,
+        });
     }
-
-    lighting_first_remove_block.0 = None;
 }
 
 // 处理墙体碰撞
 fn handle_block_wall_collision(
-    tree: ResMut<WallKdTree>,
-    mut hand_block_query: Query<(&mut Transform, &mut HandBlock), With<HandBlock>>,
+    mut bounce_events: EventWriter<WallBounce>,
+    board_query: Query<(&WallKdTree, &BoardState<HandBlockState>)>,
+    mut hand_block_query: Query<(&mut Transform, &mut HandBlock, &InBoard)>,
     wall_query: Query<&Transform, (With<Wall>, Without<HandBlock>)>,
-    mut _next_state: ResMut<NextState<HandBlockState>>,
 ) {
-    if hand_block_query.is_empty() || wall_query.is_empty() {
-        return;
-    }
+    for (mut transform, mut hand_block, in_board) in hand_block_query.iter_mut() {
+        let Ok((tree, state)) = board_query.get(in_board.0) else {
+            continue;
+        };
+        if !state.is(HandBlockState::Moving) {
+            continue;
+        }
 
-    let (mut transform, mut hand_block) = hand_block_query.single_mut();
-    let pos = transform.translation.truncate();
-    let walls = tree.0.within_radius(&[pos.x, pos.y], 42.0);
+        let pos = transform.translation.truncate();
+        let walls = tree.0.within_radius(&[pos.x, pos.y], 42.0);
 
-    for w_e in walls {
-        if let Ok(w_t) = wall_query.get(w_e.entity) {
-            hand_block.direction = Direction::Down;
-            transform.translation.x = w_t.translation.x + STEP_SIZE as f32;
+        for w_e in walls {
+            if let Ok(w_t) = wall_query.get(w_e.entity) {
+                if matches!(hand_block.direction, Direction::Left) {
+                    bounce_events.send(WallBounce { board: in_board.0 });
+                }
+                hand_block.direction = Direction::Down;
+                transform.translation.x = w_t.translation.x + STEP_SIZE as f32;
+            }
         }
     }
 }
 
 // 处理地面碰撞
 fn handle_block_ground_collision(
-    tree: ResMut<GroundKdTree>,
-    mut is_eliminate: ResMut<IsEliminate>,
-    hand_block_query: Query<&Transform, With<HandBlock>>,
-    mut next_state: ResMut<NextState<HandBlockState>>,
+    mut ground_events: EventWriter<GroundHit>,
+    mut board_query: Query<(
+        &GroundKdTree,
+        &mut CurrentThrow,
+        &mut BoardState<HandBlockState>,
+    )>,
+    hand_block_query: Query<(&Transform, &InBoard), With<HandBlock>>,
 ) {
-    if hand_block_query.is_empty() {
-        return;
-    }
+    for (transform, in_board) in hand_block_query.iter() {
+        let Ok((tree, mut current_throw, mut state)) = board_query.get_mut(in_board.0) else {
+            continue;
+        };
+        if !state.is(HandBlockState::Moving) {
+            continue;
+        }
 
-    let transform = hand_block_query.single();
-    let pos = transform.translation.truncate();
-    let grounds = tree.0.within_radius(&[pos.x, pos.y], 42.0);
+        let pos = transform.translation.truncate();
+        let grounds = tree.0.within_radius(&[pos.x, pos.y], 42.0);
 
-    for _ in grounds {
-        next_state.set(HandBlockState::Backing);
-        is_eliminate.0 = false;
+        if !grounds.is_empty() {
+            ground_events.send(GroundHit { board: in_board.0 });
+            state.set(HandBlockState::Backing);
+            current_throw.0.hit_ground();
+        }
     }
 }
 
-// 方块返回动画
+// 方块返回动画 沿贝塞尔曲线飞回玩家身后 补间结束后恢复空闲
+#[allow(clippy::type_complexity)]
 fn handle_collision_back_animation(
-    time: Res<Time>,
-    mut timer: ResMut<CollisionBackTimer>,
-    mut points: ResMut<BezierPoints>,
-    mut next_state: ResMut<NextState<HandBlockState>>,
-    player_query: Query<&Transform, With<Player>>,
-    mut hand_block_query: Query<&mut Transform, (With<HandBlock>, Without<Player>)>,
+    mut commands: Commands,
+    mut transition_events: EventReader<BoardTransition<HandBlockState>>,
+    mut completed_events: EventReader<TweenCompleted>,
+    mut board_query: Query<&mut BoardState<HandBlockState>>,
+    player_query: Query<(&Transform, &InBoard), With<Player>>,
+    hand_block_query: Query<(Entity, &Transform, &InBoard), (With<HandBlock>, Without<Player>)>,
 ) {
-    if hand_block_query.is_empty() || player_query.is_empty() {
-        return;
-    }
-
-    let player_transform = player_query.single();
-    let mut hand_block_transform = hand_block_query.single_mut();
-    let pos = hand_block_transform.translation.truncate();
-    let direction = player_transform.translation.truncate() - pos;
-
-    timer.0.tick(time.delta());
-
-    if let Some(b_points) = points.0 {
-        let bezier = CubicBezier::new(b_points).to_curve();
-
-        hand_block_transform.translation = bezier.position(timer.0.elapsed_secs() * 3.0);
-
-        if direction.length() < 50.0 {
-            points.0 = None;
-
-            next_state.set(HandBlockState::Idle);
-
-            timer.0.reset();
+    for event in transition_events.read() {
+        let Some((hand_block, hand_block_transform, _)) = hand_block_query
+            .iter()
+            .find(|(_, _, in_board)| in_board.0 == event.board)
+        else {
+            continue;
+        };
+
+        // 被其他规则打断时停止返回
+        if event.exited(HandBlockState::Backing) {
+            commands.entity(hand_block).remove::<Tween>();
+            continue;
         }
-    } else {
-        let target_pos = hand_block_transform.translation;
-        let top_y = (player_transform.translation.y + 220.0).min(WH / 2.0 - STEP_SIZE as f32);
-        points.0 = Some([[
-            target_pos,
+        if !event.entered(HandBlockState::Backing) {
+            continue;
+        }
+
+        let Some((player_transform, _)) = player_query
+            .iter()
+            .find(|(_, in_board)| in_board.0 == event.board)
+        else {
+            continue;
+        };
+
+        let top_y =
+            (player_transform.translation.y + 220.0).min(PLAYFIELD_H / 2.0 - STEP_SIZE as f32);
+        let points = [
+            hand_block_transform.translation,
             vec3(-50., top_y, 0.),
             vec3(-50., top_y, 0.),
             vec3(
@@ -383,16 +394,21 @@ fn handle_collision_back_animation(
                 player_transform.translation.y,
                 player_transform.translation.z,
             ),
-        ]]);
+        ];
+        commands
+            .entity(hand_block)
+            .insert(Tween::new(HAND_BLOCK_BACK_SEC, Ease::QuadOut).with(TweenTarget::Path(points)));
     }
-}
 
-// 返回声音
-fn hand_block_back_sound(audio_handles: Res<GlobalAudio>, mut commands: Commands) {
-    if let Some(player_move_source) = audio_handles.hand_block_black.clone() {
-        commands.spawn(AudioBundle {
-            source: player_move_source,
-            ..default()
-        });
+    for TweenCompleted { entity } in completed_events.read() {
+        let Ok((_, _, in_board)) = hand_block_query.get(*entity) else {
+            continue;
+        };
+        let Ok(mut state) = board_query.get_mut(in_board.0) else {
+            continue;
+        };
+        if state.is(HandBlockState::Backing) {
+            state.set(HandBlockState::Idle);
+        }
     }
 }
@@ -1,12 +1,26 @@
 use std::ops::RangeInclusive;
 
-// Window
+// Window 初始窗口大小
 pub const WW: f32 = 1200.0;
 pub const WH: f32 = 720.0;
+pub const WINDOW_MIN_W: f32 = 600.0;
+pub const WINDOW_MIN_H: f32 = 360.0;
+
+// Playfield 逻辑分辨率 世界坐标基于此计算
+pub const PLAYFIELD_W: f32 = 1200.0;
+pub const PLAYFIELD_H: f32 = 720.0;
 
 // Sprite sheet
 pub const SPRITE_SHEET_PATH: &str = "embedded://assets.png";
+// 精灵动画片段清单
+pub const SPRITE_CLIPS_PATH: &str = "embedded://sprites/default.sprites";
 pub const FONT_PATH: &str = "embedded://fonts/font.ttf";
+pub const MONOGRAM_FONT_PATH: &str = "embedded://fonts/monogram.ttf";
+// 字体回退链 依次尝试
+pub const FONT_STACK_PATHS: [&str; 2] = [FONT_PATH, MONOGRAM_FONT_PATH];
+
+// Locale
+pub const LOCALE_DIR: &str = "embedded://locales";
 pub const TILES_W: usize = 16;
 pub const TILES_H: usize = 16;
 pub const SPRITE_SHEET_W: usize = 4;
@@ -18,6 +32,32 @@ pub const STEP_SIZE: usize = 48;
 
 // Colors
 pub const BG_COLOR: (u8, u8, u8) = (74, 91, 198);
+pub const BACKGROUND_COLOR_PRESETS: [(u8, u8, u8); 4] =
+    [BG_COLOR, (40, 40, 48), (0, 0, 0), (96, 96, 96)];
+
+// Accessibility
+pub const LARGE_TEXT_SCALE: f32 = 1.5;
+pub const KIND_LETTER_FONT_SIZE: f32 = 8.0;
+// 方块种类描边颜色 顺序同 BlockKind::ALL
+pub const KIND_COLORS: [(u8, u8, u8); 7] = [
+    (255, 140, 160),
+    (120, 200, 120),
+    (250, 210, 90),
+    (110, 160, 240),
+    (190, 120, 220),
+    (220, 90, 90),
+    (255, 255, 255),
+];
+// Okabe-Ito 色盲友好配色
+pub const HIGH_CONTRAST_KIND_COLORS: [(u8, u8, u8); 7] = [
+    (230, 159, 0),
+    (86, 180, 233),
+    (0, 158, 115),
+    (240, 228, 66),
+    (0, 114, 178),
+    (213, 94, 0),
+    (255, 255, 255),
+];
 
 // Player
 pub const PLAYER_INIT_POS: (f32, f32) = (240.0, -288.0);
@@ -28,6 +68,79 @@ pub const LADDER_NUM: usize = 13;
 // Arrow
 pub const ARROW_TEXTATLAS_INDEX: usize = 7;
 
+// Attract 演示模式
+pub const ATTRACT_IDLE_SEC: f32 = 15.0;
+
+// AI 操作间隔
+pub const AI_STEP_SEC: f32 = 0.35;
+
+// Endless 无尽模式 推入新列的间隔随等级缩短
+pub const ENDLESS_PUSH_SEC: f32 = 10.0;
+pub const ENDLESS_PUSH_MIN_SEC: f32 = 3.0;
+pub const ENDLESS_SPEEDUP: f32 = 0.85;
+pub const ENDLESS_COLUMNS_PER_LEVEL: u32 = 5;
+pub const ENDLESS_OVER_SEC: f32 = 3.0;
+
+// Leaderboard 每个模式保留的名次
+pub const LEADERBOARD_SIZE: usize = 10;
+
+// Puzzle 解谜关卡包 失败后重试等待
+pub const PUZZLE_PACK_PATH: &str = "embedded://puzzles/pack.puzzle";
+pub const PUZZLE_RETRY_SEC: f32 = 2.0;
+pub const LEVEL_SELECT_GRID_W: f32 = 560.0;
+
+// Daily 每日挑战的关卡数
+pub const DAILY_STAGE_COUNT: usize = 3;
+
+// Statistics 统计界面每行宽度
+pub const STATS_ROW_W: f32 = 420.0;
+
+// Achievements 成就列表 解锁提示显示时长
+pub const ACHIEVEMENTS_PATH: &str = "embedded://achievements/default.achievements";
+pub const ACHIEVEMENT_TOAST_SEC: f32 = 3.0;
+pub const ACHIEVEMENT_TOAST_Z: i32 = 10;
+// 提示弹出与淡出时长
+pub const UI_TRANSITION_SEC: f32 = 0.2;
+pub const ACHIEVEMENT_ROW_W: f32 = 640.0;
+
+// Menu 菜单按钮尺寸 标题方块图标尺寸
+pub const MENU_BUTTON_W: f32 = 240.0;
+pub const MENU_BUTTON_H: f32 = 56.0;
+pub const MENU_LOGO_TILE: f32 = 48.0;
+// High Scores 排行榜每列宽度
+pub const HIGH_SCORES_COLUMN_W: f32 = 320.0;
+// Settings 设置项按钮宽度
+pub const SETTINGS_ROW_W: f32 = 480.0;
+
+// Audio 音量档位步长 同时播放的音效上限 结算时背景音乐音量比例
+pub const AUDIO_VOLUME_STEP: f32 = 0.25;
+pub const AUDIO_MAX_VOICES: usize = 12;
+pub const AUDIO_DUCK_VOLUME: f32 = 0.35;
+// Music 交叉淡入淡出时长 剩余时间低于该值切换紧迫音乐及其速度倍率 鼓点所在声道
+pub const MUSIC_FADE_SEC: f32 = 1.5;
+pub const MUSIC_HURRY_SEC: f32 = 30.0;
+pub const MUSIC_HURRY_TEMPO: f32 = 1.25;
+pub const MUSIC_DRUM_CHANNEL: usize = 3;
+// Tracker 音轨混音采样率与输出增益
+pub const TRACKER_SAMPLE_RATE: u32 = 44100;
+pub const TRACKER_GAIN: f32 = 0.8;
+// Synth 合成音效采样率 消除音效的档数及每档升高的半音
+pub const SYNTH_SAMPLE_RATE: u32 = 22050;
+pub const SYNTH_COMBO_STEPS: usize = 6;
+pub const SYNTH_COMBO_SEMITONES: f32 = 2.0;
+// Effects 强度档位步长 粒子层级与重力 大量消除的块数 震屏幅度与衰减速度 顿帧时长
+pub const EFFECT_INTENSITY_STEP: f32 = 0.5;
+pub const EFFECT_PARTICLE_Z: f32 = 2.0;
+pub const EFFECT_PARTICLE_GRAVITY: f32 = 900.0;
+pub const EFFECT_BIG_REMOVAL: usize = 4;
+pub const EFFECT_SHAKE_PX: f32 = 12.0;
+pub const EFFECT_SHAKE_DECAY: f32 = 2.5;
+pub const EFFECT_HIT_STOP_SEC: f32 = 0.08;
+
+// Dev 控制台保留的输出行数 调试层级
+pub const DEV_CONSOLE_LINES: usize = 8;
+pub const DEV_OVERLAY_Z: i32 = 20;
+
 // Kd Tree
 pub const KD_TREE_REFRESH_RATE: f32 = 0.2;
 
@@ -39,22 +152,29 @@ pub const BLOCK_DISPLAY_RANGE: RangeInclusive<usize> = 8..=13;
 pub const HAND_BLOCK_SPEED: f32 = 1280.0;
 pub const HAND_BLOCK_INDEX: usize = 15;
 pub const FALL_DOWN_TIMER: f32 = 0.12;
+// 手里方块飞回玩家的时长
+pub const HAND_BLOCK_BACK_SEC: f32 = 0.3;
 pub const BLOCK_BEFORE_REMOVE_INDEX: usize = 14;
 // 闪电 万能块索引
 pub const LIGHT_BLOCK_INDEX: usize = 15;
-// UI text
-pub const SCORE_TEXT: &str = "SCORE";
-pub const BLOCK_TEXT: &str = "BLOCK";
-pub const CLEAR_TEXT: &str = "CLEAR";
-pub const STAGE_TEXT: &str = "STAGE";
+// UI text 本地化键
+pub const SCORE_TEXT: &str = "hud.score";
+pub const BLOCK_TEXT: &str = "hud.block";
+pub const CLEAR_TEXT: &str = "hud.clear";
+pub const STAGE_TEXT: &str = "hud.stage";
+pub const THROWS_TEXT: &str = "hud.throws";
 
 // UI SCORE
 pub const SCORE_BLOCK_WIDTH: f32 = 220.0;
 pub const SCORE_BLOCK_POS: (f32, f32) = (48.0, 18.0);
 pub const ONCE_BLOCK_SCORE: u32 = 100;
-pub const HIGH_SCORE_POS_PERCENT: (f32, f32) = (-300.0, 100.0);
-pub const HIGH_SCORE_ANIMATION_DURATION: f32 = 0.5;
-pub const HIGH_SCORE_ANIMATION_SPEED: f32 = 64.0;
+// 得分提示 层级高于墙体 显示时长 上浮距离 连续出现时的叠放间距及叠放的最大中心距离
+pub const SCORE_POPUP_Z: f32 = 5.0;
+pub const SCORE_POPUP_FONT_SIZE: f32 = 40.0;
+pub const SCORE_POPUP_DURATION: f32 = 0.8;
+pub const SCORE_POPUP_RISE: f32 = 48.0;
+pub const SCORE_POPUP_STACK_GAP: f32 = 56.0;
+pub const SCORE_POPUP_STACK_RANGE: f32 = 96.0;
 pub const EVERY_SECOND_SCORE: u64 = 100;
 // UI CLEAR
 pub const CLEAR_BLOCK_POS: (f32, f32) = (48.0, 96.0);
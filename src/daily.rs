@@ -0,0 +1,254 @@
+use bevy::prelude::*;
+use chrono::{Days, Local, NaiveDate};
+use std::collections::BTreeMap;
+
+use crate::alert::AlertEvent;
+use crate::board::{despawn_boards, spawn_boards, Board};
+use crate::gui::Score;
+use crate::player::Controller;
+use crate::stage::{roll_stage_seed, Stage, StageSeed};
+use crate::state::{GameMode, GameState};
+use crate::storage::{format_key_values, parse_key_values, read_data, write_data};
+use crate::*;
+
+// 每日挑战记录存档文件
+const DAILY_FILE: &str = "daily.txt";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// 每日挑战成绩 分数及通过的关卡数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyResult {
+    pub score: u32,
+    pub cleared: usize,
+}
+
+// 每日挑战历史 每天只记录第一次计分挑战
+#[derive(Resource, Debug, Default)]
+pub struct DailyHistory(pub BTreeMap<NaiveDate, DailyResult>);
+
+impl DailyHistory {
+    pub fn played(&self, date: NaiveDate) -> bool {
+        self.0.contains_key(&date)
+    }
+
+    // 连续挑战天数 今天未挑战时从昨天算起 不中断连续记录
+    pub fn streak(&self, today: NaiveDate) -> u32 {
+        let mut date = if self.played(today) {
+            today
+        } else {
+            today - Days::new(1)
+        };
+
+        let mut streak = 0;
+        while self.played(date) {
+            streak += 1;
+            date = date - Days::new(1);
+        }
+
+        streak
+    }
+
+    // 每行 `日期 = 分数 通过关卡数`
+    pub fn parse(source: &str) -> Self {
+        Self(
+            parse_key_values(source)
+                .into_iter()
+                .filter_map(|(date, value)| {
+                    let date = NaiveDate::parse_from_str(&date, DATE_FORMAT).ok()?;
+                    let mut values = value.split_whitespace().map(str::parse::<u64>);
+                    let (Some(Ok(score)), Some(Ok(cleared))) = (values.next(), values.next())
+                    else {
+                        return None;
+                    };
+
+                    Some((
+                        date,
+                        DailyResult {
+                            score: score as u32,
+                            cleared: cleared as usize,
+                        },
+                    ))
+                })
+                .collect(),
+        )
+    }
+
+    pub fn to_source(&self) -> String {
+        let pairs = self.0.iter().map(|(date, result)| {
+            (
+                date.format(DATE_FORMAT).to_string(),
+                format!("{} {}", result.score, result.cleared),
+            )
+        });
+
+        format_key_values("Smart Block daily challenge", pairs)
+    }
+}
+
+// 本次每日挑战 日期在开始时确定 跨越零点仍使用同一组关卡
+#[derive(Resource, Debug)]
+pub struct DailyRun {
+    pub date: NaiveDate,
+    // 今天第一次挑战计分 之后为练习
+    pub scored: bool,
+}
+
+impl Default for DailyRun {
+    fn default() -> Self {
+        Self {
+            date: Local::now().date_naive(),
+            scored: false,
+        }
+    }
+}
+
+// 由日期及关卡生成种子 同一天所有玩家得到相同的关卡序列
+pub fn daily_seed(date: NaiveDate, stage: usize) -> u64 {
+    let day = date
+        .format("%Y%m%d")
+        .to_string()
+        .parse::<u64>()
+        .unwrap_or_default();
+
+    // splitmix64 打散相邻日期与关卡
+    let mut z = (day * 100 + stage as u64).wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(
+            read_data(DAILY_FILE)
+                .map(|source| DailyHistory::parse(&source))
+                .unwrap_or_default(),
+        )
+        .init_resource::<DailyRun>()
+        .add_systems(
+            OnEnter(GameState::GameInit),
+            (start_daily_run, seed_daily_stage)
+                .chain()
+                .after(spawn_boards)
+                .after(roll_stage_seed)
+                .run_if(resource_equals(GameMode::Daily)),
+        )
+        .add_systems(
+            OnEnter(GameState::InGame),
+            announce_daily_run.run_if(resource_equals(GameMode::Daily)),
+        )
+        // 棋盘在回到主菜单时销毁 需在此之前记录
+        .add_systems(
+            OnEnter(GameState::MainMenu),
+            record_daily_result
+                .before(despawn_boards)
+                .run_if(resource_equals(GameMode::Daily)),
+        );
+    }
+}
+
+// 第一关开始时确定日期 今天已有记录则本次为练习
+// 计分挑战立即记为已挑战并保存 中途退出也不能重新挑战
+fn start_daily_run(
+    mut run: ResMut<DailyRun>,
+    mut history: ResMut<DailyHistory>,
+    board_query: Query<&Stage, With<Board>>,
+) {
+    if board_query.iter().any(|stage| stage.0 > 1) {
+        return;
+    }
+
+    let date = Local::now().date_naive();
+    *run = DailyRun {
+        date,
+        scored: !history.played(date),
+    };
+
+    if run.scored {
+        history.0.insert(
+            date,
+            DailyResult {
+                score: 0,
+                cleared: 0,
+            },
+        );
+        save_daily_history(&history);
+    }
+}
+
+// 当天的固定关卡序列
+pub fn seed_daily_stage(
+    run: Res<DailyRun>,
+    mut seed: ResMut<StageSeed>,
+    board_query: Query<&Stage, With<Board>>,
+) {
+    let Some(stage) = board_query.iter().next() else {
+        return;
+    };
+
+    seed.0 = daily_seed(run.date, stage.0);
+    debug!("daily seed {} stage {}: {}", run.date, stage.0, seed.0);
+}
+
+// 第一关提示本次是否计分
+fn announce_daily_run(
+    run: Res<DailyRun>,
+    mut alert_event: EventWriter<AlertEvent>,
+    board_query: Query<(Entity, &Stage), With<Board>>,
+) {
+    for (board, stage) in board_query.iter() {
+        if stage.0 != 1 {
+            continue;
+        }
+
+        let key = if run.scored {
+            "daily.scored"
+        } else {
+            "daily.practice"
+        };
+        alert_event.send(AlertEvent {
+            board,
+            key: Some(String::from(key)),
+        });
+    }
+}
+
+// 挑战结束 更新开始时写入的记录为键盘玩家的成绩 练习不计入
+pub fn record_daily_result(
+    mut run: ResMut<DailyRun>,
+    mut history: ResMut<DailyHistory>,
+    board_query: Query<(&Score, &Stage, &Controller), With<Board>>,
+) {
+    if !run.scored {
+        return;
+    }
+
+    for (score, stage, controller) in board_query.iter() {
+        if *controller != Controller::Keyboard {
+            continue;
+        }
+
+        let result = DailyResult {
+            score: score.total_score,
+            cleared: stage.0.saturating_sub(1).min(DAILY_STAGE_COUNT),
+        };
+        history.0.insert(run.date, result);
+        run.scored = false;
+
+        info!(
+            "daily {}: {:?} streak {}",
+            run.date,
+            result,
+            history.streak(run.date)
+        );
+        save_daily_history(&history);
+    }
+}
+
+fn save_daily_history(history: &DailyHistory) {
+    if let Err(e) = write_data(DAILY_FILE, &history.to_source()) {
+        warn!("failed to save daily history: {}", e);
+    }
+}
@@ -0,0 +1,490 @@
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::ecs::query::QueryFilter;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::{ButtonState, InputSystem};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::block::{Block, BlockKind, HandBlock};
+use crate::board::{Board, InBoard};
+use crate::camera::MainCamera;
+use crate::collision::{BlockKdTree, GroundKdTree, WallKdTree};
+use crate::daily::seed_daily_stage;
+use crate::gui::CountDown;
+use crate::player::{Controller, Player};
+use crate::rules::pos_to_cell;
+use crate::stage::{roll_stage_seed, Stage, StageSeed};
+use crate::state::{
+    BlockGroupState, BoardState, GameMode, GameState, HandBlockState, SettlementState,
+};
+use crate::*;
+
+// 调试层 F12 切换
+#[derive(Resource, Default)]
+struct DevOverlay {
+    visible: bool,
+}
+
+#[derive(Component)]
+struct DevOverlayText;
+
+// 控制台 ` 键打开 打开时游戏收不到键盘输入
+#[derive(Resource, Default)]
+struct DevConsole {
+    open: bool,
+    input: String,
+    lines: Vec<String>,
+}
+
+impl DevConsole {
+    fn print(&mut self, line: impl Into<String>) {
+        self.lines.push(line.into());
+        let overflow = self.lines.len().saturating_sub(DEV_CONSOLE_LINES);
+        self.lines.drain(..overflow);
+    }
+}
+
+#[derive(Component)]
+struct DevConsoleText;
+
+// 固定关卡种子 每次进入关卡时覆盖随机种子
+#[derive(Resource, Default)]
+struct PinnedSeed(Option<u64>);
+
+// 控制台命令 作用于键盘玩家的棋盘
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+enum DevCommand {
+    Help,
+    // give lightning / set kind star
+    HandKind(BlockKind),
+    // goto stage 5
+    GotoStage(usize),
+    // time 10
+    Time(f32),
+    // win
+    Win,
+    // seed 1234 / seed off / seed
+    Seed(Option<u64>),
+    ShowSeed,
+}
+
+impl DevCommand {
+    fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let kind = |name: &str| {
+            BlockKind::ALL
+                .into_iter()
+                .find(|kind| kind.name() == name)
+                .ok_or_else(|| format!("unknown kind: {}", name))
+        };
+        let number = |value: &str| {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("not a number: {}", value))
+        };
+
+        match words.as_slice() {
+            ["help"] => Ok(DevCommand::Help),
+            ["give", name] | ["set", "kind", name] => kind(name).map(DevCommand::HandKind),
+            ["goto", "stage", n] => match number(n)? {
+                0 => Err(String::from("stages start at 1")),
+                n => Ok(DevCommand::GotoStage(n as usize)),
+            },
+            ["time", secs] => secs
+                .parse::<f32>()
+                .ok()
+                .filter(|secs| *secs >= 0.0)
+                .map(DevCommand::Time)
+                .ok_or_else(|| format!("not a duration: {}", secs)),
+            ["win"] => Ok(DevCommand::Win),
+            ["seed"] => Ok(DevCommand::ShowSeed),
+            ["seed", "off"] => Ok(DevCommand::Seed(None)),
+            ["seed", n] => number(n).map(|n| DevCommand::Seed(Some(n))),
+            [] => Err(String::new()),
+            _ => Err(format!("unknown command: {}", line)),
+        }
+    }
+}
+
+const HELP: &str =
+    "give <kind> | set kind <kind> | goto stage <n> | time <secs> | win | seed [<n>|off]";
+
+pub struct DevPlugin;
+
+impl Plugin for DevPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+
+        app.init_resource::<DevOverlay>()
+            .init_resource::<DevConsole>()
+            .init_resource::<PinnedSeed>()
+            .add_event::<DevCommand>()
+            .add_systems(Startup, spawn_dev_ui)
+            // 先于游戏读取键盘 控制台打开时清空按键状态
+            .add_systems(PreUpdate, handle_console_input.after(InputSystem))
+            .add_systems(
+                Update,
+                (
+                    toggle_overlay,
+                    run_dev_commands,
+                    update_overlay_text,
+                    update_console_text,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                debug_hand_block_keys.run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(
+                OnEnter(GameState::GameInit),
+                apply_pinned_seed
+                    .after(roll_stage_seed)
+                    .after(seed_daily_stage),
+            );
+    }
+}
+
+fn spawn_dev_ui(mut commands: Commands) {
+    let text_style = TextStyle {
+        font_size: 18.0,
+        color: Color::GREEN,
+        ..default()
+    };
+
+    let mut overlay = TextBundle::from_section("", text_style.clone())
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.7));
+    overlay.z_index = ZIndex::Global(DEV_OVERLAY_Z);
+    overlay.visibility = Visibility::Hidden;
+    commands.spawn((overlay, DevOverlayText));
+
+    let mut console = TextBundle::from_section("", text_style)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(0.0),
+            left: Val::Px(0.0),
+            width: Val::Percent(100.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.8));
+    console.z_index = ZIndex::Global(DEV_OVERLAY_Z);
+    console.visibility = Visibility::Hidden;
+    commands.spawn((console, DevConsoleText));
+}
+
+fn toggle_overlay(keyboard_input: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<DevOverlay>) {
+    if keyboard_input.just_pressed(KeyCode::F12) {
+        overlay.visible = !overlay.visible;
+    }
+}
+
+// 控制台输入 直接读取键盘事件 不受按键状态清空影响
+fn handle_console_input(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut console: ResMut<DevConsole>,
+    mut commands: EventWriter<DevCommand>,
+) {
+    for event in keyboard_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+
+        if event.key_code == KeyCode::Backquote {
+            console.open = !console.open;
+            keyboard_input.reset_all();
+            continue;
+        }
+
+        if !console.open {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Enter => {
+                let line = std::mem::take(&mut console.input);
+                console.print(format!("> {}", line));
+                match DevCommand::parse(&line) {
+                    Ok(command) => {
+                        commands.send(command);
+                    }
+                    Err(message) if !message.is_empty() => console.print(message),
+                    Err(_) => {}
+                }
+            }
+            Key::Backspace => {
+                console.input.pop();
+            }
+            Key::Space => console.input.push(' '),
+            Key::Character(text) => console.input.push_str(text),
+            _ => {}
+        }
+    }
+
+    if console.open {
+        keyboard_input.reset_all();
+    }
+}
+
+// 执行控制台命令
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn run_dev_commands(
+    mut commands: Commands,
+    mut dev_commands: EventReader<DevCommand>,
+    mut console: ResMut<DevConsole>,
+    mut pinned_seed: ResMut<PinnedSeed>,
+    seed: Res<StageSeed>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut board_query: Query<
+        (
+            Entity,
+            &Controller,
+            &mut Stage,
+            &mut CountDown,
+            &mut BoardState<SettlementState>,
+        ),
+        With<Board>,
+    >,
+    mut hand_block_query: Query<(&mut TextureAtlas, &InBoard), With<HandBlock>>,
+    block_query: Query<(Entity, &InBoard), With<Block>>,
+) {
+    for command in dev_commands.read() {
+        let in_game = *state.get() == GameState::InGame;
+        let needs_game = !matches!(
+            command,
+            DevCommand::Help | DevCommand::Seed(_) | DevCommand::ShowSeed
+        );
+        if needs_game && !in_game {
+            console.print("not in game");
+            continue;
+        }
+
+        let boards = board_query
+            .iter_mut()
+            .filter(|(_, controller, ..)| **controller == Controller::Keyboard);
+
+        match *command {
+            DevCommand::Help => console.print(HELP),
+            DevCommand::HandKind(kind) => {
+                let boards: Vec<Entity> = boards.map(|(board, ..)| board).collect();
+                for (mut atlas, in_board) in hand_block_query.iter_mut() {
+                    if boards.contains(&in_board.0) {
+                        atlas.index = kind.atlas_index();
+                    }
+                }
+                console.print(format!("hand block: {}", kind.name()));
+            }
+            DevCommand::GotoStage(n) => {
+                for (_, _, mut stage, ..) in boards {
+                    stage.0 = n;
+                }
+                next_state.set(GameState::GameInit);
+                console.print(format!("stage {}", n));
+            }
+            DevCommand::Time(secs) => {
+                for (_, _, _, mut count_down, _) in boards {
+                    let duration = count_down.0.duration();
+                    let left = Duration::from_secs_f32(secs).min(duration);
+                    count_down.0.set_elapsed(duration - left);
+                }
+                console.print(format!("time left: {}s", secs));
+            }
+            DevCommand::Win => {
+                for (board, _, _, _, mut settlement_state) in boards {
+                    if !settlement_state.is(SettlementState::Not) {
+                        continue;
+                    }
+                    for (block, _) in block_query.iter().filter(|(_, b)| b.0 == board) {
+                        commands.entity(block).despawn_recursive();
+                    }
+                    settlement_state.set(SettlementState::Start);
+                }
+                console.print("stage cleared");
+            }
+            DevCommand::Seed(pinned) => {
+                pinned_seed.0 = pinned;
+                match pinned {
+                    Some(seed) => console.print(format!("seed pinned: {}", seed)),
+                    None => console.print("seed unpinned"),
+                }
+                // 以新种子重开本关
+                if in_game {
+                    next_state.set(GameState::GameInit);
+                }
+            }
+            DevCommand::ShowSeed => console.print(format!("seed: {}", seed.0)),
+        }
+    }
+}
+
+fn apply_pinned_seed(pinned_seed: Res<PinnedSeed>, mut seed: ResMut<StageSeed>) {
+    if let Some(pinned) = pinned_seed.0 {
+        seed.0 = pinned;
+    }
+}
+
+// 调试层内容 状态 网格坐标 碰撞树 帧率
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn update_overlay_text(
+    overlay: Res<DevOverlay>,
+    diagnostics: Res<DiagnosticsStore>,
+    state: Res<State<GameState>>,
+    mode: Res<GameMode>,
+    seed: Res<StageSeed>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    board_query: Query<(
+        Entity,
+        &Board,
+        &GlobalTransform,
+        &BoardState<HandBlockState>,
+        &BoardState<BlockGroupState>,
+        &BoardState<SettlementState>,
+        &BlockKdTree,
+        &WallKdTree,
+        &GroundKdTree,
+    )>,
+    player_query: Query<(&Transform, &InBoard), With<Player>>,
+    hand_block_query: Query<(&Transform, &InBoard), With<HandBlock>>,
+    mut text_query: Query<(&mut Text, &mut Visibility), With<DevOverlayText>>,
+) {
+    let Ok((mut text, mut visibility)) = text_query.get_single_mut() else {
+        return;
+    };
+
+    *visibility = if overlay.visible {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    if !overlay.visible {
+        return;
+    }
+
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+    let cursor = window_query
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+        .zip(camera_query.get_single().ok())
+        .and_then(|(cursor, (camera, transform))| camera.viewport_to_world_2d(transform, cursor));
+
+    let mut value = String::new();
+    let _ = writeln!(value, "FPS {:.0}", fps);
+    let _ = writeln!(value, "{:?} {:?} seed {}", state.get(), *mode, seed.0);
+
+    for (board, info, transform, hand, group, settlement, blocks, walls, ground) in
+        board_query.iter()
+    {
+        let origin = transform.translation().truncate();
+
+        let _ = writeln!(
+            value,
+            "board {} hand {:?} group {:?} settlement {:?}",
+            info.index,
+            hand.get(),
+            group.get(),
+            settlement.get()
+        );
+        let _ = writeln!(
+            value,
+            "  player {:?} hand {:?} cursor {:?}",
+            board_cell(board, &player_query),
+            board_cell(board, &hand_block_query),
+            cursor.map(|cursor| pos_to_cell(cursor - origin)),
+        );
+        let _ = writeln!(
+            value,
+            "  kd blocks {} walls {} ground {}",
+            blocks.0.len(),
+            walls.0.len(),
+            ground.0.len()
+        );
+        let mut cells: Vec<IVec2> = blocks.0.iter().map(|c| pos_to_cell(c.pos())).collect();
+        cells.sort_by_key(|cell| (cell.y, cell.x));
+        for row in cells.chunks(8) {
+            let row: Vec<String> = row.iter().map(|c| format!("{},{}", c.x, c.y)).collect();
+            let _ = writeln!(value, "    {}", row.join(" "));
+        }
+    }
+
+    text.sections[0].value = value;
+}
+
+// 棋盘内实体所在格子
+fn board_cell<F: QueryFilter>(
+    board: Entity,
+    query: &Query<(&Transform, &InBoard), F>,
+) -> Option<IVec2> {
+    query
+        .iter()
+        .find(|(_, in_board)| in_board.0 == board)
+        .map(|(transform, _)| pos_to_cell(transform.translation.truncate()))
+}
+
+fn update_console_text(
+    console: Res<DevConsole>,
+    mut text_query: Query<(&mut Text, &mut Visibility), With<DevConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+
+    let Ok((mut text, mut visibility)) = text_query.get_single_mut() else {
+        return;
+    };
+
+    *visibility = if console.open {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+
+    let mut value = console.lines.join("\n");
+    if !value.is_empty() {
+        value.push('\n');
+    }
+    value.push_str(&format!("] {}_", console.input));
+    text.sections[0].value = value;
+}
+
+// 数字键 1-5 切换手里方块
+fn debug_hand_block_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut hand_block_query: Query<&mut TextureAtlas, With<HandBlock>>,
+) {
+    let keys = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+    ];
+    let Some(kind) = keys
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key))
+        .map(|i| BlockKind::ALL[i])
+    else {
+        return;
+    };
+
+    for mut texture_atlas in hand_block_query.iter_mut() {
+        texture_atlas.index = kind.atlas_index();
+    }
+}
@@ -0,0 +1,346 @@
+use bevy::prelude::*;
+use rand::Rng;
+use std::f32::consts::TAU;
+
+use crate::accessibility::AccessibilitySettings;
+use crate::block::{Block, BlockKind, HandBlock};
+use crate::board::InBoard;
+use crate::camera::MainCamera;
+use crate::events::{BlockHit, BlocksRemoved, GroundHit, WallBounce};
+use crate::player::Controller;
+use crate::rules::HitResult;
+use crate::state::GameState;
+use crate::storage::{format_key_values, parse_key_values, read_data, write_data};
+use crate::tween::{Ease, Tween, TweenTarget};
+use crate::world::GameEntity;
+use crate::*;
+
+// 特效设置存档文件
+const EFFECTS_FILE: &str = "effects.txt";
+
+// 特效强度 粒子数量 震屏幅度与顿帧时长按比例缩放 0 为关闭
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct EffectSettings {
+    pub intensity: f32,
+}
+
+impl Default for EffectSettings {
+    fn default() -> Self {
+        Self { intensity: 1.0 }
+    }
+}
+
+impl EffectSettings {
+    pub fn parse(source: &str) -> Self {
+        let mut settings = Self::default();
+
+        for (key, value) in parse_key_values(source) {
+            if key == "intensity" {
+                if let Ok(intensity) = value.parse::<f32>() {
+                    settings.intensity = intensity.clamp(0.0, 1.0);
+                }
+            }
+        }
+
+        settings
+    }
+
+    pub fn to_source(&self) -> String {
+        format_key_values(
+            "Smart Block effects",
+            vec![(String::from("intensity"), format!("{:.2}", self.intensity))],
+        )
+    }
+}
+
+// 下一档强度 最大后回到 0
+pub fn next_intensity(intensity: f32) -> f32 {
+    let steps = (1.0 / EFFECT_INTENSITY_STEP).round() as u32;
+    let step = (intensity / EFFECT_INTENSITY_STEP).round() as u32;
+    ((step + 1) % (steps + 1)) as f32 * EFFECT_INTENSITY_STEP
+}
+
+// 粒子 按速度与重力移动 淡出与销毁交给补间
+#[derive(Component)]
+pub struct Particle {
+    pub velocity: Vec2,
+    pub gravity: f32,
+}
+
+// 一次粒子爆发 数量为完整强度下的数量
+#[derive(Debug, Clone, Copy)]
+pub struct Burst {
+    pub count: usize,
+    pub speed: f32,
+    pub lifetime: f32,
+    pub size: f32,
+    pub color: Color,
+}
+
+// 消除 颜色取方块种类
+const BURST_REMOVAL: Burst = Burst {
+    count: 12,
+    speed: 260.0,
+    lifetime: 0.6,
+    size: 8.0,
+    color: Color::WHITE,
+};
+
+// 撞墙
+const BURST_WALL: Burst = Burst {
+    count: 6,
+    speed: 180.0,
+    lifetime: 0.3,
+    size: 6.0,
+    color: Color::rgb(0.85, 0.85, 0.85),
+};
+
+// 落地
+const BURST_GROUND: Burst = Burst {
+    count: 8,
+    speed: 160.0,
+    lifetime: 0.4,
+    size: 6.0,
+    color: Color::rgb(0.6, 0.45, 0.3),
+};
+
+// 震屏 强度随时间衰减 位移与强度平方成正比
+#[derive(Resource, Debug, Default)]
+pub struct CameraShake {
+    pub trauma: f32,
+}
+
+// 顿帧 暂停游戏时间 按真实时间计时
+#[derive(Resource, Debug, Default)]
+pub struct HitStop {
+    pub remaining: f32,
+}
+
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(
+            read_data(EFFECTS_FILE)
+                .map(|source| EffectSettings::parse(&source))
+                .unwrap_or_default(),
+        )
+        .init_resource::<CameraShake>()
+        .init_resource::<HitStop>()
+        .add_systems(
+            Update,
+            (
+                spawn_removal_particles,
+                spawn_impact_particles,
+                trigger_big_removal,
+                update_particles,
+            )
+                .run_if(in_state(GameState::InGame)),
+        )
+        .add_systems(
+            Update,
+            (
+                apply_camera_shake,
+                update_hit_stop,
+                save_effect_settings.run_if(resource_changed::<EffectSettings>),
+            ),
+        );
+    }
+}
+
+// 生成一次粒子爆发 坐标相对棋盘
+fn spawn_burst(commands: &mut Commands, board: Entity, pos: Vec2, burst: Burst, intensity: f32) {
+    let mut rng = rand::thread_rng();
+    let count = (burst.count as f32 * intensity).round() as usize;
+
+    for _ in 0..count {
+        // 向四周散开 略微偏向上方
+        let direction = Vec2::from_angle(rng.gen_range(0.0..TAU));
+        let velocity =
+            direction * burst.speed * rng.gen_range(0.5..1.0) + Vec2::Y * burst.speed * 0.5;
+        let lifetime = burst.lifetime * rng.gen_range(0.7..1.0);
+
+        let particle = commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: burst.color,
+                        custom_size: Some(Vec2::splat(burst.size)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(pos.extend(EFFECT_PARTICLE_Z)),
+                    ..default()
+                },
+                Particle {
+                    velocity,
+                    gravity: EFFECT_PARTICLE_GRAVITY,
+                },
+                Tween::new(lifetime, Ease::QuadIn)
+                    .with(TweenTarget::Alpha { from: 1.0, to: 0.0 })
+                    .despawn_on_complete(),
+                GameEntity,
+            ))
+            .id();
+        commands.entity(board).add_child(particle);
+    }
+}
+
+// 消除的方块碎成种类颜色的粒子
+fn spawn_removal_particles(
+    mut commands: Commands,
+    mut hit_events: EventReader<BlockHit>,
+    settings: Res<EffectSettings>,
+    accessibility: Res<AccessibilitySettings>,
+    block_query: Query<(&Transform, &TextureAtlas), With<Block>>,
+) {
+    for event in hit_events.read() {
+        if event.result != HitResult::Remove || settings.intensity <= 0.0 {
+            continue;
+        }
+        let Ok((transform, atlas)) = block_query.get(event.block) else {
+            continue;
+        };
+
+        let color = BlockKind::from_atlas_index(atlas.index)
+            .map(|kind| accessibility.kind_color(kind))
+            .unwrap_or(BURST_REMOVAL.color);
+        spawn_burst(
+            &mut commands,
+            event.board,
+            transform.translation.truncate(),
+            Burst {
+                color,
+                ..BURST_REMOVAL
+            },
+            settings.intensity,
+        );
+    }
+}
+
+// 手里方块撞墙与落地时溅起粒子
+fn spawn_impact_particles(
+    mut commands: Commands,
+    mut bounce_events: EventReader<WallBounce>,
+    mut ground_events: EventReader<GroundHit>,
+    settings: Res<EffectSettings>,
+    hand_block_query: Query<(&Transform, &InBoard), With<HandBlock>>,
+) {
+    let impacts = bounce_events
+        .read()
+        .map(|event| (event.board, BURST_WALL))
+        .chain(
+            ground_events
+                .read()
+                .map(|event| (event.board, BURST_GROUND)),
+        )
+        .collect::<Vec<_>>();
+    if settings.intensity <= 0.0 {
+        return;
+    }
+
+    for (board, burst) in impacts {
+        let Some((transform, _)) = hand_block_query
+            .iter()
+            .find(|(_, in_board)| in_board.0 == board)
+        else {
+            continue;
+        };
+
+        spawn_burst(
+            &mut commands,
+            board,
+            transform.translation.truncate(),
+            burst,
+            settings.intensity,
+        );
+    }
+}
+
+// 键盘玩家大量消除时震屏并顿帧 演示与 AI 不触发
+fn trigger_big_removal(
+    mut removed_events: EventReader<BlocksRemoved>,
+    settings: Res<EffectSettings>,
+    mut shake: ResMut<CameraShake>,
+    mut hit_stop: ResMut<HitStop>,
+    board_query: Query<&Controller>,
+) {
+    for event in removed_events.read() {
+        if event.count < EFFECT_BIG_REMOVAL
+            || settings.intensity <= 0.0
+            || !board_query
+                .get(event.board)
+                .is_ok_and(|controller| *controller == Controller::Keyboard)
+        {
+            continue;
+        }
+
+        let trauma = event.count as f32 / (EFFECT_BIG_REMOVAL * 2) as f32;
+        shake.trauma = (shake.trauma + trauma).min(1.0);
+        hit_stop.remaining = EFFECT_HIT_STOP_SEC * settings.intensity;
+    }
+}
+
+// 粒子移动
+fn update_particles(time: Res<Time>, mut query: Query<(&mut Transform, &mut Particle)>) {
+    let delta = time.delta_seconds();
+    for (mut transform, mut particle) in query.iter_mut() {
+        particle.velocity.y -= particle.gravity * delta;
+        transform.translation += (particle.velocity * delta).extend(0.0);
+    }
+}
+
+// 震屏 按真实时间衰减 顿帧时也在晃动
+fn apply_camera_shake(
+    time: Res<Time<Real>>,
+    settings: Res<EffectSettings>,
+    mut shake: ResMut<CameraShake>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    if shake.trauma <= 0.0 {
+        for mut transform in camera_query.iter_mut() {
+            if transform.translation.truncate() != Vec2::ZERO {
+                transform.translation.x = 0.0;
+                transform.translation.y = 0.0;
+            }
+        }
+        return;
+    }
+
+    let mut rng = rand::thread_rng();
+    let amount = shake.trauma * shake.trauma * EFFECT_SHAKE_PX * settings.intensity;
+    for mut transform in camera_query.iter_mut() {
+        transform.translation.x = rng.gen_range(-1.0..1.0) * amount;
+        transform.translation.y = rng.gen_range(-1.0..1.0) * amount;
+    }
+
+    shake.trauma = (shake.trauma - EFFECT_SHAKE_DECAY * time.delta_seconds()).max(0.0);
+}
+
+// 顿帧 暂停虚拟时间 结束后恢复
+fn update_hit_stop(
+    real_time: Res<Time<Real>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut hit_stop: ResMut<HitStop>,
+) {
+    if hit_stop.remaining <= 0.0 {
+        return;
+    }
+
+    if !virtual_time.is_paused() {
+        virtual_time.pause();
+    }
+    hit_stop.remaining -= real_time.delta_seconds();
+    if hit_stop.remaining <= 0.0 {
+        virtual_time.unpause();
+    }
+}
+
+fn save_effect_settings(settings: Res<EffectSettings>) {
+    if settings.is_added() {
+        return;
+    }
+
+    if let Err(e) = write_data(EFFECTS_FILE, &settings.to_source()) {
+        warn!("failed to save effect settings: {}", e);
+    }
+}
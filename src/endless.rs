@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use rand::Rng;
+use std::time::Duration;
+
+use crate::alert::AlertEvent;
+use crate::block::{block_bundle, grid_from_blocks, Block};
+use crate::board::{spawn_boards, Board, InBoard};
+use crate::player::InputLocked;
+use crate::resources::GlobalTextAtlas;
+use crate::rules::{cell_to_pos, BLOCK_GROUP_ROWS};
+use crate::stage::Stage;
+use crate::state::{BlockGroupState, BoardState, GameMode, GameState, HandBlockState};
+use crate::*;
+
+// 无尽模式 定时从右侧推入新的方块列 间隔随等级缩短 方块列到达梯子时结束
+#[derive(Component)]
+pub struct Endless {
+    push_timer: Timer,
+    // 已推入的列数
+    pushes: u32,
+    // 结束后返回主菜单的计时
+    over_timer: Option<Timer>,
+}
+
+impl Default for Endless {
+    fn default() -> Self {
+        Self {
+            push_timer: Timer::from_seconds(push_interval(1), TimerMode::Once),
+            pushes: 0,
+            over_timer: None,
+        }
+    }
+}
+
+impl Endless {
+    pub fn level(&self) -> usize {
+        (self.pushes / ENDLESS_COLUMNS_PER_LEVEL) as usize + 1
+    }
+
+    pub fn pushes(&self) -> u32 {
+        self.pushes
+    }
+
+    // 距下次推入已过去的时间
+    pub fn push_elapsed(&self) -> f32 {
+        self.push_timer.elapsed_secs()
+    }
+
+    // 读档恢复推入进度
+    pub fn resume(pushes: u32, push_elapsed: f32) -> Self {
+        let mut endless = Self {
+            pushes,
+            ..default()
+        };
+        let interval = push_interval(endless.level());
+        endless
+            .push_timer
+            .set_duration(Duration::from_secs_f32(interval));
+        endless
+            .push_timer
+            .set_elapsed(Duration::from_secs_f32(push_elapsed.min(interval)));
+        endless
+    }
+}
+
+// 推入间隔 每升一级乘以 ENDLESS_SPEEDUP
+pub fn push_interval(level: usize) -> f32 {
+    (ENDLESS_PUSH_SEC * ENDLESS_SPEEDUP.powi(level as i32 - 1)).max(ENDLESS_PUSH_MIN_SEC)
+}
+
+pub struct EndlessPlugin;
+
+impl Plugin for EndlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::GameInit),
+            setup_endless
+                .after(spawn_boards)
+                .run_if(resource_equals(GameMode::Endless)),
+        )
+        .add_systems(
+            Update,
+            (push_block_column, end_endless_run)
+                .run_if(resource_equals(GameMode::Endless))
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+pub fn setup_endless(mut commands: Commands, board_query: Query<Entity, With<Board>>) {
+    for board in board_query.iter() {
+        commands.entity(board).insert(Endless::default());
+    }
+}
+
+// 计时结束后推入新列 等待手里方块静止且方块不在下落
+#[allow(clippy::type_complexity)]
+fn push_block_column(
+    time: Res<Time>,
+    mut commands: Commands,
+    handle: Res<GlobalTextAtlas>,
+    mut alert_event: EventWriter<AlertEvent>,
+    mut board_query: Query<(
+        Entity,
+        &mut Endless,
+        &mut Stage,
+        &BoardState<HandBlockState>,
+        &BoardState<BlockGroupState>,
+    )>,
+    block_query: Query<(&Transform, &TextureAtlas, &InBoard), With<Block>>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for (board, mut endless, mut stage, hand_state, group_state) in board_query.iter_mut() {
+        if endless.over_timer.is_some() || !endless.push_timer.tick(time.delta()).finished() {
+            continue;
+        }
+
+        if !hand_state.is(HandBlockState::Idle) || !group_state.is(BlockGroupState::Static) {
+            continue;
+        }
+
+        let height = rng.gen_range(1..=BLOCK_GROUP_ROWS);
+        let kinds = (0..height)
+            .map(|_| rng.gen_range(BLOCK_DISPLAY_RANGE))
+            .collect::<Vec<_>>();
+
+        let mut grid = grid_from_blocks(board, &block_query);
+        let Some(cells) = grid.push_column(&kinds) else {
+            // 方块到达梯子 本局结束
+            endless.over_timer = Some(Timer::from_seconds(ENDLESS_OVER_SEC, TimerMode::Once));
+            commands.entity(board).insert(InputLocked);
+            alert_event.send(AlertEvent {
+                board,
+                key: Some(String::from("endless.over")),
+            });
+            continue;
+        };
+
+        commands.entity(board).with_children(|parent| {
+            for (cell, index) in cells.iter().zip(kinds) {
+                parent.spawn(block_bundle(&handle, board, cell_to_pos(*cell), index));
+            }
+        });
+
+        endless.pushes += 1;
+        stage.0 = endless.level();
+
+        let interval = push_interval(endless.level());
+        endless
+            .push_timer
+            .set_duration(Duration::from_secs_f32(interval));
+        endless.push_timer.reset();
+    }
+}
+
+// 结束提示后返回主菜单 成绩由排行榜记录
+fn end_endless_run(
+    time: Res<Time>,
+    mut board_query: Query<&mut Endless>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for mut endless in board_query.iter_mut() {
+        let Some(timer) = endless.over_timer.as_mut() else {
+            continue;
+        };
+
+        if timer.tick(time.delta()).just_finished() {
+            next_state.set(GameState::MainMenu);
+        }
+    }
+}
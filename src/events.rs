@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+
+use crate::block::BlockKind;
+use crate::rules::HitResult;
+
+// 玩法事件 由规则系统发出
+// 音效 分数 统计 成就等各自订阅 规则系统不直接依赖它们
+
+// 玩家上下移动一格
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerMoved {
+    pub board: Entity,
+}
+
+// 开始投掷 hand 为投出的手里方块种类
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ThrowStarted {
+    pub board: Entity,
+    pub hand: usize,
+}
+
+// 手里方块碰到方块
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BlockHit {
+    pub board: Entity,
+    pub block: Entity,
+    pub result: HitResult,
+}
+
+// 一次投掷消除了方块
+#[derive(Event, Debug, Clone)]
+pub struct BlocksRemoved {
+    pub board: Entity,
+    pub count: usize,
+    // 被消除方块的种类
+    pub kinds: Vec<BlockKind>,
+    // 被消除方块的中心 棋盘内坐标
+    pub center: Vec2,
+}
+
+// 手里方块与被碰方块交换种类
+#[derive(Event, Debug, Clone, Copy)]
+pub struct HandBlockSwapped {
+    pub board: Entity,
+    pub block: Entity,
+    // 投出时的种类
+    pub from: usize,
+    // 交换后的种类
+    pub to: usize,
+}
+
+// 手里方块被弹回 hand 为返回后的种类
+#[derive(Event, Debug, Clone, Copy)]
+pub struct HandBlockReturned {
+    pub board: Entity,
+    pub hand: usize,
+}
+
+// 手里方块碰到墙壁转向下
+#[derive(Event, Debug, Clone, Copy)]
+pub struct WallBounce {
+    pub board: Entity,
+}
+
+// 手里方块落到地面 随后返回
+#[derive(Event, Debug, Clone, Copy)]
+pub struct GroundHit {
+    pub board: Entity,
+}
+
+// 剩余方块达到过关数
+#[derive(Event, Debug, Clone, Copy)]
+pub struct StageCleared {
+    pub board: Entity,
+}
+
+// 手里方块返回后没有可消除的方块且未过关
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerStuck {
+    pub board: Entity,
+}
+
+// 无法消除时补给了闪电块
+#[derive(Event, Debug, Clone, Copy)]
+pub struct LightningRescued {
+    pub board: Entity,
+}
+
+pub struct GameplayEventsPlugin;
+
+impl Plugin for GameplayEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerMoved>()
+            .add_event::<ThrowStarted>()
+            .add_event::<BlockHit>()
+            .add_event::<BlocksRemoved>()
+            .add_event::<HandBlockSwapped>()
+            .add_event::<HandBlockReturned>()
+            .add_event::<WallBounce>()
+            .add_event::<GroundHit>()
+            .add_event::<StageCleared>()
+            .add_event::<PlayerStuck>()
+            .add_event::<LightningRescued>();
+    }
+}
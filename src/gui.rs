@@ -1,434 +1,447 @@
-use bevy::math::vec3;
 use bevy::prelude::*;
 
+use crate::accessibility::ScalableText;
 use crate::block::Block;
+use crate::board::{BoardUi, InBoard};
+use crate::events::BlocksRemoved;
+use crate::locale::LocalizedText;
+use crate::puzzle::PuzzleRun;
+use crate::rules::{removal_breakdown, removal_score};
 use crate::stage::Stage;
-use crate::state::{GameState, SettlementState};
+use crate::state::{BoardState, GameMode, GameState, SettlementState};
+use crate::tween::{Ease, Tween, TweenTarget};
 use crate::world::GameEntity;
 use crate::*;
 
 pub struct GuiPlugin;
 
-// UI 分数
-#[derive(Component)]
-pub struct TextScore;
-
-#[derive(Resource)]
+// 棋盘分数
+#[derive(Component, Default)]
 pub struct Score {
     pub total_score: u32,
-    pub once_remove_block: u32,
 }
 
-impl Default for Score {
+// 得分提示 加速上浮淡出后销毁
+#[derive(Component)]
+pub struct ScorePopup;
+
+// 棋盘上最近一条得分提示 消除中心相近时新提示叠在其上方
+#[derive(Component, Default)]
+pub struct LastScorePopup {
+    popup: Option<Entity>,
+    // 消除中心
+    center: Vec2,
+    // 生成时的高度 本帧刚生成的提示还查询不到位置
+    spawn_y: f32,
+}
+
+// 棋盘倒计时
+#[derive(Component)]
+pub struct CountDown(pub Timer);
+
+impl Default for CountDown {
     fn default() -> Self {
-        Self {
-            total_score: 0,
-            once_remove_block: 0,
-        }
+        Self(Timer::from_seconds(COUNT_DOWN_SEC, TimerMode::Repeating))
     }
 }
 
-// 高分展示
-#[derive(Component)]
-pub struct HighScore {
-    pub animation_timer: Timer,
+// HUD 数值来源 数值文本根据来源自动刷新
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HudValue {
+    Score,
+    Clear,
+    BlockCount,
+    CountDown,
+    Stage,
+    // 解谜 投掷数/标准投掷数
+    Throws,
 }
-impl Default for HighScore {
-    fn default() -> Self {
-        Self {
-            animation_timer: Timer::from_seconds(HIGH_SCORE_ANIMATION_DURATION, TimerMode::Once),
+
+impl HudValue {
+    // 无尽模式没有过关目标 只有计时模式显示倒计时 投掷数仅解谜显示
+    pub fn shown_in(&self, mode: GameMode) -> bool {
+        match self {
+            HudValue::Clear => mode != GameMode::Endless,
+            HudValue::CountDown => mode.is_timed(),
+            HudValue::Throws => mode == GameMode::Puzzle,
+            _ => true,
         }
     }
 }
 
-#[derive(Component)]
-pub struct TextCountDown;
+// HUD 面板锚点 相对所属棋盘游戏区域边缘的像素偏移
+#[derive(Debug, Clone, Copy)]
+pub enum HudAnchor {
+    TopLeft(f32, f32),
+    TopRight(f32, f32),
+}
 
-#[derive(Component)]
-pub struct ClearNum;
+// HUD 面板 标签(本地化键) + 数值
+#[derive(Debug, Clone)]
+pub struct HudPanel {
+    pub label: Option<&'static str>,
+    pub value_source: HudValue,
+    pub anchor: HudAnchor,
+    pub width: f32,
+    pub height: Option<f32>,
+    pub font_size: f32,
+    pub direction: FlexDirection,
+}
 
-#[derive(Component)]
-pub struct BlockNum;
+impl HudPanel {
+    // 横向面板 标签与数值同一行
+    pub fn row(label: &'static str, value_source: HudValue, anchor: HudAnchor) -> Self {
+        Self {
+            label: Some(label),
+            value_source,
+            anchor,
+            width: SCORE_BLOCK_WIDTH,
+            height: None,
+            font_size: 28.0,
+            direction: FlexDirection::Row,
+        }
+    }
 
+    // 纵向面板 标签在上 数值在下
+    pub fn column(label: Option<&'static str>, value_source: HudValue, anchor: HudAnchor) -> Self {
+        Self {
+            label,
+            value_source,
+            anchor,
+            width: RIGHT_BLOCK_WIDTH,
+            height: Some(RIGHT_BLOCK_HEIGHT),
+            font_size: 32.0,
+            direction: FlexDirection::Column,
+        }
+    }
+
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+}
+
+// HUD 布局 新增面板只需在此追加
 #[derive(Resource)]
-pub struct CountDown(pub Timer);
+pub struct HudLayout(pub Vec<HudPanel>);
 
-impl Default for CountDown {
+impl Default for HudLayout {
     fn default() -> Self {
-        Self(Timer::from_seconds(COUNT_DOWN_SEC, TimerMode::Repeating))
+        let (score_x, score_y) = SCORE_BLOCK_POS;
+        let (clear_x, clear_y) = CLEAR_BLOCK_POS;
+        let (block_x, block_y) = BLOCK_BLOCK_POS;
+        let (count_down_x, count_down_y) = COUNT_DOWN_BLOCK_POS;
+        let (stage_x, stage_y) = STAGE_BLOCK_POS;
+
+        Self(vec![
+            // 分数
+            HudPanel::row(
+                SCORE_TEXT,
+                HudValue::Score,
+                HudAnchor::TopLeft(score_x, score_y),
+            ),
+            // 获胜剩余数
+            HudPanel::column(
+                Some(CLEAR_TEXT),
+                HudValue::Clear,
+                HudAnchor::TopRight(clear_x, clear_y),
+            ),
+            // 方块数
+            HudPanel::column(
+                Some(BLOCK_TEXT),
+                HudValue::BlockCount,
+                HudAnchor::TopRight(block_x, block_y),
+            ),
+            // 倒计时
+            HudPanel::column(
+                None,
+                HudValue::CountDown,
+                HudAnchor::TopRight(count_down_x, count_down_y),
+            )
+            .with_font_size(56.0),
+            // 投掷数 与倒计时共用位置
+            HudPanel::column(
+                Some(THROWS_TEXT),
+                HudValue::Throws,
+                HudAnchor::TopRight(count_down_x, count_down_y),
+            ),
+            // 当前关卡
+            HudPanel::column(
+                Some(STAGE_TEXT),
+                HudValue::Stage,
+                HudAnchor::TopRight(stage_x, stage_y),
+            ),
+        ])
     }
 }
 
 impl Plugin for GuiPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<CountDown>()
-            .init_resource::<Score>()
+        app.init_resource::<HudLayout>()
             .add_systems(OnEnter(GameState::InGame), spawn_gui)
             .add_systems(
                 Update,
-                (update_score, update_count_down)
-                    .run_if(in_state(GameState::InGame))
-                    .run_if(in_state(SettlementState::Not)),
-            )
-            .add_systems(
-                Update,
-                (update_count_down_text, update_block_number).run_if(in_state(GameState::InGame)),
-            )
-            .add_systems(OnEnter(GameState::MainMenu), reset_resources);
+                (update_score, update_count_down, update_hud_values)
+                    .run_if(in_state(GameState::InGame)),
+            );
     }
 }
 
-// 生成游戏内UI
+// 生成游戏内UI 每个棋盘一套 挂在棋盘 UI 节点下
 fn spawn_gui(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    stage: Res<Stage>,
-    score: Res<Score>,
+    layout: Res<HudLayout>,
+    mode: Res<GameMode>,
+    ui_query: Query<(Entity, &InBoard), With<BoardUi>>,
 ) {
-    // 分数
-    commands
-        .spawn((
-            NodeBundle {
-                background_color: Color::BLACK.into(),
-                style: Style {
-                    width: Val::Px(SCORE_BLOCK_WIDTH),
-                    display: Display::Flex,
-                    align_items: AlignItems::Center,
-                    justify_content: JustifyContent::SpaceAround,
-                    position_type: PositionType::Absolute,
-                    top: Val::Px(SCORE_BLOCK_POS.1),
-                    left: Val::Px(SCORE_BLOCK_POS.0),
-                    ..Default::default()
-                },
-                ..default()
-            },
-            GameEntity,
-        ))
-        .with_children(|parent| {
-            parent.spawn(TextBundle {
-                text: Text::from_section(
-                    SCORE_TEXT,
-                    TextStyle {
-                        font: asset_server.load(FONT_PATH),
-                        font_size: 28.0,
-                        color: Color::WHITE,
-                        ..default()
-                    },
-                ),
-                ..Default::default()
-            });
+    let font = asset_server.load(FONT_PATH);
 
-            parent.spawn((
-                TextBundle {
-                    text: Text::from_section(
-                        format!("{:0>7}", score.total_score),
-                        TextStyle {
-                            font: asset_server.load(FONT_PATH),
-                            font_size: 28.0,
-                            color: Color::WHITE,
-                            ..default()
-                        },
-                    ),
-                    ..Default::default()
-                },
-                TextScore,
-            ));
+    for (ui, in_board) in ui_query.iter() {
+        commands.entity(ui).with_children(|parent| {
+            for panel in layout.0.iter() {
+                if !panel.value_source.shown_in(*mode) {
+                    continue;
+                }
+
+                spawn_hud_panel(parent, font.clone(), panel, *in_board);
+            }
         });
+    }
+}
 
-    // 获胜剩余数
-    commands
-        .spawn((
-            NodeBundle {
-                background_color: Color::BLACK.into(),
-                style: Style {
-                    width: Val::Px(RIGHT_BLOCK_WIDTH),
-                    height: Val::Px(RIGHT_BLOCK_HEIGHT),
-                    display: Display::Flex,
-                    flex_direction: FlexDirection::Column,
-                    align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Center,
-                    position_type: PositionType::Absolute,
-                    top: Val::Px(CLEAR_BLOCK_POS.1),
-                    right: Val::Px(CLEAR_BLOCK_POS.0),
-                    ..Default::default()
-                },
-                ..default()
-            },
-            GameEntity,
-        ))
-        .with_children(|parent| {
-            parent.spawn(TextBundle {
-                text: Text::from_section(
-                    CLEAR_TEXT,
-                    TextStyle {
-                        font: asset_server.load(FONT_PATH),
-                        font_size: 32.0,
-                        color: Color::WHITE,
-                        ..default()
-                    },
-                ),
-                ..Default::default()
-            });
+// 生成单个HUD面板
+pub fn spawn_hud_panel(
+    commands: &mut ChildBuilder,
+    font: Handle<Font>,
+    panel: &HudPanel,
+    in_board: InBoard,
+) {
+    let (left, right, top) = match panel.anchor {
+        HudAnchor::TopLeft(x, y) => (Val::Px(x), Val::Auto, Val::Px(y)),
+        HudAnchor::TopRight(x, y) => (Val::Auto, Val::Px(x), Val::Px(y)),
+    };
 
-            parent.spawn((
-                TextBundle {
-                    text: Text::from_section(
-                        CLEAR_NUM.to_string(),
-                        TextStyle {
-                            font: asset_server.load(FONT_PATH),
-                            font_size: 32.0,
-                            color: Color::WHITE,
-                            ..default()
-                        },
-                    ),
-                    ..Default::default()
-                },
-                ClearNum,
-            ));
-        });
+    let justify_content = if panel.direction == FlexDirection::Row {
+        JustifyContent::SpaceAround
+    } else {
+        JustifyContent::Center
+    };
+
+    let text_style = TextStyle {
+        font,
+        font_size: panel.font_size,
+        color: Color::WHITE,
+    };
 
-    // 方块数
     commands
         .spawn((
             NodeBundle {
                 background_color: Color::BLACK.into(),
                 style: Style {
-                    width: Val::Px(RIGHT_BLOCK_WIDTH),
-                    height: Val::Px(RIGHT_BLOCK_HEIGHT),
+                    min_width: Val::Px(panel.width),
+                    height: panel.height.map(Val::Px).unwrap_or(Val::Auto),
                     display: Display::Flex,
-                    flex_direction: FlexDirection::Column,
+                    flex_direction: panel.direction,
                     align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Center,
+                    justify_content,
                     position_type: PositionType::Absolute,
-                    top: Val::Px(BLOCK_BLOCK_POS.1),
-                    right: Val::Px(BLOCK_BLOCK_POS.0),
-                    ..Default::default()
+                    top,
+                    left,
+                    right,
+                    ..default()
                 },
                 ..default()
             },
             GameEntity,
         ))
         .with_children(|parent| {
-            parent.spawn(TextBundle {
-                text: Text::from_section(
-                    BLOCK_TEXT,
-                    TextStyle {
-                        font: asset_server.load(FONT_PATH),
-                        font_size: 32.0,
-                        color: Color::WHITE,
+            if let Some(label) = panel.label {
+                parent.spawn((
+                    TextBundle {
+                        text: Text::from_section("", text_style.clone()),
                         ..default()
                     },
-                ),
-                ..Default::default()
-            });
+                    LocalizedText::new(label),
+                    ScalableText(panel.font_size),
+                ));
+            }
 
             parent.spawn((
                 TextBundle {
-                    text: Text::from_section(
-                        "16",
-                        TextStyle {
-                            font: asset_server.load(FONT_PATH),
-                            font_size: 32.0,
-                            color: Color::WHITE,
-                            ..default()
-                        },
-                    ),
-                    ..Default::default()
-                },
-                BlockNum,
-            ));
-        });
-
-    // 倒计时
-    commands
-        .spawn((
-            NodeBundle {
-                background_color: Color::BLACK.into(),
-                style: Style {
-                    width: Val::Px(RIGHT_BLOCK_WIDTH),
-                    height: Val::Px(RIGHT_BLOCK_HEIGHT),
-                    display: Display::Flex,
-                    flex_direction: FlexDirection::Column,
-                    align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Center,
-                    position_type: PositionType::Absolute,
-                    top: Val::Px(COUNT_DOWN_BLOCK_POS.1),
-                    right: Val::Px(COUNT_DOWN_BLOCK_POS.0),
-                    ..Default::default()
-                },
-                ..default()
-            },
-            GameEntity,
-        ))
-        .with_children(|parent| {
-            parent.spawn((
-                TextBundle {
-                    text: Text::from_section(
-                        "0:00",
-                        TextStyle {
-                            font: asset_server.load(FONT_PATH),
-                            font_size: 56.0,
-                            color: Color::WHITE,
-                            ..default()
-                        },
-                    ),
-                    ..Default::default()
+                    text: Text::from_section("", text_style),
+                    ..default()
                 },
-                TextCountDown,
+                panel.value_source,
+                in_board,
+                ScalableText(panel.font_size),
             ));
         });
-    // 当前关卡
-    commands
-        .spawn((
-            NodeBundle {
-                background_color: Color::BLACK.into(),
-                style: Style {
-                    width: Val::Px(RIGHT_BLOCK_WIDTH),
-                    height: Val::Px(RIGHT_BLOCK_HEIGHT),
-                    display: Display::Flex,
-                    flex_direction: FlexDirection::Column,
-                    align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Center,
-                    position_type: PositionType::Absolute,
-                    top: Val::Px(STAGE_BLOCK_POS.1),
-                    right: Val::Px(STAGE_BLOCK_POS.0),
-                    ..Default::default()
-                },
-                ..default()
-            },
-            GameEntity,
-        ))
-        .with_children(|parent| {
-            parent.spawn(TextBundle {
-                text: Text::from_section(
-                    STAGE_TEXT,
-                    TextStyle {
-                        font: asset_server.load(FONT_PATH),
-                        font_size: 32.0,
-                        color: Color::WHITE,
-                        ..default()
-                    },
-                ),
-                ..Default::default()
-            });
-
-            parent.spawn((TextBundle {
-                text: Text::from_section(
-                    stage.0.to_string(),
-                    TextStyle {
-                        font: asset_server.load(FONT_PATH),
-                        font_size: 32.0,
-                        color: Color::WHITE,
-                        ..default()
-                    },
-                ),
-                ..Default::default()
-            },));
-        });
 }
 
 // 更新分数
 fn update_score(
-    commands: Commands,
-    mut score: ResMut<Score>,
+    mut commands: Commands,
+    mut events: EventReader<BlocksRemoved>,
+    mut board_query: Query<(
+        &mut Score,
+        &mut LastScorePopup,
+        &BoardState<SettlementState>,
+    )>,
+    popup_query: Query<&Transform, With<ScorePopup>>,
     asset_server: Res<AssetServer>,
-    mut text_score_query: Query<&mut Text, With<TextScore>>,
 ) {
-    if text_score_query.is_empty() {
-        return;
-    }
-    let mut text_score = text_score_query.single_mut();
+    let mut spawned = vec![];
 
-    if score.once_remove_block > 0 {
-        let once_score = score.once_remove_block.pow(2) * ONCE_BLOCK_SCORE;
-        score.total_score += once_score;
-        if score.once_remove_block > 1 {
-            spawn_hight_score(commands, asset_server.load(FONT_PATH), once_score);
+    for event in events.read() {
+        let Ok((mut score, mut last, settlement_state)) = board_query.get_mut(event.board) else {
+            continue;
+        };
+        if !settlement_state.is(SettlementState::Not) {
+            continue;
         }
-        score.once_remove_block = 0;
-        text_score.sections[0].value = format!("{:0>7}", score.total_score);
+
+        score.total_score += removal_score(event.count as u32);
+
+        // 上一条提示未消失且位置相近时叠在其当前位置上方
+        let last_y = last
+            .popup
+            .filter(|_| last.center.distance(event.center) <= SCORE_POPUP_STACK_RANGE)
+            .and_then(|popup| match popup_query.get(popup) {
+                Ok(transform) => Some(transform.translation.y),
+                Err(_) => spawned.contains(&popup).then_some(last.spawn_y),
+            });
+        let mut pos = event.center;
+        if let Some(y) = last_y {
+            pos.y = pos.y.max(y + SCORE_POPUP_STACK_GAP);
+        }
+
+        let popup = spawn_score_popup(
+            &mut commands,
+            event.board,
+            asset_server.load(FONT_PATH),
+            pos,
+            removal_breakdown(event.count as u32),
+        );
+        spawned.push(popup);
+        *last = LastScorePopup {
+            popup: Some(popup),
+            center: event.center,
+            spawn_y: pos.y,
+        };
     }
 }
 
-// 更新block数量
-fn update_block_number(
-    mut query: Query<&mut Text, With<BlockNum>>,
-    block_query: Query<&Transform, With<Block>>,
+// 根据数值来源刷新HUD文本
+fn update_hud_values(
+    board_query: Query<(&Score, &CountDown, &Stage, Option<&PuzzleRun>)>,
+    block_query: Query<&InBoard, With<Block>>,
+    mut query: Query<(&mut Text, &HudValue, &InBoard)>,
 ) {
-    if query.is_empty() {
-        return;
-    }
-    let mut text = query.single_mut();
-    let block_num = if block_query.is_empty() {
-        0
-    } else {
-        block_query.iter().count()
-    };
+    for (mut text, value_source, in_board) in query.iter_mut() {
+        let Ok((score, count_down, stage, puzzle_run)) = board_query.get(in_board.0) else {
+            continue;
+        };
 
-    text.sections[0].value = format!("{}", block_num);
+        let value = match value_source {
+            HudValue::Score => format!("{:0>7}", score.total_score),
+            HudValue::Clear => CLEAR_NUM.to_string(),
+            HudValue::BlockCount => block_query
+                .iter()
+                .filter(|b| *b == in_board)
+                .count()
+                .to_string(),
+            HudValue::CountDown => {
+                let total_time = COUNT_DOWN_SEC - count_down.0.elapsed_secs();
+                let minite = (total_time / 60.0).floor();
+                let seconds = (total_time % 60.0).floor();
+                format!("{:0>2}:{:0>2}", minite, seconds)
+            }
+            HudValue::Stage => stage.0.to_string(),
+            HudValue::Throws => puzzle_run
+                .map(|run| format!("{}/{}", run.throws, run.par))
+                .unwrap_or_default(),
+        };
+
+        // 仅在变化时写入 避免每帧重新排版
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
 }
 
-// 更新倒计时 如果结束则跳转状态
+// 更新倒计时 任一棋盘结束则跳转状态
 fn update_count_down(
     time: Res<Time>,
-    mut count_down: ResMut<CountDown>,
+    mode: Res<GameMode>,
+    mut board_query: Query<(&mut CountDown, &BoardState<SettlementState>)>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
-    count_down.0.tick(time.delta());
-
-    if count_down.0.just_finished() {
-        count_down.0.reset();
-        next_state.set(GameState::MainMenu);
+    if !mode.is_timed() {
         return;
     }
-}
 
-// 更新倒计时文案
-fn update_count_down_text(
-    count_down: Res<CountDown>,
-    mut query: Query<&mut Text, With<TextCountDown>>,
-) {
-    if query.is_empty() {
-        return;
-    }
+    for (mut count_down, settlement_state) in board_query.iter_mut() {
+        if !settlement_state.is(SettlementState::Not) {
+            continue;
+        }
 
-    let mut text = query.single_mut();
-    let total_time = COUNT_DOWN_SEC - count_down.0.elapsed_secs();
-    let minite = (total_time / 60.0).floor();
-    let seconds = (total_time % 60.0).floor();
+        count_down.0.tick(time.delta());
 
-    text.sections[0].value = format!("{:0>2}:{:0>2}", minite, seconds);
+        if count_down.0.just_finished() {
+            count_down.0.reset();
+            next_state.set(GameState::MainMenu);
+        }
+    }
 }
 
-// 生成高分提示
-pub fn spawn_hight_score(mut commands: Commands, font_handle: Handle<Font>, score_value: u32) {
-    let (x, y) = HIGH_SCORE_POS_PERCENT;
-    commands.spawn((
-        Text2dBundle {
-            transform: Transform::from_translation(vec3(x, y, 1.0)),
-            text: Text::from_section(
-                score_value.to_string(),
-                TextStyle {
-                    font: font_handle.clone_weak(),
-                    font_size: 72.0,
-                    color: Color::YELLOW,
-                    ..default()
-                },
-            ),
-            ..Default::default()
-        },
-        HighScore::default(),
-        GameEntity,
-    ));
-}
+// 生成得分提示 总分与基础分乘倍率 显示在墙体之上
+pub fn spawn_score_popup(
+    commands: &mut Commands,
+    board: Entity,
+    font: Handle<Font>,
+    pos: Vec2,
+    (base, multiplier): (u32, u32),
+) -> Entity {
+    let from = pos.extend(SCORE_POPUP_Z);
+    let popup = commands
+        .spawn((
+            Text2dBundle {
+                transform: Transform::from_translation(from),
+                text: Text::from_sections([
+                    TextSection::new(
+                        format!("+{}", base * multiplier),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: SCORE_POPUP_FONT_SIZE,
+                            color: Color::YELLOW,
+                        },
+                    ),
+                    TextSection::new(
+                        format!("\n{} x{}", base, multiplier),
+                        TextStyle {
+                            font,
+                            font_size: SCORE_POPUP_FONT_SIZE / 2.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                ])
+                .with_justify(JustifyText::Center),
+                ..Default::default()
+            },
+            ScorePopup,
+            Tween::new(SCORE_POPUP_DURATION, Ease::QuadIn)
+                .with(TweenTarget::Translation {
+                    from,
+                    to: from + Vec3::Y * SCORE_POPUP_RISE,
+                })
+                .with(TweenTarget::Alpha { from: 1.0, to: 0.0 })
+                .despawn_on_complete(),
+            InBoard(board),
+            GameEntity,
+        ))
+        .id();
+    commands.entity(board).add_child(popup);
 
-// 重置资源
-fn reset_resources(
-    mut total_score: ResMut<Score>,
-    mut stage: ResMut<Stage>,
-    mut count_down: ResMut<CountDown>,
-) {
-    total_score.total_score = 0;
-    stage.0 = 1;
-    count_down.0.reset();
+    popup
 }
@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+
+use crate::leaderboard::Leaderboard;
+use crate::locale::LocalizedText;
+use crate::menu::{
+    despawn_screen, menu_root, spawn_back_button, spawn_menu_title, spawn_text_button,
+    MenuActivated,
+};
+use crate::state::{GameMode, GameState};
+use crate::*;
+
+#[derive(Component)]
+struct HighScoresItem;
+
+// 排行榜界面按钮 统计与成就界面也从这里进入
+#[derive(Component, Debug, Clone, Copy)]
+enum HighScoresButton {
+    Statistics,
+    Achievements,
+    Back,
+}
+
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::HighScores), setup_high_scores)
+            .add_systems(
+                OnExit(GameState::HighScores),
+                despawn_screen::<HighScoresItem>,
+            )
+            .add_systems(
+                Update,
+                handle_high_scores_buttons.run_if(in_state(GameState::HighScores)),
+            );
+    }
+}
+
+// 生成排行榜界面 经典与无尽模式并排
+fn setup_high_scores(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    leaderboard: Res<Leaderboard>,
+) {
+    let font = asset_server.load(FONT_PATH);
+
+    commands
+        .spawn((menu_root(12.0), HighScoresItem))
+        .with_children(|parent| {
+            spawn_menu_title(parent, LocalizedText::new("high_scores.title"));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(48.0),
+                        margin: UiRect::bottom(Val::Px(16.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (key, mode) in [
+                        ("high_scores.classic", GameMode::Single),
+                        ("high_scores.endless", GameMode::Endless),
+                    ] {
+                        spawn_high_score_column(parent, &font, key, &leaderboard, mode);
+                    }
+                });
+
+            spawn_text_button(parent, "menu.stats", HighScoresButton::Statistics);
+            spawn_text_button(parent, "menu.achievements", HighScoresButton::Achievements);
+            spawn_back_button(parent, HighScoresButton::Back);
+        });
+}
+
+// 一个模式的名次列表 无记录时显示占位
+fn spawn_high_score_column(
+    parent: &mut ChildBuilder,
+    font: &Handle<Font>,
+    key: &'static str,
+    leaderboard: &Leaderboard,
+    mode: GameMode,
+) {
+    let text_style = |font_size| TextStyle {
+        font: font.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(HIGH_SCORES_COLUMN_W),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section("", text_style(32.0)),
+                LocalizedText::new(key),
+            ));
+
+            let entries = leaderboard.entries(mode);
+            if entries.is_empty() {
+                parent.spawn((
+                    TextBundle::from_section("", text_style(24.0)),
+                    LocalizedText::new("high_scores.empty"),
+                ));
+            }
+
+            for (rank, entry) in entries.iter().enumerate() {
+                parent.spawn((
+                    TextBundle::from_section("", text_style(24.0)),
+                    LocalizedText::new("high_scores.entry").with_args(vec![
+                        (rank + 1).to_string(),
+                        entry.score.to_string(),
+                        entry.level.to_string(),
+                    ]),
+                ));
+            }
+        });
+}
+
+fn handle_high_scores_buttons(
+    mut activated_events: EventReader<MenuActivated>,
+    button_query: Query<&HighScoresButton>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in activated_events.read() {
+        let Ok(button) = button_query.get(event.0) else {
+            continue;
+        };
+
+        next_state.set(match button {
+            HighScoresButton::Statistics => GameState::Statistics,
+            HighScoresButton::Achievements => GameState::Achievements,
+            HighScoresButton::Back => GameState::MainMenu,
+        });
+    }
+}
@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+
+use crate::board::{despawn_boards, Board};
+use crate::gui::Score;
+use crate::player::Controller;
+use crate::stage::Stage;
+use crate::state::{GameMode, GameState};
+use crate::storage::{format_key_values, parse_key_values, read_data, write_data};
+use crate::*;
+
+// 排行榜存档文件
+const LEADERBOARD_FILE: &str = "leaderboard.txt";
+
+// 排行榜记录 分数及到达的关卡(无尽模式为等级)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeaderboardEntry {
+    pub score: u32,
+    pub level: usize,
+}
+
+// 排行榜 单人与无尽模式各保存前 LEADERBOARD_SIZE 名
+#[derive(Resource, Debug, Default)]
+pub struct Leaderboard {
+    pub classic: Vec<LeaderboardEntry>,
+    pub endless: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    // 参与排行的模式及存档键
+    fn mode_key(mode: GameMode) -> Option<&'static str> {
+        match mode {
+            GameMode::Single => Some("classic"),
+            GameMode::Endless => Some("endless"),
+            _ => None,
+        }
+    }
+
+    fn entries_mut(&mut self, mode: GameMode) -> Option<&mut Vec<LeaderboardEntry>> {
+        match mode {
+            GameMode::Single => Some(&mut self.classic),
+            GameMode::Endless => Some(&mut self.endless),
+            _ => None,
+        }
+    }
+
+    pub fn entries(&self, mode: GameMode) -> &[LeaderboardEntry] {
+        match mode {
+            GameMode::Single => &self.classic,
+            GameMode::Endless => &self.endless,
+            _ => &[],
+        }
+    }
+
+    // 记录一局成绩 返回名次 从 1 开始 未上榜返回 None
+    pub fn record(&mut self, mode: GameMode, entry: LeaderboardEntry) -> Option<usize> {
+        if entry.score == 0 {
+            return None;
+        }
+
+        let entries = self.entries_mut(mode)?;
+        let rank = entries
+            .iter()
+            .position(|e| entry.score > e.score)
+            .unwrap_or(entries.len());
+
+        if rank >= LEADERBOARD_SIZE {
+            return None;
+        }
+
+        entries.insert(rank, entry);
+        entries.truncate(LEADERBOARD_SIZE);
+        Some(rank + 1)
+    }
+
+    // 每行 `模式.名次 = 分数 关卡`
+    pub fn parse(source: &str) -> Self {
+        let mut leaderboard = Self::default();
+
+        for (key, value) in parse_key_values(source) {
+            let Some((mode_key, _)) = key.split_once('.') else {
+                continue;
+            };
+            let mut values = value.split_whitespace().map(str::parse::<u64>);
+            let (Some(Ok(score)), Some(Ok(level))) = (values.next(), values.next()) else {
+                continue;
+            };
+
+            let entry = LeaderboardEntry {
+                score: score as u32,
+                level: level as usize,
+            };
+            match mode_key {
+                "classic" => leaderboard.classic.push(entry),
+                "endless" => leaderboard.endless.push(entry),
+                _ => {}
+            }
+        }
+
+        for entries in [&mut leaderboard.classic, &mut leaderboard.endless] {
+            entries.sort_by_key(|e| std::cmp::Reverse(e.score));
+            entries.truncate(LEADERBOARD_SIZE);
+        }
+
+        leaderboard
+    }
+
+    pub fn to_source(&self) -> String {
+        let pairs = [GameMode::Single, GameMode::Endless]
+            .into_iter()
+            .filter_map(|mode| Some((Self::mode_key(mode)?, self.entries(mode))))
+            .flat_map(|(mode_key, entries)| {
+                entries.iter().enumerate().map(move |(i, e)| {
+                    (
+                        format!("{}.{}", mode_key, i + 1),
+                        format!("{} {}", e.score, e.level),
+                    )
+                })
+            });
+
+        format_key_values("Smart Block leaderboard", pairs)
+    }
+}
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(
+            read_data(LEADERBOARD_FILE)
+                .map(|source| Leaderboard::parse(&source))
+                .unwrap_or_default(),
+        )
+        // 棋盘在回到主菜单时销毁 需在此之前记录
+        .add_systems(
+            OnEnter(GameState::MainMenu),
+            record_leaderboard.before(despawn_boards),
+        );
+    }
+}
+
+// 一局结束 记录键盘玩家的成绩 演示与对战不计入
+fn record_leaderboard(
+    mode: Res<GameMode>,
+    mut leaderboard: ResMut<Leaderboard>,
+    board_query: Query<(&Score, &Stage, &Controller), With<Board>>,
+) {
+    let mut changed = false;
+
+    for (score, stage, controller) in board_query.iter() {
+        if *controller != Controller::Keyboard {
+            continue;
+        }
+
+        let entry = LeaderboardEntry {
+            score: score.total_score,
+            level: stage.0,
+        };
+        if let Some(rank) = leaderboard.record(*mode, entry) {
+            info!("leaderboard rank {}: {:?}", rank, entry);
+            changed = true;
+        }
+    }
+
+    if changed {
+        if let Err(e) = write_data(LEADERBOARD_FILE, &leaderboard.to_source()) {
+            warn!("failed to save leaderboard: {}", e);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+
+use crate::locale::LocalizedText;
+use crate::menu::{
+    despawn_screen, menu_button, menu_root, spawn_back_button, spawn_menu_title, MenuActivated,
+    MenuButton,
+};
+use crate::puzzle::{stars_text, PuzzlePack, PuzzleProgress, Puzzles};
+use crate::state::GameState;
+use crate::*;
+
+#[derive(Component)]
+struct LevelSelectItem;
+
+// 选关按钮 关卡序号
+#[derive(Component, Debug, Clone, Copy)]
+enum LevelSelectButton {
+    Level(usize),
+    Back,
+}
+
+pub struct LevelSelectPlugin;
+
+impl Plugin for LevelSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::LevelSelect), setup_level_select)
+            .add_systems(
+                OnExit(GameState::LevelSelect),
+                despawn_screen::<LevelSelectItem>,
+            )
+            .add_systems(
+                Update,
+                handle_level_select_buttons.run_if(in_state(GameState::LevelSelect)),
+            );
+    }
+}
+
+// 生成选关界面 每个关卡显示序号及获得的星级
+fn setup_level_select(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    puzzles: Res<Puzzles>,
+    packs: Res<Assets<PuzzlePack>>,
+    progress: Res<PuzzleProgress>,
+) {
+    let font = asset_server.load(FONT_PATH);
+    let levels = packs
+        .get(&puzzles.pack)
+        .map(|pack| pack.0.as_slice())
+        .unwrap_or_default();
+
+    commands
+        .spawn((menu_root(24.0), LevelSelectItem))
+        .with_children(|parent| {
+            spawn_menu_title(parent, LocalizedText::new("puzzle.select"));
+
+            // 关卡网格 超出宽度自动换行
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        max_width: Val::Px(LEVEL_SELECT_GRID_W),
+                        flex_wrap: FlexWrap::Wrap,
+                        justify_content: JustifyContent::Center,
+                        row_gap: Val::Px(16.0),
+                        column_gap: Val::Px(16.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (index, level) in levels.iter().enumerate() {
+                        let stars = stars_text(progress.stars(&level.id));
+                        spawn_level_button(parent, &font, index, stars);
+                    }
+                });
+
+            spawn_back_button(parent, LevelSelectButton::Back);
+        });
+}
+
+// 关卡按钮 上方序号 下方星级
+fn spawn_level_button(parent: &mut ChildBuilder, font: &Handle<Font>, index: usize, stars: String) {
+    parent
+        .spawn((
+            menu_button(96.0, 96.0),
+            MenuButton,
+            LevelSelectButton::Level(index),
+        ))
+        .with_children(|parent| {
+            for (value, font_size) in [((index + 1).to_string(), 40.0), (stars, 28.0)] {
+                parent.spawn(TextBundle::from_section(
+                    value,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size,
+                        color: Color::BLACK,
+                    },
+                ));
+            }
+        });
+}
+
+// 选关后进入游戏 返回模式选择
+fn handle_level_select_buttons(
+    mut activated_events: EventReader<MenuActivated>,
+    button_query: Query<&LevelSelectButton>,
+    mut puzzles: ResMut<Puzzles>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in activated_events.read() {
+        match button_query.get(event.0) {
+            Ok(LevelSelectButton::Level(index)) => {
+                puzzles.selected = *index;
+                next_state.set(GameState::GameInit);
+            }
+            Ok(LevelSelectButton::Back) => next_state.set(GameState::ModeSelect),
+            Err(_) => {}
+        }
+    }
+}
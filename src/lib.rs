@@ -1,17 +1,49 @@
+pub mod accessibility;
+pub mod achievements;
+pub mod achievements_screen;
+pub mod ai;
 pub mod alert;
 pub mod animation;
 pub mod arrow;
+pub mod attract;
+pub mod audio;
 pub mod block;
+pub mod board;
 pub mod camera;
 pub mod collision;
 pub mod constants;
+pub mod daily;
+#[cfg(feature = "dev")]
+pub mod dev;
+pub mod effects;
+pub mod endless;
+pub mod events;
 pub mod gui;
+pub mod high_scores;
+pub mod leaderboard;
+pub mod level_select;
+pub mod locale;
 pub mod main_menu;
+pub mod menu;
+pub mod mode_select;
+pub mod music;
 pub mod player;
+pub mod puzzle;
 pub mod resources;
+pub mod rules;
+pub mod save;
+pub mod settings_screen;
 pub mod settlement;
+pub mod sound;
 pub mod stage;
 pub mod state;
+pub mod stats;
+pub mod stats_screen;
+pub mod storage;
+pub mod synth;
+pub mod tracker;
+pub mod tween;
+pub mod versus;
 pub mod wall;
 pub mod world;
 
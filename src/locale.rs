@@ -0,0 +1,279 @@
+use ab_glyph::Font as _;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::{BoxedFuture, HashMap};
+
+use crate::storage::{format_key_values, parse_key_values, read_data, write_data};
+use crate::*;
+
+// 语言设置存档文件
+const LANGUAGE_FILE: &str = "language.txt";
+
+// 语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Language {
+    #[default]
+    English,
+    SimplifiedChinese,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::SimplifiedChinese];
+
+    // 语言代码 同时也是字符串表文件名
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::SimplifiedChinese => "zh-CN",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|language| language.code() == code)
+    }
+
+    // 下一个语言 用于循环切换
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|l| l == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+// 字符串表 每行 `key = value` 以 # 开头为注释 value 中 \n 表示换行
+#[derive(Asset, TypePath, Debug, Default)]
+pub struct LocaleTable(pub HashMap<String, String>);
+
+impl LocaleTable {
+    pub fn parse(source: &str) -> Self {
+        let mut table = HashMap::new();
+
+        for line in source.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                table.insert(key.trim().to_string(), value.trim().replace("\\n", "\n"));
+            }
+        }
+
+        Self(table)
+    }
+}
+
+#[derive(Default)]
+pub struct LocaleTableLoader;
+
+impl AssetLoader for LocaleTableLoader {
+    type Asset = LocaleTable;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader.read_to_string(&mut source).await?;
+
+            Ok(LocaleTable::parse(&source))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["lang"]
+    }
+}
+
+// 当前语言及各语言字符串表
+#[derive(Resource, Default)]
+pub struct Locale {
+    pub language: Language,
+    tables: HashMap<Language, Handle<LocaleTable>>,
+}
+
+// 字体回退链 依次选择能完整显示文本的字体
+#[derive(Resource, Default)]
+pub struct FontStack(pub Vec<Handle<Font>>);
+
+impl FontStack {
+    pub fn pick(&self, text: &str, fonts: &Assets<Font>) -> Handle<Font> {
+        for handle in self.0.iter() {
+            let Some(font) = fonts.get(handle) else {
+                continue;
+            };
+
+            let covered = text
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .all(|c| font.font.glyph_id(c).0 != 0);
+
+            if covered {
+                return handle.clone();
+            }
+        }
+
+        // 均无法显示时使用 bevy 内置字体
+        Handle::default()
+    }
+}
+
+// 本地化文本 文本内容与字体由语言决定
+#[derive(Component, Debug, Clone)]
+pub struct LocalizedText {
+    pub key: String,
+    pub args: Vec<String>,
+}
+
+impl LocalizedText {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            args: vec![],
+        }
+    }
+
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+}
+
+// 查询当前语言的字符串
+#[derive(SystemParam)]
+pub struct Localizer<'w> {
+    pub locale: Res<'w, Locale>,
+    tables: Res<'w, Assets<LocaleTable>>,
+}
+
+impl<'w> Localizer<'w> {
+    // 当前语言缺失时回退到英文 仍缺失则返回 key
+    pub fn get(&self, key: &str) -> String {
+        [self.locale.language, Language::English]
+            .iter()
+            .filter_map(|language| self.locale.tables.get(language))
+            .filter_map(|handle| self.tables.get(handle))
+            .find_map(|table| table.0.get(key))
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    // 替换 {0} {1} ... 占位符
+    pub fn format(&self, key: &str, args: &[String]) -> String {
+        let mut value = self.get(key);
+        for (i, arg) in args.iter().enumerate() {
+            value = value.replace(&format!("{{{}}}", i), arg);
+        }
+        value
+    }
+}
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LocaleTable>()
+            .init_asset_loader::<LocaleTableLoader>()
+            .insert_resource(Locale {
+                language: read_data(LANGUAGE_FILE)
+                    .and_then(|source| parse_language(&source))
+                    .unwrap_or_default(),
+                ..default()
+            })
+            .init_resource::<FontStack>()
+            .add_systems(Startup, load_locales)
+            .add_systems(
+                Update,
+                (
+                    (switch_language, apply_localized_text).chain(),
+                    save_language.run_if(resource_changed::<Locale>),
+                ),
+            );
+    }
+}
+
+// 语言设置 `language = 语言代码` 未知代码时使用默认语言
+fn parse_language(source: &str) -> Option<Language> {
+    parse_key_values(source)
+        .into_iter()
+        .find(|(key, _)| key == "language")
+        .and_then(|(_, code)| Language::from_code(&code))
+}
+
+// 语言变化时保存 启动时读取的语言不重复写入
+fn save_language(locale: Res<Locale>, mut saved: Local<Option<Language>>) {
+    let previous = saved.replace(locale.language);
+    if previous.is_none() || previous == Some(locale.language) {
+        return;
+    }
+
+    let source = format_key_values(
+        "Smart Block language",
+        vec![(String::from("language"), locale.language.code().to_string())],
+    );
+    if let Err(e) = write_data(LANGUAGE_FILE, &source) {
+        warn!("failed to save language: {}", e);
+    }
+}
+
+// 加载字符串表与字体
+fn load_locales(
+    mut locale: ResMut<Locale>,
+    mut font_stack: ResMut<FontStack>,
+    asset_server: Res<AssetServer>,
+) {
+    for language in Language::ALL {
+        let path = format!("{}/{}.lang", LOCALE_DIR, language.code());
+        locale.tables.insert(language, asset_server.load(path));
+    }
+
+    font_stack.0 = FONT_STACK_PATHS
+        .iter()
+        .map(|path| asset_server.load(*path))
+        .collect();
+}
+
+// 切换语言 F2
+fn switch_language(keyboard_input: Res<ButtonInput<KeyCode>>, mut locale: ResMut<Locale>) {
+    if keyboard_input.just_pressed(KeyCode::F2) {
+        locale.language = locale.language.next();
+    }
+}
+
+// 语言变化 字符串表或字体加载完成时刷新文本
+fn apply_localized_text(
+    localizer: Localizer,
+    font_stack: Res<FontStack>,
+    fonts: Res<Assets<Font>>,
+    mut table_events: EventReader<AssetEvent<LocaleTable>>,
+    mut font_events: EventReader<AssetEvent<Font>>,
+    mut query: Query<(Ref<LocalizedText>, &mut Text)>,
+) {
+    let assets_changed = table_events.read().count() > 0 || font_events.read().count() > 0;
+    let refresh_all = localizer.locale.is_changed() || assets_changed;
+
+    for (localized_text, mut text) in query.iter_mut() {
+        if !refresh_all && !localized_text.is_changed() {
+            continue;
+        }
+
+        let value = localizer.format(&localized_text.key, &localized_text.args);
+        let font = font_stack.pick(&value, &fonts);
+
+        let section = &mut text.sections[0];
+        if section.value != value {
+            section.value = value;
+        }
+        if section.style.font != font {
+            section.style.font = font;
+        }
+    }
+}
@@ -1,26 +1,56 @@
 #![windows_subsystem = "windows"]
 
-use bevy::{prelude::*, window::close_on_esc};
+use bevy::prelude::*;
+use bevy::window::WindowResizeConstraints;
 use bevy_embedded_assets::EmbeddedAssetPlugin;
 
+use bevy_smart_block::accessibility::AccessibilityPlugin;
+use bevy_smart_block::achievements::AchievementsPlugin;
+use bevy_smart_block::achievements_screen::AchievementsScreenPlugin;
+use bevy_smart_block::ai::AiPlugin;
 use bevy_smart_block::alert::AlertPlugin;
 use bevy_smart_block::animation::AnimationPlugin;
-use bevy_smart_block::block::{BlockPlugin, HandBlock};
+use bevy_smart_block::attract::AttractPlugin;
+use bevy_smart_block::audio::AudioManagerPlugin;
+use bevy_smart_block::block::BlockPlugin;
+use bevy_smart_block::board::BoardPlugin;
 use bevy_smart_block::camera::MyCameraPlugin;
 use bevy_smart_block::collision::CollisionPlugin;
+use bevy_smart_block::daily::DailyPlugin;
+#[cfg(feature = "dev")]
+use bevy_smart_block::dev::DevPlugin;
+use bevy_smart_block::effects::EffectsPlugin;
+use bevy_smart_block::endless::EndlessPlugin;
+use bevy_smart_block::events::GameplayEventsPlugin;
 use bevy_smart_block::gui::GuiPlugin;
+use bevy_smart_block::high_scores::HighScoresPlugin;
+use bevy_smart_block::leaderboard::LeaderboardPlugin;
+use bevy_smart_block::level_select::LevelSelectPlugin;
+use bevy_smart_block::locale::LocalePlugin;
 use bevy_smart_block::main_menu::MainMenuPlugin;
+use bevy_smart_block::menu::MenuPlugin;
+use bevy_smart_block::mode_select::ModeSelectPlugin;
+use bevy_smart_block::music::MusicPlugin;
 use bevy_smart_block::player::PlayerPlugin;
+use bevy_smart_block::puzzle::PuzzlePlugin;
 use bevy_smart_block::resources::ResourcesPlugin;
+use bevy_smart_block::save::SavePlugin;
+use bevy_smart_block::settings_screen::SettingsScreenPlugin;
 use bevy_smart_block::settlement::SettlementPlugin;
+use bevy_smart_block::sound::SoundPlugin;
 use bevy_smart_block::stage::StagePlugin;
 use bevy_smart_block::state::GameState;
+use bevy_smart_block::stats::StatsPlugin;
+use bevy_smart_block::stats_screen::StatsScreenPlugin;
+use bevy_smart_block::tracker::TrackerPlugin;
+use bevy_smart_block::tween::TweenPlugin;
+use bevy_smart_block::versus::VersusPlugin;
 use bevy_smart_block::world::WorldPlugin;
 use bevy_smart_block::*;
 
 fn main() {
-    App::new()
-        .init_state::<GameState>()
+    let mut app = App::new();
+    app.init_state::<GameState>()
         .add_plugins(EmbeddedAssetPlugin::default())
         .add_plugins(
             DefaultPlugins
@@ -28,71 +58,66 @@ fn main() {
                 .set(WindowPlugin {
                     primary_window: Some(Window {
                         title: String::from("Smart Block"),
-                        resizable: false,
+                        resizable: true,
                         focused: true,
                         resolution: (WW, WH).into(),
+                        resize_constraints: WindowResizeConstraints {
+                            min_width: WINDOW_MIN_W,
+                            min_height: WINDOW_MIN_H,
+                            ..default()
+                        },
                         ..default()
                     }),
+                    // 由存档插件关闭窗口 关卡进行中先保存
+                    close_when_requested: false,
                     ..default()
                 }),
         )
         .insert_resource(Msaa::Off)
-        .insert_resource(ClearColor(Color::rgb_u8(
-            BG_COLOR.0, BG_COLOR.1, BG_COLOR.2,
-        )))
+        // 黑边颜色 游戏区域背景由 PlayfieldBackground 绘制
+        .insert_resource(ClearColor(Color::BLACK))
+        .add_plugins(LocalePlugin)
+        .add_plugins(MenuPlugin)
         .add_plugins(MainMenuPlugin)
+        .add_plugins(ModeSelectPlugin)
+        .add_plugins(HighScoresPlugin)
+        .add_plugins(SettingsScreenPlugin)
         .add_plugins(MyCameraPlugin)
         .add_plugins(ResourcesPlugin)
+        .add_plugins(TrackerPlugin)
+        .add_plugins(AudioManagerPlugin)
+        .add_plugins(MusicPlugin)
+        .add_plugins(TweenPlugin)
+        .add_plugins(BoardPlugin)
         .add_plugins(WorldPlugin)
+        .add_plugins(GameplayEventsPlugin)
         .add_plugins(BlockPlugin)
         .add_plugins(PlayerPlugin)
         .add_plugins(GuiPlugin)
         .add_plugins(CollisionPlugin)
         .add_plugins(AnimationPlugin)
         .add_plugins(SettlementPlugin)
+        .add_plugins(SoundPlugin)
+        .add_plugins(EffectsPlugin)
         .add_plugins(StagePlugin)
         .add_plugins(AlertPlugin)
-        .add_systems(Update, close_on_esc)
-        .add_systems(
-            Update,
-            test_debug_hand_block.run_if(in_state(GameState::InGame)),
-        )
-        .run();
-}
-
-fn test_debug_hand_block(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut hand_block_query: Query<&mut TextureAtlas, With<HandBlock>>,
-) {
-    if hand_block_query.is_empty() {
-        return;
-    }
-
-    let mut texture_atlas = hand_block_query.single_mut();
-
-    let key1 = keyboard_input.just_pressed(KeyCode::Digit1);
-    let key2 = keyboard_input.just_pressed(KeyCode::Digit2);
-    let key3 = keyboard_input.just_pressed(KeyCode::Digit3);
-    let key4 = keyboard_input.just_pressed(KeyCode::Digit4);
-    let key5 = keyboard_input.just_pressed(KeyCode::Digit5);
-
-    if key1 {
-        texture_atlas.index = 8;
-    }
-
-    if key2 {
-        texture_atlas.index = 9;
-    }
-
-    if key3 {
-        texture_atlas.index = 10;
-    }
+        .add_plugins(AccessibilityPlugin)
+        .add_plugins(AiPlugin)
+        .add_plugins(AttractPlugin)
+        .add_plugins(VersusPlugin)
+        .add_plugins(EndlessPlugin)
+        .add_plugins(LeaderboardPlugin)
+        .add_plugins(PuzzlePlugin)
+        .add_plugins(LevelSelectPlugin)
+        .add_plugins(DailyPlugin)
+        .add_plugins(StatsPlugin)
+        .add_plugins(StatsScreenPlugin)
+        .add_plugins(AchievementsPlugin)
+        .add_plugins(AchievementsScreenPlugin)
+        .add_plugins(SavePlugin);
 
-    if key4 {
-        texture_atlas.index = 11;
-    }
+    #[cfg(feature = "dev")]
+    app.add_plugins(DevPlugin);
 
-    if key5 {
-        texture_atlas.index = 12;
-    }
+    app.run();
 }
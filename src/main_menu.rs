@@ -1,101 +1,129 @@
+use bevy::app::AppExit;
 use bevy::prelude::*;
 
-use crate::state::GameState;
+use chrono::Local;
+
+use crate::attract::is_attract_mode;
+use crate::daily::{record_daily_result, DailyHistory};
+use crate::locale::LocalizedText;
+use crate::menu::{despawn_screen, menu_root, spawn_text_button, spawn_title_logo, MenuActivated};
+use crate::resources::GlobalTextAtlas;
+use crate::save::{PendingRestore, SavedGame};
+use crate::state::{GameMode, GameState};
 
 #[derive(Component)]
 pub struct MainMenuItem;
 
+// 主菜单按钮
+#[derive(Component, Debug, Clone, Copy)]
+enum MainMenuButton {
+    Continue,
+    Play,
+    Modes,
+    HighScores,
+    Settings,
+    Quit,
+}
+
 pub struct MainMenuPlugin;
 
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::MainMenu), setup_main_menu)
-            .add_systems(OnExit(GameState::MainMenu), despawn_main_menu)
-            .add_systems(
-                Update,
-                handle_main_menu_buttons.run_if(in_state(GameState::MainMenu)),
-            );
+        app.add_systems(
+            OnEnter(GameState::MainMenu),
+            // 每日挑战成绩记录后再显示连续天数
+            setup_main_menu.after(record_daily_result),
+        )
+        // 演示模式下主菜单保留在游戏画面之上
+        .add_systems(
+            OnExit(GameState::MainMenu),
+            despawn_screen::<MainMenuItem>.run_if(not(is_attract_mode)),
+        )
+        .add_systems(
+            Update,
+            handle_main_menu_buttons.run_if(in_state(GameState::MainMenu)),
+        );
     }
 }
 
 // 生成主菜单
-fn setup_main_menu(mut commands: Commands) {
+fn setup_main_menu(
+    mut commands: Commands,
+    atlas: Res<GlobalTextAtlas>,
+    daily_history: Res<DailyHistory>,
+    saved_game: Res<SavedGame>,
+    main_menu_query: Query<(), With<MainMenuItem>>,
+) {
+    // 从演示模式返回时主菜单仍在
+    if !main_menu_query.is_empty() {
+        return;
+    }
+
     commands
-        .spawn(NodeBundle {
-            style: Style {
-                width: Val::Percent(100.0),
-                height: Val::Percent(100.0),
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
-                ..default()
-            },
-            ..default()
-        })
+        .spawn((menu_root(12.0), MainMenuItem))
         .with_children(|parent| {
-            parent
-                .spawn(ButtonBundle {
-                    style: Style {
-                        width: Val::Px(150.0),
-                        height: Val::Px(65.0),
-                        border: UiRect::all(Val::Px(2.0)),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    border_color: Color::BLACK.into(),
-                    ..default()
-                })
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        "Play",
+            spawn_title_logo(parent, &atlas);
+
+            // 有中途存档时显示继续游戏
+            if saved_game.0.is_some() {
+                spawn_text_button(parent, "menu.continue", MainMenuButton::Continue);
+            }
+            spawn_text_button(parent, "menu.play", MainMenuButton::Play);
+            spawn_text_button(parent, "menu.modes", MainMenuButton::Modes);
+            spawn_text_button(parent, "menu.high_scores", MainMenuButton::HighScores);
+            spawn_text_button(parent, "menu.settings", MainMenuButton::Settings);
+            spawn_text_button(parent, "menu.quit", MainMenuButton::Quit);
+
+            let streak = daily_history.streak(Local::now().date_naive());
+            if streak > 0 {
+                parent.spawn((
+                    TextBundle::from_section(
+                        "",
                         TextStyle {
-                            font_size: 40.0,
-                            color: Color::BLACK,
+                            font_size: 28.0,
+                            color: Color::WHITE,
                             ..default()
                         },
-                    ));
-                });
-        })
-        .insert(MainMenuItem);
-}
-
-// 移除主菜单
-fn despawn_main_menu(main_menu_query: Query<Entity, With<MainMenuItem>>, mut commands: Commands) {
-    // despawn main menu
-    for entity in main_menu_query.iter() {
-        commands.entity(entity).despawn_recursive();
-    }
+                    ),
+                    LocalizedText::new("daily.streak").with_args(vec![streak.to_string()]),
+                ));
+            }
+        });
 }
 
 // 主菜单按钮
 fn handle_main_menu_buttons(
-    mut interaction_query: Query<
-        (
-            &Interaction,
-            &mut BackgroundColor,
-            &mut BorderColor,
-            &Children,
-        ),
-        (Changed<Interaction>, With<Button>),
-    >,
-    mut text_query: Query<&mut Text, With<Text>>,
+    mut commands: Commands,
+    mut activated_events: EventReader<MenuActivated>,
+    button_query: Query<&MainMenuButton>,
+    saved_game: Res<SavedGame>,
+    mut mode: ResMut<GameMode>,
     mut next_state: ResMut<NextState<GameState>>,
+    mut exit_events: EventWriter<AppExit>,
 ) {
-    let mut text = text_query.single_mut();
-    for (interaction, mut bg_color, mut border_color, _) in interaction_query.iter_mut() {
-        match interaction {
-            Interaction::Pressed => {
+    for event in activated_events.read() {
+        let Ok(button) = button_query.get(event.0) else {
+            continue;
+        };
+
+        match button {
+            MainMenuButton::Continue => {
+                let Some(save) = saved_game.0.clone() else {
+                    continue;
+                };
+                *mode = save.mode;
+                commands.insert_resource(PendingRestore(save));
                 next_state.set(GameState::GameInit);
             }
-            Interaction::Hovered => {
-                *bg_color = Color::YELLOW.into();
-                *border_color = Color::BLUE.into();
-                text.sections[0].style.color = Color::BLUE;
+            MainMenuButton::Play => {
+                *mode = GameMode::Single;
+                next_state.set(GameState::GameInit);
             }
-            Interaction::None => {
-                *bg_color = Color::WHITE.into();
-                *border_color = Color::BLACK.into();
-                text.sections[0].style.color = Color::BLACK;
+            MainMenuButton::Modes => next_state.set(GameState::ModeSelect),
+            MainMenuButton::HighScores => next_state.set(GameState::HighScores),
+            MainMenuButton::Settings => next_state.set(GameState::Settings),
+            MainMenuButton::Quit => {
+                exit_events.send(AppExit);
             }
         }
     }
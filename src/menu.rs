@@ -0,0 +1,330 @@
+use bevy::prelude::*;
+
+use crate::attract::is_attract_mode;
+use crate::block::BlockKind;
+use crate::locale::LocalizedText;
+use crate::resources::GlobalTextAtlas;
+use crate::*;
+
+// 菜单按钮 可由鼠标 键盘方向键或手柄十字键选中
+#[derive(Component)]
+pub struct MenuButton;
+
+// 返回按钮 Esc 退格键或手柄 B 键直接触发
+#[derive(Component)]
+pub struct MenuCancel;
+
+// 当前选中的菜单按钮
+#[derive(Resource, Default)]
+pub struct MenuFocus(pub Option<Entity>);
+
+// 菜单按钮被触发 鼠标点击 回车 空格或手柄 A 键
+#[derive(Event, Debug, Clone, Copy)]
+pub struct MenuActivated(pub Entity);
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuFocus>()
+            .add_event::<MenuActivated>()
+            .add_systems(
+                Update,
+                (
+                    handle_menu_pointer,
+                    handle_menu_navigation.run_if(not(is_attract_mode)),
+                    paint_menu_buttons,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// 菜单界面根节点 全屏纵向居中
+pub fn menu_root(row_gap: f32) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(row_gap),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        ..default()
+    }
+}
+
+// 菜单按钮外框 内容由调用方添加
+pub fn menu_button(width: f32, height: f32) -> ButtonBundle {
+    ButtonBundle {
+        style: Style {
+            width: Val::Px(width),
+            height: Val::Px(height),
+            border: UiRect::all(Val::Px(2.0)),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        border_color: Color::BLACK.into(),
+        ..default()
+    }
+}
+
+// 界面标题
+pub fn spawn_menu_title(parent: &mut ChildBuilder, text: LocalizedText) {
+    parent.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 48.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ),
+        text,
+    ));
+}
+
+// 文字菜单按钮 附带调用方的按钮类型组件
+pub fn spawn_text_button(
+    parent: &mut ChildBuilder,
+    key: &'static str,
+    bundle: impl Bundle,
+) -> Entity {
+    parent
+        .spawn((
+            menu_button(MENU_BUTTON_W, MENU_BUTTON_H),
+            MenuButton,
+            bundle,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 40.0,
+                        color: Color::BLACK,
+                        ..default()
+                    },
+                ),
+                LocalizedText::new(key),
+            ));
+        })
+        .id()
+}
+
+// 返回按钮 同时响应退格键
+pub fn spawn_back_button(parent: &mut ChildBuilder, bundle: impl Bundle) -> Entity {
+    parent
+        .spawn((
+            menu_button(MENU_BUTTON_W, MENU_BUTTON_H),
+            MenuButton,
+            MenuCancel,
+            bundle,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 40.0,
+                        color: Color::BLACK,
+                        ..default()
+                    },
+                ),
+                LocalizedText::new("menu.back"),
+            ));
+        })
+        .id()
+}
+
+// 标题 一排方块图标下方为游戏名
+pub fn spawn_title_logo(parent: &mut ChildBuilder, atlas: &GlobalTextAtlas) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                margin: UiRect::bottom(Val::Px(16.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            if let (Some(image), Some(layout)) = (&atlas.image, &atlas.layout) {
+                parent.spawn(NodeBundle::default()).with_children(|parent| {
+                    for kind in BlockKind::ALL {
+                        parent.spawn(AtlasImageBundle {
+                            style: Style {
+                                width: Val::Px(MENU_LOGO_TILE),
+                                height: Val::Px(MENU_LOGO_TILE),
+                                ..default()
+                            },
+                            image: UiImage::new(image.clone()),
+                            texture_atlas: TextureAtlas {
+                                layout: layout.clone(),
+                                index: kind.atlas_index(),
+                            },
+                            ..default()
+                        });
+                    }
+                });
+            }
+
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 72.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                LocalizedText::new("menu.title"),
+            ));
+        });
+}
+
+// 移除界面 OnExit 时使用
+pub fn despawn_screen<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// 鼠标悬停选中 点击触发
+#[allow(clippy::type_complexity)]
+fn handle_menu_pointer(
+    interaction_query: Query<(Entity, &Interaction), (Changed<Interaction>, With<MenuButton>)>,
+    mut focus: ResMut<MenuFocus>,
+    mut activated_events: EventWriter<MenuActivated>,
+) {
+    for (entity, interaction) in interaction_query.iter() {
+        match interaction {
+            Interaction::Pressed => {
+                focus.0 = Some(entity);
+                activated_events.send(MenuActivated(entity));
+            }
+            Interaction::Hovered => focus.0 = Some(entity),
+            Interaction::None => {}
+        }
+    }
+}
+
+// 方向键在按钮间移动 选择该方向上最近的按钮
+#[allow(clippy::type_complexity)]
+fn handle_menu_navigation(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<ButtonInput<GamepadButton>>,
+    button_query: Query<(Entity, &GlobalTransform), With<MenuButton>>,
+    cancel_query: Query<Entity, With<MenuCancel>>,
+    mut focus: ResMut<MenuFocus>,
+    mut activated_events: EventWriter<MenuActivated>,
+) {
+    if button_query.is_empty() {
+        return;
+    }
+
+    let pressed = |key: KeyCode, button: GamepadButtonType| {
+        keyboard_input.just_pressed(key)
+            || gamepads
+                .iter()
+                .any(|gamepad| gamepad_input.just_pressed(GamepadButton::new(gamepad, button)))
+    };
+
+    if pressed(KeyCode::Backspace, GamepadButtonType::East)
+        || keyboard_input.just_pressed(KeyCode::Escape)
+    {
+        if let Some(entity) = cancel_query.iter().next() {
+            activated_events.send(MenuActivated(entity));
+        }
+        return;
+    }
+
+    let current = focus.0.and_then(|entity| button_query.get(entity).ok());
+
+    if pressed(KeyCode::Enter, GamepadButtonType::South)
+        || keyboard_input.just_pressed(KeyCode::Space)
+    {
+        if let Some((entity, _)) = current {
+            activated_events.send(MenuActivated(entity));
+        }
+        return;
+    }
+
+    // UI 坐标 y 轴向下
+    let direction = if pressed(KeyCode::ArrowUp, GamepadButtonType::DPadUp) {
+        Vec2::NEG_Y
+    } else if pressed(KeyCode::ArrowDown, GamepadButtonType::DPadDown) {
+        Vec2::Y
+    } else if pressed(KeyCode::ArrowLeft, GamepadButtonType::DPadLeft) {
+        Vec2::NEG_X
+    } else if pressed(KeyCode::ArrowRight, GamepadButtonType::DPadRight) {
+        Vec2::X
+    } else {
+        return;
+    };
+
+    // 尚未选中时先选中左上角的按钮
+    let Some((current, transform)) = current else {
+        focus.0 = button_query
+            .iter()
+            .map(|(entity, transform)| (entity, transform.translation().truncate()))
+            .min_by(|(_, a), (_, b)| (a.y, a.x).partial_cmp(&(b.y, b.x)).unwrap())
+            .map(|(entity, _)| entity);
+        return;
+    };
+
+    let origin = transform.translation().truncate();
+    let next = button_query
+        .iter()
+        .filter(|(entity, _)| *entity != current)
+        .filter_map(|(entity, transform)| {
+            let offset = transform.translation().truncate() - origin;
+            let along = offset.dot(direction);
+            let across = offset.perp_dot(direction).abs();
+            // 偏离方向的按钮距离加倍计算
+            (along > 0.0).then_some((entity, along + across * 2.0))
+        })
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+
+    if let Some((entity, _)) = next {
+        focus.0 = Some(entity);
+    }
+}
+
+// 按钮颜色 选中黄底蓝字 未选中白底黑字
+#[allow(clippy::type_complexity)]
+fn paint_menu_buttons(
+    focus: Res<MenuFocus>,
+    added_query: Query<(), Added<MenuButton>>,
+    mut button_query: Query<
+        (Entity, &mut BackgroundColor, &mut BorderColor, &Children),
+        With<MenuButton>,
+    >,
+    mut text_query: Query<&mut Text>,
+) {
+    if !focus.is_changed() && added_query.is_empty() {
+        return;
+    }
+
+    for (entity, mut bg_color, mut border_color, children) in button_query.iter_mut() {
+        let (bg, border, text_color) = if focus.0 == Some(entity) {
+            (Color::YELLOW, Color::BLUE, Color::BLUE)
+        } else {
+            (Color::WHITE, Color::BLACK, Color::BLACK)
+        };
+
+        *bg_color = bg.into();
+        *border_color = border.into();
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].style.color = text_color;
+            }
+        }
+    }
+}
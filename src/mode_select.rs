@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+
+use crate::locale::LocalizedText;
+use crate::menu::{
+    despawn_screen, menu_root, spawn_back_button, spawn_menu_title, spawn_text_button,
+    MenuActivated,
+};
+use crate::state::{GameMode, GameState};
+
+#[derive(Component)]
+struct ModeSelectItem;
+
+// 模式按钮 返回为 None
+#[derive(Component, Debug, Clone, Copy)]
+struct ModeSelectButton(Option<GameMode>);
+
+pub struct ModeSelectPlugin;
+
+impl Plugin for ModeSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::ModeSelect), setup_mode_select)
+            .add_systems(
+                OnExit(GameState::ModeSelect),
+                despawn_screen::<ModeSelectItem>,
+            )
+            .add_systems(
+                Update,
+                handle_mode_select_buttons.run_if(in_state(GameState::ModeSelect)),
+            );
+    }
+}
+
+// 生成模式选择界面
+fn setup_mode_select(mut commands: Commands) {
+    commands
+        .spawn((menu_root(12.0), ModeSelectItem))
+        .with_children(|parent| {
+            spawn_menu_title(parent, LocalizedText::new("menu.modes"));
+
+            for (key, mode) in [
+                ("menu.endless", GameMode::Endless),
+                ("menu.puzzle", GameMode::Puzzle),
+                ("menu.daily", GameMode::Daily),
+                ("menu.versus", GameMode::Versus),
+                ("menu.versus_ai", GameMode::VersusAi),
+            ] {
+                spawn_text_button(parent, key, ModeSelectButton(Some(mode)));
+            }
+
+            spawn_back_button(parent, ModeSelectButton(None));
+        });
+}
+
+// 选择模式后进入游戏 解谜先选关
+fn handle_mode_select_buttons(
+    mut activated_events: EventReader<MenuActivated>,
+    button_query: Query<&ModeSelectButton>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in activated_events.read() {
+        let Ok(ModeSelectButton(game_mode)) = button_query.get(event.0) else {
+            continue;
+        };
+
+        let Some(game_mode) = game_mode else {
+            next_state.set(GameState::MainMenu);
+            continue;
+        };
+
+        *mode = *game_mode;
+        next_state.set(match game_mode {
+            GameMode::Puzzle => GameState::LevelSelect,
+            _ => GameState::GameInit,
+        });
+    }
+}
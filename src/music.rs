@@ -0,0 +1,206 @@
+use bevy::audio::{PlaybackMode, Volume};
+use bevy::prelude::*;
+use std::sync::Arc;
+
+use crate::audio::{AudioBus, AudioMix, AudioSettings};
+use crate::gui::CountDown;
+use crate::player::Controller;
+use crate::resources::GlobalAudio;
+use crate::state::{BoardState, GameMode, GameState, SettlementState};
+use crate::tracker::{TrackerControls, TrackerModule};
+use crate::*;
+
+// 背景音乐场景
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MusicCue {
+    Menu,
+    // 关卡中 按模式选曲
+    Stage(GameMode),
+    // 倒计时将尽
+    Hurry,
+    // 结算 过关或对局结束
+    Settlement,
+}
+
+impl MusicCue {
+    // 速度倍率与静音声道掩码 只改变编排 不重新开始曲目
+    pub fn arrangement(&self) -> (f32, u32) {
+        let drums = 1 << MUSIC_DRUM_CHANNEL;
+        match self {
+            MusicCue::Menu => (1.0, 0),
+            MusicCue::Stage(GameMode::Puzzle) => (0.9, drums),
+            MusicCue::Stage(_) => (1.0, 0),
+            MusicCue::Hurry => (MUSIC_HURRY_TEMPO, 0),
+            MusicCue::Settlement => (0.8, drums),
+        }
+    }
+
+    // 进出关卡时淡入淡出重新开始 关卡内切换只改编排
+    pub fn restarts_from(&self, other: MusicCue) -> bool {
+        (*self == MusicCue::Menu) != (other == MusicCue::Menu)
+    }
+}
+
+// 正在播放的曲目 切换时旧曲目淡出 新曲目淡入
+#[derive(Component, Debug)]
+pub struct MusicChannel {
+    pub cue: MusicCue,
+    // 播放的曲目
+    pub track: AssetId<TrackerModule>,
+    pub controls: Arc<TrackerControls>,
+    // 淡入淡出进度 0-1
+    pub fade: f32,
+    pub fading_out: bool,
+}
+
+// 当前场景
+#[derive(Resource, Debug, Default)]
+pub struct CurrentMusic(pub Option<MusicCue>);
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurrentMusic>().add_systems(
+            Update,
+            (select_music_cue, fade_music, apply_music_volume)
+                .chain()
+                .run_if(not(in_state(GameState::Loading))),
+        );
+    }
+}
+
+// 根据游戏状态选曲 结算优先于紧迫
+fn music_cue(
+    state: &GameState,
+    mode: GameMode,
+    board_query: &Query<(&Controller, &CountDown, &BoardState<SettlementState>)>,
+) -> MusicCue {
+    if !matches!(state, GameState::GameInit | GameState::InGame) {
+        return MusicCue::Menu;
+    }
+
+    let players = board_query
+        .iter()
+        .filter(|(controller, _, _)| **controller == Controller::Keyboard);
+    let mut hurry = false;
+    for (_, count_down, settlement) in players {
+        if !settlement.is(SettlementState::Not) {
+            return MusicCue::Settlement;
+        }
+        hurry |= mode.is_timed() && count_down.0.remaining_secs() < MUSIC_HURRY_SEC;
+    }
+
+    if hurry {
+        MusicCue::Hurry
+    } else {
+        MusicCue::Stage(mode)
+    }
+}
+
+// 场景变化时调整编排 需要重新开始时交叉淡入淡出
+#[allow(clippy::too_many_arguments)]
+fn select_music_cue(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    mode: Res<GameMode>,
+    audio_handles: Res<GlobalAudio>,
+    mut modules: ResMut<Assets<TrackerModule>>,
+    mut current: ResMut<CurrentMusic>,
+    board_query: Query<(&Controller, &CountDown, &BoardState<SettlementState>)>,
+    mut channel_query: Query<&mut MusicChannel>,
+) {
+    let cue = music_cue(state.get(), *mode, &board_query);
+    if current.0 == Some(cue) {
+        return;
+    }
+
+    // 曲目不同时也需要重新开始
+    let Some(track) = audio_handles
+        .music_track((cue != MusicCue::Menu).then_some(*mode))
+        .map(|handle| handle.id())
+    else {
+        return;
+    };
+
+    let (tempo, muted) = cue.arrangement();
+    if let Some(mut channel) = channel_query.iter_mut().find(|channel| {
+        !channel.fading_out && !cue.restarts_from(channel.cue) && channel.track == track
+    }) {
+        debug!("music cue: {:?}", cue);
+        current.0 = Some(cue);
+        channel.cue = cue;
+        channel.controls.set_tempo_scale(tempo);
+        channel.controls.set_muted_channels(muted);
+        return;
+    }
+
+    // 曲目加载完成后再开始
+    let Some(module) = modules.get(track).map(|module| module.instance()) else {
+        return;
+    };
+
+    debug!("music cue: {:?}", cue);
+    current.0 = Some(cue);
+    for mut channel in channel_query.iter_mut() {
+        channel.fading_out = true;
+    }
+
+    module.controls.set_tempo_scale(tempo);
+    module.controls.set_muted_channels(muted);
+    let controls = module.controls.clone();
+    commands.spawn((
+        AudioSourceBundle {
+            source: modules.add(module),
+            settings: PlaybackSettings {
+                mode: PlaybackMode::Loop,
+                volume: Volume::new(0.0),
+                ..default()
+            },
+        },
+        AudioBus::Music,
+        MusicChannel {
+            cue,
+            track,
+            controls,
+            fade: 0.0,
+            fading_out: false,
+        },
+    ));
+}
+
+// 淡入淡出 淡出完成后销毁
+fn fade_music(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut channel_query: Query<(Entity, &mut MusicChannel)>,
+) {
+    let step = time.delta_seconds() / MUSIC_FADE_SEC;
+    for (entity, mut channel) in channel_query.iter_mut() {
+        if channel.fading_out {
+            channel.fade -= step;
+            if channel.fade <= 0.0 {
+                commands.entity(entity).despawn();
+            }
+        } else if channel.fade < 1.0 {
+            channel.fade = (channel.fade + step).min(1.0);
+        }
+    }
+}
+
+// 音量 结算压低 窗口失去焦点暂停
+fn apply_music_volume(
+    settings: Res<AudioSettings>,
+    mix: Res<AudioMix>,
+    channel_query: Query<(&MusicChannel, &AudioSink)>,
+) {
+    let volume = settings.volume(AudioBus::Music) * mix.music_scale();
+    for (channel, sink) in channel_query.iter() {
+        sink.set_volume(volume * channel.fade.max(0.0));
+        if mix.paused {
+            sink.pause();
+        } else {
+            sink.play();
+        }
+    }
+}
@@ -2,8 +2,9 @@ use bevy::math::vec3;
 use bevy::prelude::*;
 
 use crate::arrow::ArrowPlugin;
-use crate::resources::GlobalAudio;
-use crate::state::{GameState, HandBlockState, PlayerState};
+use crate::board::InBoard;
+use crate::events::PlayerMoved;
+use crate::state::{BoardState, GameState, HandBlockState, PlayerState};
 use crate::*;
 
 // Player
@@ -14,6 +15,66 @@ pub struct Player;
 #[derive(Component)]
 pub struct Ladder;
 
+// 玩家本帧操作 挂在棋盘上 由键盘或AI写入
+#[derive(Component, Default)]
+pub struct PlayerInput {
+    pub up: bool,
+    pub down: bool,
+    pub throw: bool,
+}
+
+// 棋盘按键 对战时两名玩家各用一组
+#[derive(Component)]
+pub struct KeyBindings {
+    pub up: Vec<KeyCode>,
+    pub down: Vec<KeyCode>,
+    pub throw: Vec<KeyCode>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            up: vec![KeyCode::KeyW, KeyCode::ArrowUp],
+            down: vec![KeyCode::KeyS, KeyCode::ArrowDown],
+            throw: vec![KeyCode::Space],
+        }
+    }
+}
+
+impl KeyBindings {
+    // 对战按键 玩家一 WS 空格 玩家二 方向键 回车
+    pub fn versus(index: usize) -> Self {
+        match index {
+            0 => Self {
+                up: vec![KeyCode::KeyW],
+                down: vec![KeyCode::KeyS],
+                throw: vec![KeyCode::Space],
+            },
+            _ => Self {
+                up: vec![KeyCode::ArrowUp],
+                down: vec![KeyCode::ArrowDown],
+                throw: vec![KeyCode::Enter, KeyCode::NumpadEnter],
+            },
+        }
+    }
+}
+
+// 棋盘操作锁定 例如无尽模式结束后
+#[derive(Component)]
+pub struct InputLocked;
+
+// 棋盘操作者
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Controller {
+    #[default]
+    Keyboard,
+    Ai,
+}
+
+// 写入玩家操作的系统集 玩家系统在其之后运行
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlayerInputSet;
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
@@ -21,107 +82,111 @@ impl Plugin for PlayerPlugin {
         app.add_systems(
             Update,
             (
-                (
-                    handle_player_movement,
-                    player_move_sound.run_if(has_user_input_up_or_down),
-                    player_throw_sound.run_if(has_user_input_space),
-                )
-                    .run_if(in_state(HandBlockState::Idle)),
+                clear_player_input.before(PlayerInputSet),
+                read_keyboard_input.in_set(PlayerInputSet),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                ignore_busy_input,
+                handle_player_movement,
                 handle_throw_block,
             )
+                .chain()
+                .after(PlayerInputSet)
                 .run_if(in_state(GameState::InGame)),
         )
         .add_plugins(ArrowPlugin);
     }
 }
 
-// 玩家移动
-fn handle_player_movement(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut player_query: Query<(&mut Transform, &mut PlayerState), With<Player>>,
-) {
-    if player_query.is_empty() {
-        return;
-    }
-
-    let (mut player_transform, mut player_state) = player_query.single_mut();
-
-    let w_key =
-        keyboard_input.just_pressed(KeyCode::KeyW) || keyboard_input.just_pressed(KeyCode::ArrowUp);
-
-    let s_key = keyboard_input.just_pressed(KeyCode::KeyS)
-        || keyboard_input.just_pressed(KeyCode::ArrowDown);
-
-    let mut delta = Vec3::ZERO;
-
-    // 只有上下操作
-    if w_key && player_transform.translation.y <= -PLAYER_INIT_POS.1 - STEP_SIZE as f32 {
-        delta.y += 1.0;
-    }
-    if s_key && player_transform.translation.y > PLAYER_INIT_POS.1 {
-        delta.y -= 1.0;
+// 清空上一帧操作
+fn clear_player_input(mut query: Query<&mut PlayerInput>) {
+    for mut input in query.iter_mut() {
+        *input = PlayerInput::default();
     }
+}
 
-    let delta = delta.normalize();
-
-    if delta.is_finite() && (w_key || s_key) {
-        player_transform.translation += vec3(delta.x, delta.y, 0.0) * (STEP_SIZE as f32);
-        *player_state = PlayerState::Moving;
+// 手里方块未静止或操作锁定时操作无效
+fn ignore_busy_input(
+    mut query: Query<(
+        &mut PlayerInput,
+        &BoardState<HandBlockState>,
+        Has<InputLocked>,
+    )>,
+) {
+    for (mut input, state, locked) in query.iter_mut() {
+        if locked || !state.is(HandBlockState::Idle) {
+            *input = PlayerInput::default();
+        }
     }
 }
 
-// 扔方块
-fn handle_throw_block(
-    mut player_query: Query<&mut PlayerState, With<Player>>,
+// 读取键盘操作
+fn read_keyboard_input(
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut next_state: ResMut<NextState<HandBlockState>>,
+    mut query: Query<(&mut PlayerInput, &KeyBindings, &Controller)>,
 ) {
-    if player_query.is_empty() || !keyboard_input.pressed(KeyCode::Space) {
-        return;
+    for (mut input, bindings, controller) in query.iter_mut() {
+        if *controller != Controller::Keyboard {
+            continue;
+        }
+
+        input.up = keyboard_input.any_just_pressed(bindings.up.iter().copied());
+        input.down = keyboard_input.any_just_pressed(bindings.down.iter().copied());
+        input.throw = keyboard_input.any_just_pressed(bindings.throw.iter().copied());
     }
+}
 
-    let mut player_state = player_query.single_mut();
-
-    let space_key = keyboard_input.just_pressed(KeyCode::Space);
+// 玩家移动
+fn handle_player_movement(
+    mut moved_events: EventWriter<PlayerMoved>,
+    board_query: Query<&PlayerInput>,
+    mut player_query: Query<(&mut Transform, &mut PlayerState, &InBoard), With<Player>>,
+) {
+    for (mut player_transform, mut player_state, in_board) in player_query.iter_mut() {
+        let Ok(input) = board_query.get(in_board.0) else {
+            continue;
+        };
 
-    if space_key {
-        next_state.set(HandBlockState::Moving);
-        *player_state = PlayerState::Throwing;
-    }
-}
+        let w_key = input.up;
 
-// 播放玩家移动音效
-fn player_move_sound(audio_handles: Res<GlobalAudio>, mut commands: Commands) {
-    if let Some(player_move_source) = audio_handles.player_move.clone() {
-        commands.spawn(AudioBundle {
-            source: player_move_source,
-            ..default()
-        });
-    }
-}
+        let s_key = input.down;
 
-//  播放玩家抛出音效
-fn player_throw_sound(audio_handles: Res<GlobalAudio>, mut commands: Commands) {
-    if let Some(player_throw_source) = audio_handles.player_throw.clone() {
-        commands.spawn(AudioBundle {
-            source: player_throw_source,
-            ..default()
-        });
-    }
-}
+        let mut delta = Vec3::ZERO;
 
-// 玩家是否按下了上下方向键
-pub fn has_user_input_up_or_down(keyboard_input: Res<ButtonInput<KeyCode>>) -> bool {
-    let arrow_up =
-        keyboard_input.just_pressed(KeyCode::ArrowUp) || keyboard_input.just_pressed(KeyCode::KeyW);
+        // 只有上下操作
+        if w_key && player_transform.translation.y <= -PLAYER_INIT_POS.1 - STEP_SIZE as f32 {
+            delta.y += 1.0;
+        }
+        if s_key && player_transform.translation.y > PLAYER_INIT_POS.1 {
+            delta.y -= 1.0;
+        }
 
-    let arrow_down = keyboard_input.just_pressed(KeyCode::ArrowDown)
-        || keyboard_input.just_pressed(KeyCode::KeyS);
+        let delta = delta.normalize();
 
-    return arrow_up || arrow_down;
+        if delta.is_finite() && (w_key || s_key) {
+            player_transform.translation += vec3(delta.x, delta.y, 0.0) * (STEP_SIZE as f32);
+            *player_state = PlayerState::Moving;
+            moved_events.send(PlayerMoved { board: in_board.0 });
+        }
+    }
 }
 
-// 玩家是否按下了空格键
-pub fn has_user_input_space(keyboard_input: Res<ButtonInput<KeyCode>>) -> bool {
-    keyboard_input.just_pressed(KeyCode::Space)
+// 扔方块
+fn handle_throw_block(
+    mut board_query: Query<(&PlayerInput, &mut BoardState<HandBlockState>)>,
+    mut player_query: Query<(&mut PlayerState, &InBoard), With<Player>>,
+) {
+    for (mut player_state, in_board) in player_query.iter_mut() {
+        let Ok((input, mut state)) = board_query.get_mut(in_board.0) else {
+            continue;
+        };
+
+        if input.throw {
+            state.set(HandBlockState::Moving);
+            *player_state = PlayerState::Throwing;
+        }
+    }
 }
@@ -0,0 +1,459 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::math::ivec2;
+use bevy::prelude::*;
+use bevy::utils::{BoxedFuture, HashMap};
+
+use crate::alert::AlertEvent;
+use crate::block::{block_bundle, Block, BlockKind, HandBlock};
+use crate::board::{spawn_boards, Board, InBoard};
+use crate::events::{PlayerStuck, ThrowStarted};
+use crate::player::InputLocked;
+use crate::resources::GlobalTextAtlas;
+use crate::rules::{cell_to_pos, puzzle_stars, BLOCK_ORIGIN_CELL};
+use crate::stage::Stage;
+use crate::state::{
+    apply_board_transitions, BoardTransition, GameMode, GameState, SettlementState,
+};
+use crate::storage::{format_key_values, parse_key_values, read_data, write_data};
+use crate::*;
+
+// 解谜进度存档文件
+const PROGRESS_FILE: &str = "puzzle.txt";
+
+// 解谜关卡 无倒计时 固定的初始手里方块 标准投掷数及完美剩余数
+#[derive(Debug, Clone)]
+pub struct PuzzleLevel {
+    pub id: String,
+    pub hand: usize,
+    pub par: u32,
+    // 完美过关时最多剩余的方块数
+    pub perfect: usize,
+    pub blocks: Vec<(IVec2, usize)>,
+}
+
+// 关卡包 每个关卡以 `level = id` 开始
+// `hand` 与 `row` 使用 BlockKind::letter 中的字符 `.` 为空
+// `row` 从上到下书写 最后一行在地面之上 第一列紧贴左侧墙体
+#[derive(Asset, TypePath, Debug, Default)]
+pub struct PuzzlePack(pub Vec<PuzzleLevel>);
+
+impl PuzzlePack {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut levels = vec![];
+        let mut rows: Vec<String> = vec![];
+
+        let kind = |letter: &str| {
+            BlockKind::ALL
+                .into_iter()
+                .find(|kind| kind.letter() == letter)
+                .map(|kind| kind.atlas_index())
+                .ok_or(format!("unknown block kind: {}", letter))
+        };
+
+        // 行已全部读入 生成方块位置
+        let finish = |level: &mut PuzzleLevel, rows: &mut Vec<String>| -> Result<(), String> {
+            for (y, row) in rows.iter().rev().enumerate() {
+                for (x, letter) in row.chars().enumerate() {
+                    if letter == '.' {
+                        continue;
+                    }
+                    let cell = BLOCK_ORIGIN_CELL + ivec2(x as i32, y as i32);
+                    level.blocks.push((cell, kind(&letter.to_string())?));
+                }
+            }
+            rows.clear();
+            Ok(())
+        };
+
+        for (key, value) in parse_key_values(source) {
+            if key == "level" {
+                if let Some(level) = levels.last_mut() {
+                    finish(level, &mut rows)?;
+                }
+                levels.push(PuzzleLevel {
+                    id: value,
+                    hand: LIGHT_BLOCK_INDEX,
+                    par: 0,
+                    perfect: 0,
+                    blocks: vec![],
+                });
+                continue;
+            }
+
+            let Some(level) = levels.last_mut() else {
+                return Err(format!("`{}` before the first level", key));
+            };
+
+            match key.as_str() {
+                "hand" => level.hand = kind(&value)?,
+                "par" => level.par = value.parse().map_err(|e| format!("par: {}", e))?,
+                "perfect" => {
+                    level.perfect = value.parse().map_err(|e| format!("perfect: {}", e))?
+                }
+                "row" => rows.push(value),
+                _ => return Err(format!("unknown key: {}", key)),
+            }
+        }
+
+        if let Some(level) = levels.last_mut() {
+            finish(level, &mut rows)?;
+        }
+
+        Ok(Self(levels))
+    }
+}
+
+#[derive(Default)]
+pub struct PuzzlePackLoader;
+
+impl AssetLoader for PuzzlePackLoader {
+    type Asset = PuzzlePack;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader.read_to_string(&mut source).await?;
+
+            PuzzlePack::parse(&source)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["puzzle"]
+    }
+}
+
+// 当前关卡包
+#[derive(Resource, Default)]
+pub struct Puzzles {
+    pub pack: Handle<PuzzlePack>,
+    // 选择的关卡序号
+    pub selected: usize,
+}
+
+// 各关卡获得的最高星级 以关卡 id 保存
+#[derive(Resource, Debug, Default)]
+pub struct PuzzleProgress(pub HashMap<String, u8>);
+
+impl PuzzleProgress {
+    pub fn stars(&self, id: &str) -> u8 {
+        self.0.get(id).copied().unwrap_or(0)
+    }
+
+    // 记录星级 返回是否刷新了最高星级
+    pub fn record(&mut self, id: &str, stars: u8) -> bool {
+        if stars <= self.stars(id) {
+            return false;
+        }
+
+        self.0.insert(id.to_string(), stars);
+        true
+    }
+
+    pub fn parse(source: &str) -> Self {
+        Self(
+            parse_key_values(source)
+                .into_iter()
+                .filter_map(|(id, stars)| Some((id, stars.parse().ok()?)))
+                .collect(),
+        )
+    }
+
+    pub fn to_source(&self) -> String {
+        let mut pairs = self
+            .0
+            .iter()
+            .map(|(id, stars)| (id.clone(), stars.to_string()))
+            .collect::<Vec<_>>();
+        pairs.sort();
+
+        format_key_values("Smart Block puzzle progress", pairs)
+    }
+}
+
+// 星级文案 获得的星为 * 未获得为 .
+pub fn stars_text(stars: u8) -> String {
+    (0..3).map(|i| if i < stars { '*' } else { '.' }).collect()
+}
+
+// 本次解谜 挂在棋盘上
+#[derive(Component, Debug)]
+pub struct PuzzleRun {
+    pub level: usize,
+    pub par: u32,
+    pub throws: u32,
+    // 过关后获得的星级
+    pub stars: Option<u8>,
+    // 失败后重试计时
+    retry_timer: Option<Timer>,
+}
+
+pub struct PuzzlePlugin;
+
+impl Plugin for PuzzlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<PuzzlePack>()
+            .init_asset_loader::<PuzzlePackLoader>()
+            .init_resource::<Puzzles>()
+            .insert_resource(
+                read_data(PROGRESS_FILE)
+                    .map(|source| PuzzleProgress::parse(&source))
+                    .unwrap_or_default(),
+            )
+            .add_systems(Startup, load_puzzle_pack)
+            .add_systems(
+                OnEnter(GameState::GameInit),
+                setup_puzzle_run
+                    .after(spawn_boards)
+                    .run_if(resource_equals(GameMode::Puzzle)),
+            )
+            .add_systems(
+                OnEnter(GameState::InGame),
+                create_puzzle_blocks.run_if(resource_equals(GameMode::Puzzle)),
+            )
+            .add_systems(
+                PreUpdate,
+                rate_puzzle
+                    .after(apply_board_transitions::<SettlementState>)
+                    .run_if(resource_equals(GameMode::Puzzle)),
+            )
+            .add_systems(
+                Update,
+                (count_throws, handle_puzzle_stuck, retry_puzzle)
+                    .run_if(resource_equals(GameMode::Puzzle))
+                    .run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+fn load_puzzle_pack(mut puzzles: ResMut<Puzzles>, asset_server: Res<AssetServer>) {
+    puzzles.pack = asset_server.load(PUZZLE_PACK_PATH);
+}
+
+// 开始关卡 关卡显示为第几关
+fn setup_puzzle_run(
+    mut commands: Commands,
+    puzzles: Res<Puzzles>,
+    packs: Res<Assets<PuzzlePack>>,
+    mut board_query: Query<(Entity, &mut Stage), With<Board>>,
+) {
+    let Some(level) = packs
+        .get(&puzzles.pack)
+        .and_then(|pack| pack.0.get(puzzles.selected))
+    else {
+        return;
+    };
+
+    for (board, mut stage) in board_query.iter_mut() {
+        stage.0 = puzzles.selected + 1;
+        commands.entity(board).insert(PuzzleRun {
+            level: puzzles.selected,
+            par: level.par,
+            throws: 0,
+            stars: None,
+            retry_timer: None,
+        });
+    }
+}
+
+// 生成关卡方块及初始手里方块
+fn create_puzzle_blocks(
+    mut commands: Commands,
+    handle: Res<GlobalTextAtlas>,
+    puzzles: Res<Puzzles>,
+    packs: Res<Assets<PuzzlePack>>,
+    board_query: Query<Entity, With<Board>>,
+    mut hand_block_query: Query<(&mut TextureAtlas, &mut HandBlock)>,
+) {
+    let Some(level) = packs
+        .get(&puzzles.pack)
+        .and_then(|pack| pack.0.get(puzzles.selected))
+    else {
+        return;
+    };
+
+    for board in board_query.iter() {
+        commands.entity(board).with_children(|parent| {
+            for (cell, index) in level.blocks.iter() {
+                parent.spawn(block_bundle(&handle, board, cell_to_pos(*cell), *index));
+            }
+        });
+    }
+
+    for (mut atlas, mut hand_block) in hand_block_query.iter_mut() {
+        atlas.index = level.hand;
+        hand_block.index = level.hand;
+    }
+}
+
+// 统计投掷次数
+fn count_throws(mut events: EventReader<ThrowStarted>, mut board_query: Query<&mut PuzzleRun>) {
+    for event in events.read() {
+        if let Ok(mut run) = board_query.get_mut(event.board) {
+            run.throws += 1;
+        }
+    }
+}
+
+// 无法继续消除且未过关 提示后重试本关
+fn handle_puzzle_stuck(
+    mut commands: Commands,
+    mut stuck_events: EventReader<PlayerStuck>,
+    mut alert_event: EventWriter<AlertEvent>,
+    mut board_query: Query<&mut PuzzleRun>,
+) {
+    for PlayerStuck { board } in stuck_events.read() {
+        let Ok(mut run) = board_query.get_mut(*board) else {
+            continue;
+        };
+
+        if run.retry_timer.is_some() {
+            continue;
+        }
+
+        run.retry_timer = Some(Timer::from_seconds(PUZZLE_RETRY_SEC, TimerMode::Once));
+        commands.entity(*board).insert(InputLocked);
+        alert_event.send(AlertEvent {
+            board: *board,
+            key: Some(String::from("puzzle.failed")),
+        });
+    }
+}
+
+fn retry_puzzle(
+    time: Res<Time>,
+    mut board_query: Query<&mut PuzzleRun>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for mut run in board_query.iter_mut() {
+        let Some(timer) = run.retry_timer.as_mut() else {
+            continue;
+        };
+
+        if timer.tick(time.delta()).just_finished() {
+            next_state.set(GameState::GameInit);
+        }
+    }
+}
+
+// 过关时评定星级并保存进度
+fn rate_puzzle(
+    mut events: EventReader<BoardTransition<SettlementState>>,
+    puzzles: Res<Puzzles>,
+    packs: Res<Assets<PuzzlePack>>,
+    mut progress: ResMut<PuzzleProgress>,
+    mut board_query: Query<&mut PuzzleRun>,
+    block_query: Query<&InBoard, With<Block>>,
+) {
+    for event in events.read().filter(|e| e.entered(SettlementState::Start)) {
+        let Ok(mut run) = board_query.get_mut(event.board) else {
+            continue;
+        };
+        let Some(level) = packs.get(&puzzles.pack).and_then(|p| p.0.get(run.level)) else {
+            continue;
+        };
+
+        let remaining = block_query.iter().filter(|b| b.0 == event.board).count();
+        let stars = puzzle_stars(run.throws, run.par, remaining, level.perfect);
+        run.stars = Some(stars);
+
+        if progress.record(&level.id, stars) {
+            if let Err(e) = write_data(PROGRESS_FILE, &progress.to_source()) {
+                warn!("failed to save puzzle progress: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{ladder_rows, Grid};
+
+    const PACK: &str = include_str!("../assets/puzzles/pack.puzzle");
+
+    #[test]
+    fn parse_maps_bottom_row_to_block_origin() {
+        let pack = PuzzlePack::parse("level = a\nhand = ^\npar = 1\nrow = ^.\nrow = O*").unwrap();
+        let level = &pack.0[0];
+        let circle = BlockKind::Circle.atlas_index();
+        let star = BlockKind::Star.atlas_index();
+        let triangle = BlockKind::Triangle.atlas_index();
+
+        assert_eq!(level.hand, triangle);
+        assert!(level.blocks.contains(&(BLOCK_ORIGIN_CELL, circle)));
+        assert!(level
+            .blocks
+            .contains(&(BLOCK_ORIGIN_CELL + ivec2(1, 0), star)));
+        assert!(level
+            .blocks
+            .contains(&(BLOCK_ORIGIN_CELL + ivec2(0, 1), triangle)));
+        assert_eq!(level.blocks.len(), 3);
+    }
+
+    #[test]
+    fn parse_rejects_unknown_letter() {
+        assert!(PuzzlePack::parse("level = a\nrow = O?").is_err());
+        assert!(PuzzlePack::parse("level = a\nhand = ?").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_key_before_level() {
+        assert!(PuzzlePack::parse("hand = O\nlevel = a").is_err());
+    }
+
+    #[test]
+    fn stars_follow_par_and_perfect() {
+        assert_eq!(puzzle_stars(3, 3, 1, 1), 3);
+        assert_eq!(puzzle_stars(3, 3, 2, 1), 2);
+        assert_eq!(puzzle_stars(4, 3, 0, 1), 1);
+    }
+
+    // 不超过剩余投掷数能否过关 只尝试有消除的投掷
+    fn solvable(grid: &Grid, hand: usize, throws: u32) -> bool {
+        if !grid.can_remove(hand) {
+            return grid.is_cleared();
+        }
+        if throws == 0 {
+            return false;
+        }
+
+        ladder_rows().any(|row| {
+            let outcome = grid.resolve_throw(row, hand);
+            if outcome.removed.is_empty() {
+                return false;
+            }
+
+            let mut next = grid.clone();
+            next.apply_throw(&outcome);
+            solvable(&next, outcome.hand, throws - 1)
+        })
+    }
+
+    #[test]
+    fn pack_levels_are_solvable_within_par() {
+        let pack = PuzzlePack::parse(PACK).unwrap();
+        assert!(!pack.0.is_empty());
+
+        for level in pack.0.iter() {
+            let mut grid = Grid::with_terrain();
+            grid.blocks = level.blocks.iter().copied().collect();
+
+            assert!(
+                solvable(&grid, level.hand, level.par),
+                "level {} is not solvable in {} throws",
+                level.id,
+                level.par
+            );
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use bevy::prelude::*;
+use bevy::utils::HashMap;
 
-use crate::state::GameState;
+use crate::state::{GameMode, GameState};
+use crate::synth::{synthesize, SFX_HIT_WALL, SFX_REMOVED, SFX_STUCK, SFX_SWAP};
+use crate::tracker::TrackerModule;
 use crate::*;
 
 // Resource
@@ -28,16 +31,31 @@ pub struct GlobalAudio {
     // 手里块
     pub hand_block_hit_block: Option<Handle<AudioSource>>,
     pub hand_block_hit_wall: Option<Handle<AudioSource>>,
-    pub hand_block_hit_groud: Option<Handle<AudioSource>>,
+    pub hand_block_swap: Option<Handle<AudioSource>>,
     pub hand_block_black: Option<Handle<AudioSource>>,
     // 方块
     pub block_fall_down: Option<Handle<AudioSource>>,
-    pub block_despawn: Option<Handle<AudioSource>>,
+    // 消除 下标为消除数减一 越多音高越高
+    pub block_removed: Vec<Handle<AudioSource>>,
+    // 无法消除
+    pub player_stuck: Option<Handle<AudioSource>>,
+    // 过关
+    pub stage_clear: Option<Handle<AudioSource>>,
 
     // 时间清空
     pub time_clear: Option<Handle<AudioSource>>,
-    // 背景音乐
-    pub background_music: Option<Handle<AudioSource>>,
+    // 背景音乐 主题曲
+    pub background_music: Option<Handle<TrackerModule>>,
+    // 各模式的关卡曲目 没有单独曲目的模式使用主题曲
+    pub stage_music: HashMap<GameMode, Handle<TrackerModule>>,
+}
+
+impl GlobalAudio {
+    // 场景使用的曲目 菜单为主题曲 关卡中按模式选择
+    pub fn music_track(&self, mode: Option<GameMode>) -> Option<&Handle<TrackerModule>> {
+        mode.and_then(|mode| self.stage_music.get(&mode))
+            .or(self.background_music.as_ref())
+    }
 }
 impl Default for GlobalAudio {
     fn default() -> Self {
@@ -46,10 +64,13 @@ impl Default for GlobalAudio {
             player_throw: None,
             hand_block_hit_block: None,
             hand_block_hit_wall: None,
-            hand_block_hit_groud: None,
+            hand_block_swap: None,
             block_fall_down: None,
-            block_despawn: None,
+            block_removed: vec![],
+            player_stuck: None,
+            stage_clear: None,
             background_music: None,
+            stage_music: HashMap::new(),
             hand_block_black: None,
             time_clear: None,
         }
@@ -71,6 +92,7 @@ fn load_assets(
     mut audio_handle: ResMut<GlobalAudio>,
     asset_server: Res<AssetServer>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut audio_sources: ResMut<Assets<AudioSource>>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     // 资源图片
@@ -92,10 +114,26 @@ fn load_assets(
     audio_handle.player_throw = Some(asset_server.load("embedded://audio/throw.ogg"));
     audio_handle.hand_block_black = Some(asset_server.load("embedded://audio/back.ogg"));
     audio_handle.hand_block_hit_block = Some(asset_server.load("embedded://audio/hit_block.wav"));
-    // audio_handle.block_fall_down = Some(asset_server.load("embedded://audio/block_fall_down.wav"));
+    audio_handle.block_fall_down = Some(asset_server.load("embedded://audio/block_fall_down.wav"));
+    audio_handle.stage_clear = Some(asset_server.load("embedded://audio/success_bell.wav"));
     audio_handle.time_clear = Some(asset_server.load("embedded://audio/success_bell.wav"));
 
-    audio_handle.background_music = Some(asset_server.load("embedded://audio/bgm.mp3"));
+    audio_handle.background_music = Some(asset_server.load("embedded://audio/theme.mod"));
+    audio_handle.stage_music.insert(
+        GameMode::Puzzle,
+        asset_server.load("embedded://audio/puzzle.mod"),
+    );
+
+    // 合成声音 没有素材的音效
+    audio_handle.hand_block_hit_wall = Some(synthesize(&SFX_HIT_WALL, &mut audio_sources));
+    audio_handle.hand_block_swap = Some(synthesize(&SFX_SWAP, &mut audio_sources));
+    audio_handle.player_stuck = Some(synthesize(&SFX_STUCK, &mut audio_sources));
+    audio_handle.block_removed = (0..SYNTH_COMBO_STEPS)
+        .map(|step| {
+            let params = SFX_REMOVED.transpose(step as f32 * SYNTH_COMBO_SEMITONES);
+            synthesize(&params, &mut audio_sources)
+        })
+        .collect();
 
     next_state.set(GameState::MainMenu);
 }
@@ -0,0 +1,622 @@
+use bevy::math::{ivec2, vec2, IVec2, Vec2};
+use bevy::utils::{HashMap, HashSet};
+use rand::Rng;
+
+use crate::*;
+
+// 游戏规则 不依赖 ECS 游戏系统与模拟器共用
+
+// 网格坐标 列从左侧墙体开始 行从底部地面开始
+pub const GRID_COLS: i32 = 25;
+pub const GRID_ROWS: i32 = 15;
+// 手里方块所在列
+pub const HAND_BLOCK_COL: i32 = 16;
+// 梯子最低行
+pub const LADDER_FIRST_ROW: i32 = 1;
+// 方块组左下角及大小
+pub const BLOCK_ORIGIN_CELL: IVec2 = IVec2::new(1, 1);
+pub const BLOCK_GROUP_ROWS: usize = 4;
+pub const BLOCK_GROUP_COLS: usize = 5;
+
+// 障碍墙 [x][y] 从左上角开始
+const OBSTACLE_WALLS: [[u8; 4]; 4] = [
+    // 墙体
+    [1, 1, 1, 1],
+    [1, 1, 1, 0],
+    [1, 1, 0, 0],
+    [1, 0, 0, 0],
+];
+
+// 网格左下角中心点的世界坐标
+fn grid_origin() -> Vec2 {
+    vec2(
+        -(PLAYFIELD_W - STEP_SIZE as f32) / 2.0,
+        -(PLAYFIELD_H - STEP_SIZE as f32) / 2.0,
+    )
+}
+
+pub fn cell_to_pos(cell: IVec2) -> Vec2 {
+    grid_origin() + cell.as_vec2() * STEP_SIZE as f32
+}
+
+pub fn pos_to_cell(pos: Vec2) -> IVec2 {
+    ((pos - grid_origin()) / STEP_SIZE as f32)
+        .round()
+        .as_ivec2()
+}
+
+// 梯子所在的所有行
+pub fn ladder_rows() -> impl Iterator<Item = i32> {
+    LADDER_FIRST_ROW..LADDER_FIRST_ROW + LADDER_NUM as i32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terrain {
+    Wall,
+    Ground,
+}
+
+// 地形 上下为地面 左右及左上角障碍为墙体
+pub fn terrain_cells() -> Vec<(IVec2, Terrain)> {
+    let mut cells = vec![];
+
+    for col in 0..GRID_COLS {
+        for row in 0..GRID_ROWS {
+            if row == 0 || row == GRID_ROWS - 1 {
+                cells.push((ivec2(col, row), Terrain::Ground));
+            } else if col == 0 || col >= 18 {
+                cells.push((ivec2(col, row), Terrain::Wall));
+            }
+        }
+    }
+
+    for (i, column) in OBSTACLE_WALLS.iter().enumerate() {
+        for (j, wall) in column.iter().enumerate() {
+            if *wall == 1 {
+                cells.push((ivec2(i as i32 + 1, GRID_ROWS - 2 - j as i32), Terrain::Wall));
+            }
+        }
+    }
+
+    cells
+}
+
+// 生成方块组 自下而上每行一组精灵图索引
+pub fn generate_stage(rng: &mut impl Rng) -> Vec<Vec<usize>> {
+    generate_block_group(rng, BLOCK_GROUP_ROWS, BLOCK_GROUP_COLS)
+}
+
+fn generate_block_group(rng: &mut impl Rng, row: usize, col: usize) -> Vec<Vec<usize>> {
+    let mut block_group = vec![];
+
+    let mut col0_index = None;
+
+    for _ in 0..row {
+        let mut _row: Vec<usize> = vec![];
+        let index = rng.gen_range(BLOCK_DISPLAY_RANGE);
+        let other_index = rng.gen_range(BLOCK_DISPLAY_RANGE);
+        for j in 0..col {
+            if j <= 1 {
+                _row.push(rng.gen_range(BLOCK_DISPLAY_RANGE));
+            } else {
+                _row.push(col0_index.unwrap_or(other_index));
+            }
+        }
+        col0_index = Some(index);
+        block_group.push(_row);
+    }
+
+    // 翻转一下
+    block_group.reverse();
+
+    block_group
+}
+
+// 一次消除的得分
+pub fn removal_score(count: u32) -> u32 {
+    let (base, multiplier) = removal_breakdown(count);
+    base * multiplier
+}
+
+// 一次消除的基础分与倍率 每块基础分相同 倍率为消除块数
+pub fn removal_breakdown(count: u32) -> (u32, u32) {
+    (count * ONCE_BLOCK_SCORE, count)
+}
+
+// 剩余时间得分
+pub fn time_score(remaining_secs: u64) -> u64 {
+    remaining_secs * EVERY_SECOND_SCORE
+}
+
+// 对战中一次消除送给对手的垃圾方块数
+pub fn garbage_count(removed: usize) -> u32 {
+    removed.saturating_sub(1) as u32
+}
+
+// 解谜星级 过关一星 不超过标准投掷数两星 同时剩余方块不多于完美数三星
+pub fn puzzle_stars(throws: u32, par: u32, remaining: usize, perfect: usize) -> u8 {
+    match (throws <= par, remaining <= perfect) {
+        (true, true) => 3,
+        (true, false) => 2,
+        _ => 1,
+    }
+}
+
+// 消除后方块下落的格数 同列下方被消除的方块数
+pub fn fall_distance(cell: IVec2, removed: &[IVec2]) -> i32 {
+    removed
+        .iter()
+        .filter(|r| r.x == cell.x && r.y < cell.y)
+        .count() as i32
+}
+
+// 方块碰撞结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitResult {
+    // 消除并继续前进
+    Remove,
+    // 交换种类后返回
+    Swap,
+    // 直接返回
+    Return,
+}
+
+// 一次投掷的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThrowState {
+    pub hand: usize,
+    // 本次投掷是否已消除
+    pub eliminated: bool,
+    // 闪电块首次碰触的方块
+    pub lightning_kind: Option<usize>,
+}
+
+impl ThrowState {
+    pub fn new(hand: usize) -> Self {
+        Self {
+            hand,
+            eliminated: false,
+            lightning_kind: None,
+        }
+    }
+
+    // 碰到方块
+    // 同种类消除 不同种类且之前有消除过则交换 闪电块消除首次碰触种类
+    pub fn hit_block(&mut self, kind: usize) -> HitResult {
+        if self.hand == LIGHT_BLOCK_INDEX {
+            return match self.lightning_kind {
+                None => {
+                    self.lightning_kind = Some(kind);
+                    HitResult::Remove
+                }
+                Some(first) if first == kind => HitResult::Remove,
+                Some(_) => HitResult::Return,
+            };
+        }
+
+        if kind == self.hand {
+            self.eliminated = true;
+            HitResult::Remove
+        } else if self.eliminated {
+            self.eliminated = false;
+            self.hand = kind;
+            HitResult::Swap
+        } else {
+            HitResult::Return
+        }
+    }
+
+    // 碰到地面
+    pub fn hit_ground(&mut self) {
+        self.eliminated = false;
+    }
+
+    // 返回后手里的方块 闪电块变为首次消除的种类
+    pub fn returned_hand(&self) -> usize {
+        self.lightning_kind.unwrap_or(self.hand)
+    }
+}
+
+// 一次投掷的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThrowOutcome {
+    pub removed: Vec<IVec2>,
+    // 交换的方块及其新种类
+    pub swapped: Option<(IVec2, usize)>,
+    pub hand: usize,
+}
+
+// 规则网格 方块与地形
+#[derive(Debug, Clone, Default)]
+pub struct Grid {
+    pub blocks: HashMap<IVec2, usize>,
+    pub walls: HashSet<IVec2>,
+    pub grounds: HashSet<IVec2>,
+}
+
+impl Grid {
+    pub fn with_terrain() -> Self {
+        let mut grid = Self::default();
+
+        for (cell, terrain) in terrain_cells() {
+            match terrain {
+                Terrain::Wall => grid.walls.insert(cell),
+                Terrain::Ground => grid.grounds.insert(cell),
+            };
+        }
+
+        grid
+    }
+
+    // 由方块组生成网格
+    pub fn from_block_group(group: &[Vec<usize>]) -> Self {
+        let mut grid = Self::with_terrain();
+
+        for (y, row) in group.iter().enumerate() {
+            for (x, index) in row.iter().enumerate() {
+                if *index > 0 {
+                    let cell = BLOCK_ORIGIN_CELL + ivec2(x as i32, y as i32);
+                    grid.blocks.insert(cell, *index);
+                }
+            }
+        }
+
+        grid
+    }
+
+    // 由方块世界坐标生成网格
+    pub fn from_positions(blocks: impl IntoIterator<Item = (Vec2, usize)>) -> Self {
+        let mut grid = Self::with_terrain();
+        grid.blocks = blocks
+            .into_iter()
+            .map(|(pos, index)| (pos_to_cell(pos), index))
+            .collect();
+
+        grid
+    }
+
+    // 是否达到获胜条件
+    pub fn is_cleared(&self) -> bool {
+        self.blocks.len() <= CLEAR_NUM
+    }
+
+    // 某一行投掷时最先碰到的方块
+    // 该行有方块则取最右侧方块 否则沿左侧墙体落下 取墙边一列最上方方块
+    pub fn target_at_row(&self, row: i32) -> Option<(IVec2, usize)> {
+        let in_row = self
+            .blocks
+            .iter()
+            .filter(|(cell, _)| cell.y == row)
+            .max_by_key(|(cell, _)| cell.x);
+
+        if let Some((cell, index)) = in_row {
+            return Some((*cell, *index));
+        }
+
+        // 左半边的墙体
+        let wall_col = self
+            .walls
+            .iter()
+            .filter(|cell| cell.y == row && cell_to_pos(**cell).x < 0.0)
+            .map(|cell| cell.x)
+            .max()?;
+
+        self.blocks
+            .iter()
+            .filter(|(cell, _)| cell.x == wall_col + 1)
+            .max_by_key(|(cell, _)| cell.y)
+            .map(|(cell, index)| (*cell, *index))
+    }
+
+    // 手里方块是否还有可消除的方块
+    pub fn can_remove(&self, hand: usize) -> bool {
+        ladder_rows().any(|row| {
+            self.target_at_row(row)
+                .is_some_and(|(_, index)| hand == LIGHT_BLOCK_INDEX || index == hand)
+        })
+    }
+
+    // 计算从某一行投掷的结果 向左移动 碰墙向下 碰地面返回
+    pub fn resolve_throw(&self, row: i32, hand: usize) -> ThrowOutcome {
+        let mut state = ThrowState::new(hand);
+        let mut cell = ivec2(HAND_BLOCK_COL, row);
+        let mut direction = IVec2::NEG_X;
+        let mut outcome = ThrowOutcome::default();
+
+        loop {
+            let next = cell + direction;
+
+            if self.grounds.contains(&next) || next.y < 0 {
+                state.hit_ground();
+                break;
+            }
+
+            if self.walls.contains(&next) {
+                if direction == IVec2::NEG_X {
+                    direction = IVec2::NEG_Y;
+                    continue;
+                }
+                break;
+            }
+
+            if let Some(kind) = self.blocks.get(&next) {
+                let before = state.hand;
+                match state.hit_block(*kind) {
+                    HitResult::Remove => outcome.removed.push(next),
+                    HitResult::Swap => {
+                        outcome.swapped = Some((next, before));
+                        break;
+                    }
+                    HitResult::Return => break,
+                }
+            }
+
+            cell = next;
+        }
+
+        outcome.hand = state.returned_hand();
+        outcome
+    }
+
+    // 应用投掷结果 消除 交换 下落
+    pub fn apply_throw(&mut self, outcome: &ThrowOutcome) {
+        if let Some((cell, kind)) = outcome.swapped {
+            self.blocks.insert(cell, kind);
+        }
+
+        for cell in outcome.removed.iter() {
+            self.blocks.remove(cell);
+        }
+
+        self.blocks = self
+            .blocks
+            .drain()
+            .map(|(cell, kind)| {
+                let distance = fall_distance(cell, &outcome.removed);
+                (cell - ivec2(0, distance), kind)
+            })
+            .collect();
+    }
+
+    // 贪心选择消除最多的行 距离相同取离当前行最近的行
+    pub fn best_throw(&self, hand: usize, current_row: i32) -> Option<i32> {
+        ladder_rows()
+            .filter_map(|row| {
+                let outcome = self.resolve_throw(row, hand);
+                (!outcome.removed.is_empty()).then_some((row, outcome.removed.len()))
+            })
+            .max_by_key(|(row, removed)| (*removed, -(row - current_row).abs()))
+            .map(|(row, _)| row)
+    }
+
+    // 机器人选择的行 没有可消除行时选最近的有方块行 投掷后触发闪电块补给
+    pub fn bot_row(&self, hand: usize, current_row: i32) -> Option<i32> {
+        self.best_throw(hand, current_row).or_else(|| {
+            ladder_rows()
+                .filter(|row| self.target_at_row(*row).is_some())
+                .min_by_key(|row| (row - current_row).abs())
+        })
+    }
+
+    // 垃圾方块 依次堆在方块组区域最矮的一列 返回落点 放不下的丢弃
+    pub fn drop_garbage(&mut self, kinds: &[usize]) -> Vec<IVec2> {
+        let cols = BLOCK_ORIGIN_CELL.x..BLOCK_ORIGIN_CELL.x + BLOCK_GROUP_COLS as i32;
+        let mut cells = vec![];
+
+        for kind in kinds {
+            let lowest = cols
+                .clone()
+                .filter_map(|x| {
+                    // 方块自底部堆叠 取最低的空位 碰到障碍墙则该列已满
+                    let cell = (1..GRID_ROWS)
+                        .map(|y| ivec2(x, y))
+                        .find(|cell| !self.blocks.contains_key(cell))?;
+                    (!self.walls.contains(&cell) && !self.grounds.contains(&cell)).then_some(cell)
+                })
+                .min_by_key(|cell| (cell.y, cell.x));
+
+            let Some(cell) = lowest else {
+                break;
+            };
+
+            self.blocks.insert(cell, *kind);
+            cells.push(cell);
+        }
+
+        cells
+    }
+
+    // 无尽模式 在最右侧方块列之后推入一列 自底部堆叠
+    // 新列到达手里方块所在列时返回 None 表示本局结束
+    pub fn push_column(&mut self, kinds: &[usize]) -> Option<Vec<IVec2>> {
+        let x = self
+            .blocks
+            .keys()
+            .map(|cell| cell.x)
+            .max()
+            .unwrap_or(BLOCK_ORIGIN_CELL.x - 1)
+            + 1;
+
+        if x >= HAND_BLOCK_COL {
+            return None;
+        }
+
+        let cells = (BLOCK_ORIGIN_CELL.y..)
+            .map(|y| ivec2(x, y))
+            .zip(kinds)
+            .take_while(|(cell, _)| !self.walls.contains(cell) && !self.grounds.contains(cell))
+            .map(|(cell, kind)| {
+                self.blocks.insert(cell, *kind);
+                cell
+            })
+            .collect();
+
+        Some(cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: usize = 8;
+    const B: usize = 9;
+    const C: usize = 10;
+
+    // 只有地形与给定方块的网格
+    fn grid(blocks: &[((i32, i32), usize)]) -> Grid {
+        let mut grid = Grid::with_terrain();
+        for ((x, y), kind) in blocks {
+            grid.blocks.insert(ivec2(*x, *y), *kind);
+        }
+        grid
+    }
+
+    #[test]
+    fn hit_block_removes_swaps_and_returns() {
+        let mut state = ThrowState::new(A);
+        assert_eq!(state.hit_block(A), HitResult::Remove);
+        assert_eq!(state.hit_block(B), HitResult::Swap);
+        assert_eq!(state.hand, B);
+        // 交换后需要再次消除才能交换
+        assert_eq!(state.hit_block(C), HitResult::Return);
+        assert_eq!(state.returned_hand(), B);
+    }
+
+    #[test]
+    fn hit_ground_resets_elimination() {
+        let mut state = ThrowState::new(A);
+        state.hit_block(A);
+        state.hit_ground();
+        assert_eq!(state.hit_block(B), HitResult::Return);
+    }
+
+    #[test]
+    fn lightning_removes_first_kind_only() {
+        let mut state = ThrowState::new(LIGHT_BLOCK_INDEX);
+        assert_eq!(state.hit_block(B), HitResult::Remove);
+        assert_eq!(state.hit_block(B), HitResult::Remove);
+        assert_eq!(state.hit_block(A), HitResult::Return);
+        assert_eq!(state.returned_hand(), B);
+    }
+
+    #[test]
+    fn resolve_throw_removes_then_swaps() {
+        let grid = grid(&[((5, 1), A), ((4, 1), A), ((3, 1), B), ((2, 1), C)]);
+        let outcome = grid.resolve_throw(1, A);
+
+        assert_eq!(outcome.removed, vec![ivec2(5, 1), ivec2(4, 1)]);
+        assert_eq!(outcome.swapped, Some((ivec2(3, 1), A)));
+        assert_eq!(outcome.hand, B);
+    }
+
+    #[test]
+    fn resolve_throw_returns_on_other_kind() {
+        let grid = grid(&[((5, 1), B), ((4, 1), A)]);
+        let outcome = grid.resolve_throw(1, A);
+
+        assert_eq!(
+            outcome,
+            ThrowOutcome {
+                hand: A,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn resolve_throw_with_lightning() {
+        let grid = grid(&[((5, 2), B), ((4, 2), B), ((3, 2), A)]);
+        let outcome = grid.resolve_throw(2, LIGHT_BLOCK_INDEX);
+
+        assert_eq!(outcome.removed, vec![ivec2(5, 2), ivec2(4, 2)]);
+        assert_eq!(outcome.swapped, None);
+        assert_eq!(outcome.hand, B);
+    }
+
+    #[test]
+    fn resolve_throw_bounces_off_wall_and_ground() {
+        // 第 11 行碰到左上角障碍墙后沿墙边落下
+        let grid = grid(&[((3, 2), A), ((3, 1), A)]);
+        let outcome = grid.resolve_throw(11, A);
+        assert_eq!(outcome.removed, vec![ivec2(3, 2), ivec2(3, 1)]);
+        assert_eq!(outcome.hand, A);
+
+        // 空行一路碰到左侧墙体 落到地面后返回
+        let outcome = Grid::with_terrain().resolve_throw(1, B);
+        assert!(outcome.removed.is_empty());
+        assert_eq!(outcome.hand, B);
+    }
+
+    #[test]
+    fn can_remove_checks_reachable_targets() {
+        // B 被 A 挡住 也不在沿墙落下的列上
+        let grid = grid(&[((7, 1), A), ((6, 1), B)]);
+
+        assert!(grid.can_remove(A));
+        assert!(!grid.can_remove(B));
+        assert!(grid.can_remove(LIGHT_BLOCK_INDEX));
+        assert!(!Grid::with_terrain().can_remove(LIGHT_BLOCK_INDEX));
+    }
+
+    #[test]
+    fn bot_row_prefers_removal_then_nearest_target() {
+        let grid = grid(&[((5, 1), A), ((5, 3), B), ((5, 6), C)]);
+
+        assert_eq!(grid.bot_row(B, 1), Some(3));
+        assert_eq!(grid.bot_row(C, 1), Some(6));
+        // 没有可消除行时取最近的有方块行
+        assert_eq!(grid.bot_row(13, 5), Some(6));
+        assert_eq!(Grid::with_terrain().bot_row(A, 1), None);
+    }
+
+    #[test]
+    fn drop_garbage_stacks_on_lowest_column() {
+        let mut grid = grid(&[((1, 1), A), ((2, 1), A), ((2, 2), A)]);
+        let cells = grid.drop_garbage(&[B, B, B, B, C]);
+
+        assert_eq!(
+            cells,
+            vec![
+                ivec2(3, 1),
+                ivec2(4, 1),
+                ivec2(5, 1),
+                ivec2(1, 2),
+                ivec2(3, 2)
+            ]
+        );
+        assert_eq!(grid.blocks.get(&ivec2(3, 2)), Some(&C));
+    }
+
+    #[test]
+    fn drop_garbage_discards_when_full() {
+        let mut grid = Grid::with_terrain();
+        let kinds = vec![A; 100];
+        let cells = grid.drop_garbage(&kinds);
+
+        // 障碍墙下方与地面之间的空位全部填满
+        assert_eq!(cells.len(), 9 + 10 + 11 + 12 + 13);
+        assert!(grid.drop_garbage(&[A]).is_empty());
+    }
+
+    #[test]
+    fn push_column_stacks_after_rightmost_column() {
+        let mut grid = grid(&[((1, 1), A), ((3, 1), A)]);
+        let cells = grid.push_column(&[B, C]).unwrap();
+
+        assert_eq!(cells, vec![ivec2(4, 1), ivec2(4, 2)]);
+        assert_eq!(grid.blocks.get(&ivec2(4, 2)), Some(&C));
+    }
+
+    #[test]
+    fn push_column_overflows_into_hand_block_column() {
+        let mut grid = grid(&[((HAND_BLOCK_COL - 2, 1), A)]);
+
+        let cells = grid.push_column(&[B; 20]).unwrap();
+        assert!(cells.iter().all(|cell| cell.x == HAND_BLOCK_COL - 1));
+        // 超出顶部地面的方块被丢弃
+        assert_eq!(cells.len(), (GRID_ROWS - 2) as usize);
+
+        assert_eq!(grid.push_column(&[B]), None);
+    }
+}
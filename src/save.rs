@@ -0,0 +1,562 @@
+use std::time::Duration;
+
+use bevy::math::vec3;
+use bevy::prelude::*;
+use bevy::window::{WindowCloseRequested, WindowFocused};
+
+use crate::attract::AttractMode;
+use crate::block::{block_bundle, Block, HandBlock};
+use crate::board::{spawn_boards, Board, InBoard};
+use crate::endless::{setup_endless, Endless};
+use crate::gui::{CountDown, Score};
+use crate::player::{Controller, Player};
+use crate::resources::GlobalTextAtlas;
+use crate::rules::{cell_to_pos, pos_to_cell};
+use crate::stage::{create_block_group, roll_stage_seed, Stage, StageSeed};
+use crate::state::{
+    BlockGroupState, BoardState, GameMode, GameState, HandBlockState, SettlementState,
+};
+use crate::storage::{format_key_values, parse_key_values, read_data, remove_data, write_data};
+use crate::*;
+
+// 中途存档文件
+const SAVE_FILE: &str = "save.txt";
+// 存档格式版本 格式变化时递增 旧版本存档不再读取
+const SAVE_VERSION: u32 = 1;
+
+// 中途存档 关卡进行中退出时保存 继续游戏时原样恢复
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveGame {
+    pub mode: GameMode,
+    pub stage: usize,
+    pub seed: u64,
+    pub score: u32,
+    // 倒计时剩余秒数
+    pub count_down: f32,
+    pub player_row: i32,
+    // 手里方块精灵图索引
+    pub hand: usize,
+    // 方块所在格子及精灵图索引
+    pub blocks: Vec<(IVec2, usize)>,
+    // 无尽模式推入进度
+    pub endless: Option<(u32, f32)>,
+}
+
+impl SaveGame {
+    // 可以存档的模式
+    fn mode_key(mode: GameMode) -> Option<&'static str> {
+        match mode {
+            GameMode::Single => Some("single"),
+            GameMode::Endless => Some("endless"),
+            _ => None,
+        }
+    }
+
+    fn is_block_index(index: usize) -> bool {
+        BLOCK_DISPLAY_RANGE.contains(&index) || index == LIGHT_BLOCK_INDEX
+    }
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let pairs = parse_key_values(source);
+        let get = |key: &str| {
+            pairs
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+                .ok_or_else(|| format!("missing {}", key))
+        };
+        fn number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+            value
+                .parse()
+                .map_err(|_| format!("invalid {}: {}", key, value))
+        }
+
+        let version: u32 = number("version", get("version")?)?;
+        if version != SAVE_VERSION {
+            return Err(format!(
+                "incompatible save version {} (expected {})",
+                version, SAVE_VERSION
+            ));
+        }
+
+        let mode = [GameMode::Single, GameMode::Endless]
+            .into_iter()
+            .find(|mode| Self::mode_key(*mode) == get("mode").ok())
+            .ok_or_else(|| String::from("invalid mode"))?;
+
+        let blocks = get("blocks")?
+            .split_whitespace()
+            .map(|block| {
+                let (cell, index) = block
+                    .split_once(':')
+                    .ok_or_else(|| format!("invalid block: {}", block))?;
+                let (x, y) = cell
+                    .split_once(',')
+                    .ok_or_else(|| format!("invalid block: {}", block))?;
+                let index: usize = number("block", index)?;
+                if !Self::is_block_index(index) {
+                    return Err(format!("invalid block: {}", block));
+                }
+                Ok((IVec2::new(number("block", x)?, number("block", y)?), index))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let endless = match mode {
+            GameMode::Endless => Some((
+                number("endless.pushes", get("endless.pushes")?)?,
+                number("endless.push_elapsed", get("endless.push_elapsed")?)?,
+            )),
+            _ => None,
+        };
+
+        let hand: usize = number("hand", get("hand")?)?;
+        if !Self::is_block_index(hand) {
+            return Err(format!("invalid hand: {}", hand));
+        }
+
+        Ok(Self {
+            mode,
+            stage: number("stage", get("stage")?)?,
+            seed: number("seed", get("seed")?)?,
+            score: number("score", get("score")?)?,
+            count_down: number("count_down", get("count_down")?)?,
+            player_row: number("player_row", get("player_row")?)?,
+            hand,
+            blocks,
+            endless,
+        })
+    }
+
+    pub fn to_source(&self) -> String {
+        let blocks: Vec<String> = self
+            .blocks
+            .iter()
+            .map(|(cell, index)| format!("{},{}:{}", cell.x, cell.y, index))
+            .collect();
+
+        let mut pairs = vec![
+            ("version", SAVE_VERSION.to_string()),
+            (
+                "mode",
+                Self::mode_key(self.mode).unwrap_or_default().to_string(),
+            ),
+            ("stage", self.stage.to_string()),
+            ("seed", self.seed.to_string()),
+            ("score", self.score.to_string()),
+            ("count_down", format!("{:.3}", self.count_down)),
+            ("player_row", self.player_row.to_string()),
+            ("hand", self.hand.to_string()),
+            ("blocks", blocks.join(" ")),
+        ];
+        if let Some((pushes, push_elapsed)) = self.endless {
+            pairs.push(("endless.pushes", pushes.to_string()));
+            pairs.push(("endless.push_elapsed", format!("{:.3}", push_elapsed)));
+        }
+
+        format_key_values(
+            "Smart Block save",
+            pairs.into_iter().map(|(k, v)| (k.to_string(), v)),
+        )
+    }
+}
+
+// 启动时读取的存档 主菜单据此显示继续游戏
+#[derive(Resource, Default)]
+pub struct SavedGame(pub Option<SaveGame>);
+
+// 正在恢复的存档 进入游戏后移除
+#[derive(Resource)]
+pub struct PendingRestore(pub SaveGame);
+
+// 待完成的保存 棋盘正忙时等到空闲再保存 关闭窗口的请求随之推迟
+#[derive(Resource, Default)]
+pub struct SaveRequest {
+    // 保存后关闭的窗口
+    pub close: Option<Entity>,
+}
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SavedGame(load_save()))
+            .add_systems(
+                OnEnter(GameState::GameInit),
+                restore_board
+                    .after(spawn_boards)
+                    .after(roll_stage_seed)
+                    .after(setup_endless)
+                    .run_if(resource_exists::<PendingRestore>),
+            )
+            .add_systems(
+                OnEnter(GameState::InGame),
+                restore_board_content
+                    .after(create_block_group)
+                    .run_if(resource_exists::<PendingRestore>),
+            )
+            // 关卡结束或放弃 存档作废
+            .add_systems(OnExit(GameState::InGame), discard_save)
+            // 关闭窗口或窗口失去焦点时保存
+            .add_systems(
+                Last,
+                (
+                    request_save,
+                    save_game.run_if(resource_exists::<SaveRequest>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn load_save() -> Option<SaveGame> {
+    let source = read_data(SAVE_FILE)?;
+    match SaveGame::parse(&source) {
+        Ok(save) => Some(save),
+        Err(e) => {
+            warn!("ignoring save file: {}", e);
+            None
+        }
+    }
+}
+
+// 当前模式可以存档
+fn can_save(mode: GameMode, attract_mode: &AttractMode, state: GameState) -> bool {
+    !attract_mode.0
+        && SaveGame::mode_key(mode).is_some()
+        && matches!(state, GameState::GameInit | GameState::InGame)
+}
+
+// 关闭窗口时先保存 无需保存则直接关闭 失去焦点时只保存
+fn request_save(
+    mut commands: Commands,
+    mut close_events: EventReader<WindowCloseRequested>,
+    mut focus_events: EventReader<WindowFocused>,
+    mode: Res<GameMode>,
+    attract_mode: Res<AttractMode>,
+    state: Res<State<GameState>>,
+    mut request: Option<ResMut<SaveRequest>>,
+) {
+    let saving = can_save(*mode, &attract_mode, *state.get());
+
+    for event in close_events.read() {
+        if !saving {
+            commands.entity(event.window).despawn();
+            continue;
+        }
+
+        match request.as_mut() {
+            Some(request) => request.close = Some(event.window),
+            None => commands.insert_resource(SaveRequest {
+                close: Some(event.window),
+            }),
+        }
+    }
+
+    let unfocused = focus_events.read().any(|event| !event.focused);
+    if saving && unfocused && request.is_none() {
+        commands.init_resource::<SaveRequest>();
+    }
+}
+
+// 棋盘空闲时保存 投掷 下落及结算中途无法原样恢复 等到空闲或进入下一关后再保存
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn save_game(
+    mut commands: Commands,
+    request: Res<SaveRequest>,
+    mode: Res<GameMode>,
+    state: Res<State<GameState>>,
+    seed: Res<StageSeed>,
+    mut saved_game: ResMut<SavedGame>,
+    board_query: Query<(
+        Entity,
+        &Controller,
+        &Stage,
+        &Score,
+        &CountDown,
+        &BoardState<HandBlockState>,
+        &BoardState<BlockGroupState>,
+        &BoardState<SettlementState>,
+        Option<&Endless>,
+    )>,
+    player_query: Query<(&Transform, &InBoard), With<Player>>,
+    hand_block_query: Query<(&TextureAtlas, &InBoard), With<HandBlock>>,
+    block_query: Query<(&Transform, &TextureAtlas, &InBoard), With<Block>>,
+) {
+    match state.get() {
+        // 结算后进入下一关 等关卡开始
+        GameState::GameInit => return,
+        GameState::InGame => {}
+        // 回到菜单 没有可保存的关卡
+        _ => {
+            finish_save_request(&mut commands, &request);
+            return;
+        }
+    }
+
+    let Ok((board, controller, stage, score, count_down, hand, group, settlement, endless)) =
+        board_query.get_single()
+    else {
+        finish_save_request(&mut commands, &request);
+        return;
+    };
+
+    if !hand.is(HandBlockState::Idle)
+        || !group.is(BlockGroupState::Static)
+        || !settlement.is(SettlementState::Not)
+    {
+        return;
+    }
+
+    let (Some(player), Some(hand)) = (
+        player_query.iter().find(|(_, b)| b.0 == board),
+        hand_block_query.iter().find(|(_, b)| b.0 == board),
+    ) else {
+        finish_save_request(&mut commands, &request);
+        return;
+    };
+
+    if *controller == Controller::Keyboard {
+        let save = SaveGame {
+            mode: *mode,
+            stage: stage.0,
+            seed: seed.0,
+            score: score.total_score,
+            count_down: count_down.0.remaining_secs(),
+            player_row: pos_to_cell(player.0.translation.truncate()).y,
+            hand: hand.0.index,
+            blocks: block_query
+                .iter()
+                .filter(|(_, _, in_board)| in_board.0 == board)
+                .map(|(transform, atlas, _)| {
+                    (pos_to_cell(transform.translation.truncate()), atlas.index)
+                })
+                .collect(),
+            endless: endless.map(|endless| (endless.pushes(), endless.push_elapsed())),
+        };
+
+        match write_data(SAVE_FILE, &save.to_source()) {
+            Ok(()) => {
+                info!("game saved at stage {}", save.stage);
+                saved_game.0 = Some(save);
+            }
+            Err(e) => warn!("failed to save game: {}", e),
+        }
+    }
+
+    finish_save_request(&mut commands, &request);
+}
+
+fn finish_save_request(commands: &mut Commands, request: &SaveRequest) {
+    if let Some(window) = request.close {
+        commands.entity(window).despawn();
+    }
+    commands.remove_resource::<SaveRequest>();
+}
+
+// 恢复整局状态 分数 倒计时 关卡及种子
+fn restore_board(
+    mut commands: Commands,
+    restore: Res<PendingRestore>,
+    mut seed: ResMut<StageSeed>,
+    mut board_query: Query<(Entity, &mut Stage, &mut Score, &mut CountDown), With<Board>>,
+) {
+    let save = &restore.0;
+    seed.0 = save.seed;
+
+    for (board, mut stage, mut score, mut count_down) in board_query.iter_mut() {
+        stage.0 = save.stage;
+        score.total_score = save.score;
+
+        let duration = count_down.0.duration();
+        let left = Duration::from_secs_f32(save.count_down.max(0.0)).min(duration);
+        count_down.0.set_elapsed(duration - left);
+
+        if let Some((pushes, push_elapsed)) = save.endless {
+            commands
+                .entity(board)
+                .insert(Endless::resume(pushes, push_elapsed));
+        }
+    }
+}
+
+// 恢复方块 手里方块及玩家所在行 替代按种子生成的方块组
+#[allow(clippy::type_complexity)]
+fn restore_board_content(
+    mut commands: Commands,
+    restore: Res<PendingRestore>,
+    handle: Res<GlobalTextAtlas>,
+    board_query: Query<Entity, With<Board>>,
+    mut player_query: Query<(&mut Transform, &InBoard), With<Player>>,
+    mut hand_block_query: Query<
+        (&mut Transform, &mut TextureAtlas, &InBoard),
+        (With<HandBlock>, Without<Player>),
+    >,
+) {
+    let save = &restore.0;
+    let (x, _) = PLAYER_INIT_POS;
+    let y = cell_to_pos(IVec2::new(0, save.player_row)).y;
+
+    for board in board_query.iter() {
+        commands.entity(board).with_children(|parent| {
+            for (cell, index) in save.blocks.iter() {
+                parent.spawn(block_bundle(&handle, board, cell_to_pos(*cell), *index));
+            }
+        });
+
+        for (mut transform, in_board) in player_query.iter_mut() {
+            if in_board.0 == board {
+                transform.translation = vec3(x, y, 1.0);
+            }
+        }
+        for (mut transform, mut atlas, in_board) in hand_block_query.iter_mut() {
+            if in_board.0 == board {
+                transform.translation = vec3(x - STEP_SIZE as f32, y, 1.0);
+                atlas.index = save.hand;
+            }
+        }
+    }
+
+    commands.remove_resource::<PendingRestore>();
+}
+
+// 存档只在读取前有效 进入下一关或结束后删除
+fn discard_save(mut saved_game: ResMut<SavedGame>) {
+    if saved_game.0.take().is_some() {
+        if let Err(e) = remove_data(SAVE_FILE) {
+            warn!("failed to remove save: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single() -> SaveGame {
+        SaveGame {
+            mode: GameMode::Single,
+            stage: 3,
+            seed: 1234567890123,
+            score: 4200,
+            count_down: 42.5,
+            player_row: 5,
+            hand: LIGHT_BLOCK_INDEX,
+            blocks: vec![(IVec2::new(1, 1), 8), (IVec2::new(2, 1), 13)],
+            endless: None,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let save = single();
+        assert_eq!(SaveGame::parse(&save.to_source()), Ok(save));
+
+        let save = SaveGame {
+            mode: GameMode::Endless,
+            blocks: vec![],
+            endless: Some((7, 1.25)),
+            ..single()
+        };
+        assert_eq!(SaveGame::parse(&save.to_source()), Ok(save));
+    }
+
+    #[test]
+    fn rejects_other_version() {
+        let source = single().to_source().replace(
+            &format!("version = {}", SAVE_VERSION),
+            &format!("version = {}", SAVE_VERSION + 1),
+        );
+        assert!(SaveGame::parse(&source).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_block_index() {
+        let save = SaveGame {
+            blocks: vec![(IVec2::new(1, 1), 3)],
+            ..single()
+        };
+        assert!(SaveGame::parse(&save.to_source()).is_err());
+
+        let save = SaveGame {
+            hand: 0,
+            ..single()
+        };
+        assert!(SaveGame::parse(&save.to_source()).is_err());
+    }
+
+    #[test]
+    fn rejects_missing_key() {
+        let source = single()
+            .to_source()
+            .lines()
+            .filter(|line| !line.starts_with("seed"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(SaveGame::parse(&source).is_err());
+    }
+
+    // 投掷途中关闭窗口 方块回到手里后才保存并关闭
+    #[test]
+    fn close_waits_until_board_is_idle() {
+        let dir = std::env::temp_dir().join(format!("smart-block-save-{}", std::process::id()));
+        std::env::set_var("SMART_BLOCK_DATA_DIR", &dir);
+
+        let mut app = App::new();
+        app.add_event::<WindowCloseRequested>()
+            .add_event::<WindowFocused>()
+            .insert_state(GameState::InGame)
+            .insert_resource(GameMode::Single)
+            .init_resource::<AttractMode>()
+            .init_resource::<StageSeed>()
+            .add_plugins(SavePlugin);
+
+        let mut hand_state = BoardState::default();
+        hand_state.reset(HandBlockState::Moving);
+        let board = app
+            .world
+            .spawn((
+                Controller::Keyboard,
+                Stage::default(),
+                Score::default(),
+                CountDown::default(),
+                hand_state,
+                BoardState::<BlockGroupState>::default(),
+                BoardState::<SettlementState>::default(),
+            ))
+            .id();
+        let atlas = |index| TextureAtlas {
+            layout: Handle::default(),
+            index,
+        };
+        app.world
+            .spawn((Player, Transform::default(), InBoard(board)));
+        app.world
+            .spawn((HandBlock::default(), atlas(8), InBoard(board)));
+        app.world.spawn((
+            Block {
+                show: true,
+                pos: Vec2::ZERO,
+            },
+            Transform::from_translation(cell_to_pos(IVec2::new(2, 1)).extend(1.0)),
+            atlas(9),
+            InBoard(board),
+        ));
+        let window = app.world.spawn_empty().id();
+
+        app.world.send_event(WindowCloseRequested { window });
+        app.update();
+        assert!(app.world.get_entity(window).is_some());
+        assert!(app.world.resource::<SavedGame>().0.is_none());
+
+        app.world
+            .get_mut::<BoardState<HandBlockState>>(board)
+            .unwrap()
+            .reset(HandBlockState::Idle);
+        app.update();
+        assert!(app.world.get_entity(window).is_none());
+        let save = app.world.resource::<SavedGame>().0.clone().unwrap();
+        assert_eq!((save.hand, save.blocks), (8, vec![(IVec2::new(2, 1), 9)]));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,204 @@
+use bevy::prelude::*;
+
+use crate::accessibility::{next_background_color, AccessibilitySettings};
+use crate::audio::{next_volume, AudioSettings};
+use crate::effects::{next_intensity, EffectSettings};
+use crate::locale::{Locale, LocalizedText};
+use crate::menu::{
+    despawn_screen, menu_button, menu_root, spawn_back_button, spawn_menu_title, MenuActivated,
+    MenuButton,
+};
+use crate::state::GameState;
+use crate::*;
+
+#[derive(Component)]
+struct SettingsItem;
+
+// 设置项 除特效强度外与快捷键 F2-F7 对应
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingsButton {
+    Language,
+    HighContrast,
+    KindOverlays,
+    LargeText,
+    Background,
+    MusicVolume,
+    SfxVolume,
+    Mute,
+    Effects,
+    Back,
+}
+
+// 设置项当前值文本
+#[derive(Component)]
+struct SettingsValue(SettingsButton);
+
+// 背景色预览
+#[derive(Component)]
+struct BackgroundSwatch;
+
+pub struct SettingsScreenPlugin;
+
+impl Plugin for SettingsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Settings), setup_settings_screen)
+            .add_systems(OnExit(GameState::Settings), despawn_screen::<SettingsItem>)
+            .add_systems(
+                Update,
+                (handle_settings_buttons, update_settings_values)
+                    .chain()
+                    .run_if(in_state(GameState::Settings)),
+            );
+    }
+}
+
+// 生成设置界面 每项左侧名称右侧当前值
+fn setup_settings_screen(mut commands: Commands) {
+    commands
+        .spawn((menu_root(4.0), SettingsItem))
+        .with_children(|parent| {
+            spawn_menu_title(parent, LocalizedText::new("settings.title"));
+
+            for (key, button) in [
+                ("settings.language", SettingsButton::Language),
+                ("settings.high_contrast", SettingsButton::HighContrast),
+                ("settings.kind_overlays", SettingsButton::KindOverlays),
+                ("settings.large_text", SettingsButton::LargeText),
+                ("settings.background", SettingsButton::Background),
+                ("settings.music_volume", SettingsButton::MusicVolume),
+                ("settings.sfx_volume", SettingsButton::SfxVolume),
+                ("settings.mute", SettingsButton::Mute),
+                ("settings.effects", SettingsButton::Effects),
+            ] {
+                spawn_settings_button(parent, key, button);
+            }
+
+            spawn_back_button(parent, SettingsButton::Back);
+        });
+}
+
+fn spawn_settings_button(parent: &mut ChildBuilder, key: &'static str, button: SettingsButton) {
+    let mut bundle = menu_button(SETTINGS_ROW_W, MENU_BUTTON_H);
+    bundle.style.flex_direction = FlexDirection::Row;
+    bundle.style.justify_content = JustifyContent::SpaceBetween;
+    bundle.style.padding = UiRect::horizontal(Val::Px(16.0));
+
+    let text_style = TextStyle {
+        font_size: 32.0,
+        color: Color::BLACK,
+        ..default()
+    };
+
+    parent
+        .spawn((bundle, MenuButton, button))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section("", text_style.clone()),
+                LocalizedText::new(key),
+            ));
+
+            if button == SettingsButton::Background {
+                parent.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Px(64.0),
+                            height: Val::Px(32.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: Color::BLACK.into(),
+                        ..default()
+                    },
+                    BackgroundSwatch,
+                ));
+            } else {
+                parent.spawn((
+                    TextBundle::from_section("", text_style),
+                    LocalizedText::new("settings.off"),
+                    SettingsValue(button),
+                ));
+            }
+        });
+}
+
+fn handle_settings_buttons(
+    mut activated_events: EventReader<MenuActivated>,
+    button_query: Query<&SettingsButton>,
+    mut locale: ResMut<Locale>,
+    mut settings: ResMut<AccessibilitySettings>,
+    mut audio_settings: ResMut<AudioSettings>,
+    mut effect_settings: ResMut<EffectSettings>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in activated_events.read() {
+        let Ok(button) = button_query.get(event.0) else {
+            continue;
+        };
+
+        match button {
+            SettingsButton::Language => locale.language = locale.language.next(),
+            SettingsButton::HighContrast => settings.high_contrast = !settings.high_contrast,
+            SettingsButton::KindOverlays => settings.kind_overlays = !settings.kind_overlays,
+            SettingsButton::LargeText => settings.large_text = !settings.large_text,
+            SettingsButton::Background => {
+                settings.background_color = next_background_color(settings.background_color);
+            }
+            SettingsButton::MusicVolume => {
+                audio_settings.music_volume = next_volume(audio_settings.music_volume);
+            }
+            SettingsButton::SfxVolume => {
+                audio_settings.sfx_volume = next_volume(audio_settings.sfx_volume);
+            }
+            SettingsButton::Mute => audio_settings.muted = !audio_settings.muted,
+            SettingsButton::Effects => {
+                effect_settings.intensity = next_intensity(effect_settings.intensity);
+            }
+            SettingsButton::Back => next_state.set(GameState::MainMenu),
+        }
+    }
+}
+
+// 设置变化或界面生成时刷新显示的值 快捷键修改同样生效
+fn update_settings_values(
+    settings: Res<AccessibilitySettings>,
+    audio_settings: Res<AudioSettings>,
+    effect_settings: Res<EffectSettings>,
+    added_query: Query<(), Added<SettingsValue>>,
+    mut value_query: Query<(&SettingsValue, &mut LocalizedText)>,
+    mut swatch_query: Query<&mut BackgroundColor, With<BackgroundSwatch>>,
+) {
+    if !settings.is_changed()
+        && !audio_settings.is_changed()
+        && !effect_settings.is_changed()
+        && added_query.is_empty()
+    {
+        return;
+    }
+
+    let on_off = |on| LocalizedText::new(if on { "settings.on" } else { "settings.off" });
+    let percent = |volume: f32| {
+        LocalizedText::new("settings.percent")
+            .with_args(vec![((volume * 100.0).round() as u32).to_string()])
+    };
+    for (SettingsValue(button), mut text) in value_query.iter_mut() {
+        let value = match button {
+            SettingsButton::Language => LocalizedText::new("settings.language_name"),
+            SettingsButton::HighContrast => on_off(settings.high_contrast),
+            SettingsButton::KindOverlays => on_off(settings.kind_overlays),
+            SettingsButton::LargeText => on_off(settings.large_text),
+            SettingsButton::MusicVolume => percent(audio_settings.music_volume),
+            SettingsButton::SfxVolume => percent(audio_settings.sfx_volume),
+            SettingsButton::Mute => on_off(audio_settings.muted),
+            SettingsButton::Effects => percent(effect_settings.intensity),
+            SettingsButton::Background | SettingsButton::Back => continue,
+        };
+
+        if text.key != value.key || text.args != value.args {
+            *text = value;
+        }
+    }
+
+    for mut color in swatch_query.iter_mut() {
+        *color = settings.background_color.into();
+    }
+}
@@ -1,49 +1,47 @@
 use bevy::prelude::*;
 use std::time::Duration;
 
+use crate::animation::{AnimationFinished, SpriteAnimation};
+use crate::audio::PlaySfx;
 use crate::block::Block;
-use crate::gui::{CountDown, Score, TextScore};
+use crate::board::{Board, BoardUi, InBoard};
+use crate::gui::{CountDown, Score};
+use crate::locale::LocalizedText;
+use crate::puzzle::{stars_text, PuzzleRun};
 use crate::resources::GlobalAudio;
+use crate::rules::time_score;
 use crate::stage::Stage;
-use crate::state::{GameState, SettlementState};
+use crate::state::{BoardState, BoardTransition, GameMode, GameState, SettlementState};
+use crate::versus::RoundWinner;
 use crate::world::GameEntity;
 use crate::*;
 
 pub struct SettlementPlugin;
 
-// 时间转化分数
-#[derive(Resource)]
-pub struct TimeToScore(u64);
-impl Default for TimeToScore {
-    fn default() -> Self {
-        Self(0)
-    }
-}
-
-// 结算开始倒计时
-#[derive(Resource)]
-struct SettleStartTimer(Timer);
-impl Default for SettleStartTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(1.0, TimerMode::Repeating))
-    }
+// 棋盘结算 计时及时间转化分数
+#[derive(Component)]
+pub struct Settlement {
+    // 结算开始倒计时
+    start_timer: Timer,
+    // 时间转化分数
+    time_to_score: u64,
+    // 剩余时间
+    remain_time: Option<u64>,
 }
-
-// 消除每一个方块倒计时
-#[derive(Resource)]
-struct DespawnBlockTimer(Timer);
-impl Default for DespawnBlockTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(0.2, TimerMode::Repeating))
+impl Settlement {
+    // 本关剩余时间转化的分数
+    pub fn time_bonus(&self) -> u64 {
+        self.time_to_score
     }
 }
 
-// 剩余时间
-#[derive(Resource)]
-struct RemainTime(Option<u64>);
-impl Default for RemainTime {
+impl Default for Settlement {
     fn default() -> Self {
-        Self(None)
+        Self {
+            start_timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+            time_to_score: 0,
+            remain_time: None,
+        }
     }
 }
 
@@ -52,205 +50,313 @@ struct TextTimeToScore;
 
 impl Plugin for SettlementPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<TimeToScore>()
-            .init_resource::<RemainTime>()
-            .init_state::<SettlementState>()
-            .init_resource::<SettleStartTimer>()
-            .init_resource::<DespawnBlockTimer>()
-            .add_systems(
-                OnEnter(SettlementState::Start),
-                spawn_settlement.run_if(in_state(GameState::InGame)),
-            )
-            .add_systems(
-                Update,
-                (
-                    // 开始结算
-                    settle_start.run_if(in_state(SettlementState::Start)),
-                    // 消除方块
-                    despawn_remainder_block.run_if(in_state(SettlementState::DespawnBlock)),
-                    // 计算分数
-                    time_to_score.run_if(in_state(SettlementState::TimeToScore)),
-                    // 结算结束
-                ),
+        app.add_systems(
+            Update,
+            (
+                spawn_settlement,
+                // 开始结算
+                settle_start,
+                // 消除方块
+                despawn_remainder_block,
+                // 计算分数
+                time_to_score,
+                // 结算结束
+                update_total_score,
             )
-            .add_systems(OnEnter(SettlementState::End), update_total_score);
+                .run_if(in_state(GameState::InGame)),
+        );
     }
 }
 
 // 生成结算
-fn spawn_settlement(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let font_handle = asset_server.load(FONT_PATH);
-    commands
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    width: Val::Percent(100.0),
-                    height: Val::Percent(100.0),
-                    display: Display::Flex,
-                    flex_direction: FlexDirection::Column,
-                    justify_content: JustifyContent::Center,
-                    align_items: AlignItems::Center,
+fn spawn_settlement(
+    mut commands: Commands,
+    mut events: EventReader<BoardTransition<SettlementState>>,
+    asset_server: Res<AssetServer>,
+    mode: Res<GameMode>,
+    board_query: Query<(&Board, Has<RoundWinner>)>,
+    puzzle_query: Query<&PuzzleRun>,
+    ui_query: Query<(Entity, &InBoard), With<BoardUi>>,
+) {
+    for event in events.read().filter(|e| e.entered(SettlementState::Start)) {
+        let Some((ui, _)) = ui_query.iter().find(|(_, b)| b.0 == event.board) else {
+            continue;
+        };
+
+        let puzzle_run = puzzle_query.get(event.board).ok();
+        let (title, subtitle) = if mode.is_versus() {
+            versus_texts(event.board, &board_query)
+        } else if let Some(run) = puzzle_run {
+            (
+                LocalizedText::new("settlement.clear"),
+                LocalizedText::new("puzzle.result").with_args(vec![
+                    run.throws.to_string(),
+                    run.par.to_string(),
+                    stars_text(run.stars.unwrap_or(0)),
+                ]),
+            )
+        } else {
+            (
+                LocalizedText::new("settlement.clear"),
+                LocalizedText::new("settlement.congratulations"),
+            )
+        };
+
+        let font_handle = asset_server.load(FONT_PATH);
+        let text_style = TextStyle {
+            font_size: 32.0,
+            font: font_handle,
+            ..default()
+        };
+
+        let overlay = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
                     ..default()
                 },
-                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
-                ..default()
-            },
-            GameEntity,
-        ))
-        .with_children(|parent| {
-            parent.spawn(TextBundle {
-                text: Text::from_section(
-                    "CLEAR!",
-                    TextStyle {
-                        font_size: 32.0,
-                        font: font_handle.clone(),
+                GameEntity,
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    TextBundle {
+                        text: Text::from_section("", text_style.clone()),
                         ..default()
                     },
-                ),
-                ..default()
-            });
-
-            parent.spawn(TextBundle {
-                text: Text::from_section(
-                    "CONGRATULATIONS!",
-                    TextStyle {
-                        font_size: 32.0,
-                        font: font_handle.clone(),
+                    title,
+                ));
+
+                parent.spawn((
+                    TextBundle {
+                        text: Text::from_section("", text_style.clone()),
                         ..default()
                     },
-                ),
-                ..default()
-            });
-
-            parent.spawn((
-                TextBundle {
-                    text: Text::from_sections([TextSection::new(
-                        "time score: 00000",
-                        TextStyle {
-                            font: font_handle.clone(),
-                            font_size: 32.0,
-                            ..default()
-                        },
-                    )]),
-                    ..default()
-                },
-                TextTimeToScore,
-            ));
-        });
+                    subtitle,
+                ));
+
+                // 解谜没有倒计时 不结算时间分数
+                if puzzle_run.is_some() {
+                    return;
+                }
+
+                parent.spawn((
+                    TextBundle {
+                        text: Text::from_section("", text_style),
+                        ..default()
+                    },
+                    TextTimeToScore,
+                    InBoard(event.board),
+                    time_score_text(0),
+                ));
+            })
+            .id();
+
+        commands.entity(ui).add_child(overlay);
+    }
+}
+
+// 对战结算文案 胜负及各玩家胜场
+fn versus_texts(
+    board: Entity,
+    board_query: &Query<(&Board, Has<RoundWinner>)>,
+) -> (LocalizedText, LocalizedText) {
+    let mut boards = board_query.iter().map(|(b, _)| b).collect::<Vec<_>>();
+    boards.sort_by_key(|b| b.index);
+
+    let title = match board_query.get(board) {
+        Ok((b, true)) => {
+            LocalizedText::new("versus.win").with_args(vec![(b.index + 1).to_string()])
+        }
+        Ok((b, false)) => {
+            LocalizedText::new("versus.lose").with_args(vec![(b.index + 1).to_string()])
+        }
+        Err(_) => LocalizedText::new("settlement.clear"),
+    };
+    let rounds = LocalizedText::new("versus.rounds")
+        .with_args(boards.iter().map(|b| b.wins.to_string()).collect());
+
+    (title, rounds)
 }
 
 // 结算总分数
 fn settle_start(
     time: Res<Time>,
-    mut settle_start_timer: ResMut<SettleStartTimer>,
-    mut next_state: ResMut<NextState<SettlementState>>,
+    mut board_query: Query<(&mut Settlement, &mut BoardState<SettlementState>)>,
 ) {
-    settle_start_timer.0.tick(time.delta());
+    for (mut settlement, mut state) in board_query.iter_mut() {
+        if !state.is(SettlementState::Start) {
+            continue;
+        }
+
+        settlement.start_timer.tick(time.delta());
 
-    if settle_start_timer.0.just_finished() {
-        next_state.set(SettlementState::DespawnBlock);
+        if settlement.start_timer.just_finished() {
+            state.set(SettlementState::DespawnBlock);
+        }
     }
 }
 
-// 销毁剩余方块
+// 销毁剩余方块 逐个播放消除片段 播放结束后销毁下一个
 fn despawn_remainder_block(
-    time: Res<Time>,
-    mut timer: ResMut<DespawnBlockTimer>,
     mut commands: Commands,
-    mut block_query: Query<(&mut TextureAtlas, Entity), With<Block>>,
-    mut next_state: ResMut<NextState<SettlementState>>,
+    mut finished_events: EventReader<AnimationFinished>,
+    mut board_query: Query<(Entity, &mut BoardState<SettlementState>)>,
+    block_query: Query<(Entity, &InBoard, Has<SpriteAnimation>), With<Block>>,
 ) {
-    if block_query.is_empty() {
-        // 没有剩余则直接进入分数统计
-        next_state.set(SettlementState::TimeToScore);
-        return;
-    }
-    timer.0.tick(time.delta());
+    let finished = finished_events
+        .read()
+        .map(|event| event.entity)
+        .collect::<Vec<_>>();
 
-    for (mut text_atlas, entity) in block_query.iter_mut() {
-        text_atlas.index = BLOCK_BEFORE_REMOVE_INDEX;
+    for (board, mut state) in board_query.iter_mut() {
+        if !state.is(SettlementState::DespawnBlock) {
+            continue;
+        }
 
-        if timer.0.just_finished() {
-            commands.entity(entity).despawn();
+        let blocks = block_query
+            .iter()
+            .filter(|(entity, in_board, _)| in_board.0 == board && !finished.contains(entity))
+            .collect::<Vec<_>>();
+        if blocks.is_empty() {
+            // 没有剩余则直接进入分数统计
+            state.set(SettlementState::TimeToScore);
+            continue;
         }
-        return;
+
+        // 上一个方块仍在播放
+        if blocks.iter().any(|(_, _, popping)| *popping) {
+            continue;
+        }
+
+        commands
+            .entity(blocks[0].0)
+            .insert(SpriteAnimation::new("block.pop").despawn_on_finish());
     }
 }
 
 // 计算剩余时间获得分数
+#[allow(clippy::type_complexity)]
 fn time_to_score(
-    mut count_down: ResMut<CountDown>,
-    mut time_to_score: ResMut<TimeToScore>,
-    mut time_to_score_text_query: Query<&mut Text, With<TextTimeToScore>>,
-    mut remain_time: Local<RemainTime>,
-    mut next_state: ResMut<NextState<SettlementState>>,
-    mut commands: Commands,
+    mut board_query: Query<(
+        Entity,
+        &mut Settlement,
+        &mut CountDown,
+        &mut BoardState<SettlementState>,
+        Has<PuzzleRun>,
+    )>,
+    mut time_to_score_text_query: Query<(&mut LocalizedText, &InBoard), With<TextTimeToScore>>,
+    mut sfx_events: EventWriter<PlaySfx>,
     audio_handles: Res<GlobalAudio>,
 ) {
-    if time_to_score_text_query.is_empty() {
-        return;
-    }
+    for (board, mut settlement, mut count_down, mut state, is_puzzle) in board_query.iter_mut() {
+        if !state.is(SettlementState::TimeToScore) {
+            continue;
+        }
 
-    let mut time_to_score_text = time_to_score_text_query.single_mut();
+        if is_puzzle {
+            state.set(SettlementState::End);
+            continue;
+        }
 
-    // 每0.5秒计算一次
-    let pass_time = 0.5;
+        let Some((mut time_to_score_text, _)) = time_to_score_text_query
+            .iter_mut()
+            .find(|(_, in_board)| in_board.0 == board)
+        else {
+            continue;
+        };
 
-    // 获取剩余时间
-    if remain_time.0.is_none() {
-        let time = count_down.0.remaining();
-        remain_time.0 = Some(time.as_secs());
-    }
+        // 每0.5秒计算一次
+        let pass_time = 0.5;
 
-    // 获取倒计时
-    count_down.0.tick(Duration::from_secs_f32(pass_time));
+        // 获取剩余时间
+        if settlement.remain_time.is_none() {
+            let time = count_down.0.remaining();
+            settlement.remain_time = Some(time.as_secs());
+        }
 
-    if count_down.0.remaining().as_secs() != 0 {
-        time_to_score.0 += (EVERY_SECOND_SCORE as f32 * pass_time) as u64;
-        time_to_score_text.sections[0].value = format!("time score: {:0>5}", time_to_score.0);
-    }
+        // 获取倒计时
+        count_down.0.tick(Duration::from_secs_f32(pass_time));
 
-    if count_down.0.just_finished() {
-        if let Some(time_clear_sound) = audio_handles.time_clear.clone() {
-            commands.spawn(AudioBundle {
-                source: time_clear_sound,
-                settings: PlaybackSettings {
-                    // mode: PlaybackMode::Once,
-                    // volume: Volume::new(2.0),
-                    ..default()
-                },
-            });
+        if count_down.0.remaining().as_secs() != 0 {
+            settlement.time_to_score += (EVERY_SECOND_SCORE as f32 * pass_time) as u64;
+            *time_to_score_text = time_score_text(settlement.time_to_score);
         }
-        if let Some(time) = remain_time.0 {
-            time_to_score.0 = (time * EVERY_SECOND_SCORE) as u64;
-            time_to_score_text.sections[0].value = format!("time score: {:0>5}", time_to_score.0);
 
-            next_state.set(SettlementState::End);
+        if count_down.0.just_finished() {
+            sfx_events.send(PlaySfx::new(&audio_handles.time_clear));
+            if let Some(time) = settlement.remain_time {
+                settlement.time_to_score = time_score(time);
+                *time_to_score_text = time_score_text(settlement.time_to_score);
+
+                state.set(SettlementState::End);
+            }
         }
     }
 }
 
-// 局部分数更新总分数
+// 时间分数文案
+fn time_score_text(time_score: u64) -> LocalizedText {
+    LocalizedText::new("settlement.time_score").with_args(vec![format!("{:0>5}", time_score)])
+}
+
+// 局部分数更新总分数 所有棋盘结算结束后进入下一关 解谜返回选关 每日挑战最后一关后返回主菜单
 fn update_total_score(
-    mut time_to_score: ResMut<TimeToScore>,
-    mut stage: ResMut<Stage>,
-    mut score: ResMut<Score>,
-    mut query: Query<&mut Text, With<TextScore>>,
+    mode: Res<GameMode>,
+    mut events: EventReader<BoardTransition<SettlementState>>,
+    mut board_query: Query<(
+        &mut Settlement,
+        &mut Score,
+        &mut Stage,
+        &BoardState<SettlementState>,
+    )>,
     mut next_state: ResMut<NextState<GameState>>,
-    mut settlement_next_state: ResMut<NextState<SettlementState>>,
 ) {
-    if query.is_empty() {
+    let mut ended = false;
+
+    for event in events.read().filter(|e| e.entered(SettlementState::End)) {
+        let Ok((mut settlement, mut score, _, _)) = board_query.get_mut(event.board) else {
+            continue;
+        };
+
+        score.total_score += settlement.time_to_score as u32;
+
+        // 清空局部分数
+        settlement.time_to_score = 0;
+        ended = true;
+    }
+
+    if !ended
+        || board_query
+            .iter()
+            .any(|(_, _, _, state)| !state.is(SettlementState::End))
+    {
         return;
     }
-    let mut text = query.single_mut();
-    score.total_score += time_to_score.0 as u32;
-    text.sections[0].value = format!("{:0>7}", score.total_score);
 
-    // 清空局部分数
-    time_to_score.0 = 0;
+    if *mode == GameMode::Puzzle {
+        next_state.set(GameState::LevelSelect);
+        return;
+    }
 
     // 更新关卡
-    stage.0 += 1;
+    let mut last_stage = 0;
+    for (_, _, mut stage, _) in board_query.iter_mut() {
+        stage.0 += 1;
+        last_stage = last_stage.max(stage.0);
+    }
+
+    if *mode == GameMode::Daily && last_stage > DAILY_STAGE_COUNT {
+        next_state.set(GameState::MainMenu);
+        return;
+    }
     next_state.set(GameState::GameInit);
-    settlement_next_state.set(SettlementState::Not);
 }
@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+
+use crate::audio::PlaySfx;
+use crate::events::{
+    BlockHit, BlocksRemoved, HandBlockReturned, HandBlockSwapped, PlayerMoved, PlayerStuck,
+    StageCleared, ThrowStarted, WallBounce,
+};
+use crate::resources::GlobalAudio;
+use crate::rules::HitResult;
+use crate::state::{BlockGroupState, BoardTransition};
+
+// 玩法音效 每个玩法事件对应一个音效 交给 audio 播放
+// 复用素材的音效通过播放速度区分
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (player_sounds, hand_block_sounds, block_sounds, stage_sounds),
+        );
+    }
+}
+
+// 玩家移动与投掷
+fn player_sounds(
+    mut moved_events: EventReader<PlayerMoved>,
+    mut throw_events: EventReader<ThrowStarted>,
+    mut sfx_events: EventWriter<PlaySfx>,
+    audio_handles: Res<GlobalAudio>,
+) {
+    for _ in moved_events.read() {
+        sfx_events.send(PlaySfx::new(&audio_handles.player_move));
+    }
+    for _ in throw_events.read() {
+        sfx_events.send(PlaySfx::new(&audio_handles.player_throw));
+    }
+}
+
+// 手里方块碰撞 交换 撞墙 返回
+fn hand_block_sounds(
+    mut hit_events: EventReader<BlockHit>,
+    mut swap_events: EventReader<HandBlockSwapped>,
+    mut bounce_events: EventReader<WallBounce>,
+    mut returned_events: EventReader<HandBlockReturned>,
+    mut sfx_events: EventWriter<PlaySfx>,
+    audio_handles: Res<GlobalAudio>,
+) {
+    for event in hit_events.read() {
+        // 消除时随后由 BlocksRemoved 播放
+        if event.result != HitResult::Remove {
+            sfx_events.send(PlaySfx::new(&audio_handles.hand_block_hit_block).with_speed(0.8));
+        }
+    }
+    for _ in swap_events.read() {
+        sfx_events.send(PlaySfx::new(&audio_handles.hand_block_swap));
+    }
+    for _ in bounce_events.read() {
+        sfx_events.send(PlaySfx::new(&audio_handles.hand_block_hit_wall));
+    }
+    for _ in returned_events.read() {
+        sfx_events.send(PlaySfx::new(&audio_handles.hand_block_black));
+    }
+}
+
+// 消除与下落 消除音效一次消除越多音高越高
+fn block_sounds(
+    mut removed_events: EventReader<BlocksRemoved>,
+    mut transition_events: EventReader<BoardTransition<BlockGroupState>>,
+    mut sfx_events: EventWriter<PlaySfx>,
+    audio_handles: Res<GlobalAudio>,
+) {
+    for event in removed_events.read() {
+        sfx_events.send(PlaySfx::new(&audio_handles.hand_block_hit_block));
+        let removed = &audio_handles.block_removed;
+        let step = event.count.clamp(1, removed.len().max(1)) - 1;
+        sfx_events.send(PlaySfx::new(&removed.get(step).cloned()));
+    }
+    for _ in transition_events
+        .read()
+        .filter(|e| e.exited(BlockGroupState::FallDown))
+    {
+        sfx_events.send(PlaySfx::new(&audio_handles.block_fall_down));
+    }
+}
+
+// 过关与无法消除
+fn stage_sounds(
+    mut cleared_events: EventReader<StageCleared>,
+    mut stuck_events: EventReader<PlayerStuck>,
+    mut sfx_events: EventWriter<PlaySfx>,
+    audio_handles: Res<GlobalAudio>,
+) {
+    for _ in cleared_events.read() {
+        sfx_events.send(PlaySfx::new(&audio_handles.stage_clear).with_speed(1.25));
+    }
+    for _ in stuck_events.read() {
+        sfx_events.send(PlaySfx::new(&audio_handles.player_stuck));
+    }
+}
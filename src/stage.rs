@@ -1,14 +1,17 @@
-use bevy::math::{vec2, vec3};
 use bevy::prelude::*;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
-use crate::block::Block;
+use crate::block::block_bundle;
+use crate::board::Board;
+use crate::locale::LocalizedText;
 use crate::resources::GlobalTextAtlas;
-use crate::state::GameState;
+use crate::rules::{cell_to_pos, generate_stage, Grid};
+use crate::save::PendingRestore;
+use crate::state::{GameMode, GameState};
 use crate::world::GameEntity;
-use crate::*;
 
-#[derive(Resource)]
+#[derive(Component)]
 pub struct Stage(pub usize);
 
 impl Default for Stage {
@@ -17,145 +20,107 @@ impl Default for Stage {
     }
 }
 
-#[derive(Resource)]
-pub struct DespawnStageTextTimer(Timer);
-impl Default for DespawnStageTextTimer {
+// 关卡种子 相同种子生成相同方块组 与模拟器一致 对战时双方共用
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct StageSeed(pub u64);
+
+impl Default for StageSeed {
     fn default() -> Self {
-        Self(Timer::from_seconds(2.0, TimerMode::Repeating))
+        Self(rand::thread_rng().gen())
     }
 }
+
+// 关卡提示文案 计时结束销毁
 #[derive(Component)]
-pub struct StageText;
+pub struct StageText(Timer);
+impl Default for StageText {
+    fn default() -> Self {
+        Self(Timer::from_seconds(2.0, TimerMode::Once))
+    }
+}
 
 pub struct StagePlugin;
 
 impl Plugin for StagePlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Stage>()
-            .init_resource::<DespawnStageTextTimer>()
+        app.init_resource::<StageSeed>()
+            .add_systems(OnEnter(GameState::GameInit), roll_stage_seed)
             .add_systems(OnEnter(GameState::InGame), spawn_game_stage)
             .add_systems(
                 Update,
                 despawn_stage_text.run_if(in_state(GameState::InGame)),
             )
-            .add_systems(OnEnter(GameState::InGame), create_block_group);
+            .add_systems(
+                OnEnter(GameState::InGame),
+                // 解谜关卡的方块由关卡包生成 读档时由存档恢复
+                create_block_group
+                    .run_if(not(resource_equals(GameMode::Puzzle)))
+                    .run_if(not(resource_exists::<PendingRestore>)),
+            );
     }
 }
 
-fn spawn_game_stage(mut commands: Commands, stage: Res<Stage>) {
+fn spawn_game_stage(mut commands: Commands, board_query: Query<(Entity, &Stage), With<Board>>) {
     // 生成关卡提示文案
-    let text = format!("{} {}", STAGE_TEXT, stage.0);
-    commands.spawn((
-        Text2dBundle {
-            text: Text::from_section(
-                text,
-                TextStyle {
-                    font_size: 40.0,
-                    color: Color::WHITE,
+    for (board, stage) in board_query.iter() {
+        commands.entity(board).with_children(|parent| {
+            parent.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 40.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ),
+                    transform: Transform::from_xyz(0.0, 0.0, 2.0),
                     ..default()
                 },
-            ),
-            ..default()
-        },
-        StageText,
-    ));
+                StageText::default(),
+                GameEntity,
+                LocalizedText::new("stage.banner").with_args(vec![stage.0.to_string()]),
+            ));
+        });
+    }
 }
 
-// 销毁关卡文案  1s延时
+// 销毁关卡文案  2s延时
 fn despawn_stage_text(
     time: Res<Time>,
     mut commands: Commands,
-    mut timer: ResMut<DespawnStageTextTimer>,
-    mut text_query: Query<Entity, With<StageText>>,
+    mut text_query: Query<(Entity, &mut StageText)>,
 ) {
-    if text_query.is_empty() {
-        return;
-    }
-
-    let entity = text_query.single_mut();
-
-    // 计时器
-    if timer.0.tick(time.delta()).just_finished() {
-        commands.entity(entity).despawn();
+    for (entity, mut stage_text) in text_query.iter_mut() {
+        // 计时器
+        if stage_text.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn_recursive();
+        }
     }
 }
 
-// 检测新关卡是否
-fn _new_stage(stage: Res<Stage>) -> bool {
-    stage.is_changed()
+// 每关重新生成种子
+pub fn roll_stage_seed(mut seed: ResMut<StageSeed>) {
+    seed.0 = rand::thread_rng().gen();
+    debug!("stage seed: {}", seed.0);
 }
 
-fn create_block_group(mut commands: Commands, handle: ResMut<GlobalTextAtlas>) {
-    println!("生成方块");
-    // 初始位置
-    let (x, y) = BLOCK_INIT_POS;
-
+pub fn create_block_group(
+    mut commands: Commands,
+    handle: Res<GlobalTextAtlas>,
+    seed: Res<StageSeed>,
+    board_query: Query<Entity, With<Board>>,
+) {
     // 测试固定渲染
     // let group = TEST_BLOCK_POS.iter().rev().cloned().collect::<Vec<_>>();
-    let group = create_block(4, 5);
-    for pos_y in 0..group.len() {
-        for pos_x in 0..group[pos_y].len() {
-            let index = group[pos_y][pos_x];
-            if index > 0 {
-                commands.spawn((
-                    SpriteSheetBundle {
-                        texture: handle.image.clone().unwrap(),
-                        atlas: TextureAtlas {
-                            layout: handle.layout.clone().unwrap(),
-                            index: index,
-                        },
-                        transform: Transform::from_translation(vec3(
-                            x + (pos_x * STEP_SIZE) as f32,
-                            y + (pos_y * STEP_SIZE) as f32,
-                            0.0,
-                        ))
-                        .with_scale(Vec3::splat(SPRITE_SCALE_FACTOR)),
-                        ..default()
-                    },
-                    Block {
-                        show: true,
-                        pos: vec2(
-                            x + (pos_x * STEP_SIZE) as f32,
-                            y + (pos_y * STEP_SIZE) as f32,
-                        ),
-                    },
-                    GameEntity,
-                ));
-            }
-        }
-    }
-}
+    let group = generate_stage(&mut StdRng::seed_from_u64(seed.0));
+    debug!("block group: {:?}", group);
 
-// 生成方块组
-fn create_block(row: usize, col: usize) -> Vec<Vec<usize>> {
-    let mut block_group = vec![];
-
-    let mut col0_index = None;
-
-    for i in 0..row {
-        let mut _row: Vec<usize> = vec![];
-        let index = rand::thread_rng().gen_range(BLOCK_DISPLAY_RANGE);
-        let other_index = rand::thread_rng().gen_range(BLOCK_DISPLAY_RANGE);
-        for j in 0..col {
-            if j <= 1 {
-                let index = rand::thread_rng().gen_range(BLOCK_DISPLAY_RANGE);
-                _row.push(index);
-            } else {
-                if let Some(index) = col0_index {
-                    _row.push(index);
-                } else {
-                    _row.push(other_index);
-                }
+    for board in board_query.iter() {
+        commands.entity(board).with_children(|parent| {
+            for (cell, index) in Grid::from_block_group(&group).blocks {
+                parent.spawn(block_bundle(&handle, board, cell_to_pos(cell), index));
             }
-        }
-        col0_index = Some(index);
-        block_group.push(_row);
+        });
     }
-
-    println!("{:?}", block_group);
-
-    // 翻转一下
-    block_group.reverse();
-
-    return block_group;
 }
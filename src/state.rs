@@ -6,10 +6,61 @@ pub enum GameState {
     #[default]
     Loading,
     MainMenu,
+    // 模式选择
+    ModeSelect,
+    // 排行榜
+    HighScores,
+    // 设置
+    Settings,
+    // 解谜选关
+    LevelSelect,
+    // 统计界面
+    Statistics,
+    // 成就界面
+    Achievements,
     GameInit,
     InGame,
 }
 
+// 游戏模式
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GameMode {
+    #[default]
+    Single,
+    // 本地双人对战
+    Versus,
+    // 玩家对战 AI
+    VersusAi,
+    // 无尽模式 方块列不断从右侧推入
+    Endless,
+    // 解谜 固定关卡 无倒计时
+    Puzzle,
+    // 每日挑战 关卡由日期决定
+    Daily,
+}
+
+impl GameMode {
+    // 棋盘数量
+    pub fn board_count(&self) -> usize {
+        match self {
+            GameMode::Single | GameMode::Endless | GameMode::Puzzle | GameMode::Daily => 1,
+            GameMode::Versus | GameMode::VersusAi => 2,
+        }
+    }
+
+    pub fn is_versus(&self) -> bool {
+        matches!(self, GameMode::Versus | GameMode::VersusAi)
+    }
+
+    // 是否有倒计时
+    pub fn is_timed(&self) -> bool {
+        matches!(
+            self,
+            GameMode::Single | GameMode::Versus | GameMode::VersusAi | GameMode::Daily
+        )
+    }
+}
+
 // 玩家状态
 #[derive(Component, Default, PartialEq, Eq)]
 pub enum PlayerState {
@@ -20,7 +71,7 @@ pub enum PlayerState {
 }
 
 // 手里方块状态
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Default, Copy, States)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default, Copy)]
 pub enum HandBlockState {
     #[default]
     Idle,
@@ -29,7 +80,7 @@ pub enum HandBlockState {
 }
 
 // 方块组状态
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Default, Copy, States)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default, Copy)]
 pub enum BlockGroupState {
     #[default]
     Static,
@@ -37,7 +88,7 @@ pub enum BlockGroupState {
 }
 
 // 游戏结束 结算状态
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Default, Copy, States)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default, Copy)]
 pub enum SettlementState {
     #[default]
     Not,
@@ -46,3 +97,74 @@ pub enum SettlementState {
     TimeToScore,
     End,
 }
+
+// 棋盘内状态 挂在棋盘上 每个棋盘独立切换
+// 用法同 State/NextState 设置后在下一帧 PreUpdate 切换并发送 BoardTransition
+#[derive(Component, Debug, Default)]
+pub struct BoardState<S> {
+    current: S,
+    next: Option<S>,
+}
+
+impl<S: Copy + Eq> BoardState<S> {
+    pub fn get(&self) -> S {
+        self.current
+    }
+
+    pub fn is(&self, state: S) -> bool {
+        self.current == state
+    }
+
+    // 同一帧多次设置以最后一次为准
+    pub fn set(&mut self, next: S) {
+        self.next = Some(next);
+    }
+
+    // 立即重置 不发送切换事件
+    pub fn reset(&mut self, state: S) {
+        self.current = state;
+        self.next = None;
+    }
+}
+
+// 棋盘状态切换事件 替代 OnEnter / OnExit
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BoardTransition<S> {
+    pub board: Entity,
+    pub exited: S,
+    pub entered: S,
+}
+
+impl<S: Copy + Eq> BoardTransition<S> {
+    pub fn entered(&self, state: S) -> bool {
+        self.entered == state
+    }
+
+    pub fn exited(&self, state: S) -> bool {
+        self.exited == state
+    }
+}
+
+// 应用棋盘状态切换
+pub fn apply_board_transitions<S: Copy + Eq + Send + Sync + 'static>(
+    mut query: Query<(Entity, &mut BoardState<S>)>,
+    mut events: EventWriter<BoardTransition<S>>,
+) {
+    for (board, mut state) in query.iter_mut() {
+        let Some(entered) = state.next.take() else {
+            continue;
+        };
+
+        if entered == state.current {
+            continue;
+        }
+
+        let exited = state.current;
+        state.current = entered;
+        events.send(BoardTransition {
+            board,
+            exited,
+            entered,
+        });
+    }
+}
@@ -0,0 +1,235 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::block::BlockKind;
+use crate::board::Board;
+use crate::events::{BlocksRemoved, LightningRescued, StageCleared, ThrowStarted};
+use crate::player::Controller;
+use crate::settlement::Settlement;
+use crate::state::{apply_board_transitions, BoardTransition, GameState, SettlementState};
+use crate::storage::{format_key_values, parse_key_values, read_data, write_data};
+
+// 统计存档文件
+const STATS_FILE: &str = "stats.txt";
+
+// 玩家累计统计 跨局保存 只统计键盘玩家
+#[derive(Resource, Debug, Default, Clone)]
+pub struct Stats {
+    pub throws: u64,
+    // 各种类消除的方块数
+    pub removed: HashMap<BlockKind, u64>,
+    // 单次投掷最多消除数
+    pub largest_removal: u32,
+    pub lightning_rescues: u64,
+    pub stages_cleared: u64,
+    // 游戏时长 秒
+    pub time_played: f64,
+    // 单关最高时间奖励
+    pub best_time_bonus: u64,
+}
+
+impl Stats {
+    pub fn removed(&self, kind: BlockKind) -> u64 {
+        self.removed.get(&kind).copied().unwrap_or(0)
+    }
+
+    pub fn total_removed(&self) -> u64 {
+        self.removed.values().sum()
+    }
+
+    pub fn parse(source: &str) -> Self {
+        let mut stats = Self::default();
+
+        for (key, value) in parse_key_values(source) {
+            let Ok(number) = value.parse::<f64>() else {
+                continue;
+            };
+
+            match key.as_str() {
+                "throws" => stats.throws = number as u64,
+                "largest_removal" => stats.largest_removal = number as u32,
+                "lightning_rescues" => stats.lightning_rescues = number as u64,
+                "stages_cleared" => stats.stages_cleared = number as u64,
+                "time_played" => stats.time_played = number,
+                "best_time_bonus" => stats.best_time_bonus = number as u64,
+                _ => {
+                    let kind = key.strip_prefix("removed.").and_then(|name| {
+                        BlockKind::ALL.into_iter().find(|kind| kind.name() == name)
+                    });
+                    if let Some(kind) = kind {
+                        stats.removed.insert(kind, number as u64);
+                    }
+                }
+            }
+        }
+
+        stats
+    }
+
+    pub fn to_source(&self) -> String {
+        let mut pairs = vec![
+            (String::from("throws"), self.throws.to_string()),
+            (
+                String::from("largest_removal"),
+                self.largest_removal.to_string(),
+            ),
+            (
+                String::from("lightning_rescues"),
+                self.lightning_rescues.to_string(),
+            ),
+            (
+                String::from("stages_cleared"),
+                self.stages_cleared.to_string(),
+            ),
+            (
+                String::from("time_played"),
+                format!("{:.1}", self.time_played),
+            ),
+            (
+                String::from("best_time_bonus"),
+                self.best_time_bonus.to_string(),
+            ),
+        ];
+        pairs.extend(BlockKind::ALL.into_iter().map(|kind| {
+            (
+                format!("removed.{}", kind.name()),
+                self.removed(kind).to_string(),
+            )
+        }));
+
+        format_key_values("Smart Block statistics", pairs)
+    }
+}
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(
+            read_data(STATS_FILE)
+                .map(|source| Stats::parse(&source))
+                .unwrap_or_default(),
+        )
+        .add_systems(
+            PreUpdate,
+            record_time_bonus
+                .after(apply_board_transitions::<SettlementState>)
+                .run_if(in_state(GameState::InGame)),
+        )
+        .add_systems(
+            Update,
+            (
+                count_throws,
+                count_removed_blocks,
+                count_lightning_rescues,
+                count_stage_clears,
+                count_time_played,
+            )
+                .run_if(in_state(GameState::InGame)),
+        )
+        // 离开游戏状态时保存 进入下一关与返回菜单都会触发 退出程序时也保存
+        .add_systems(OnExit(GameState::InGame), save_stats)
+        .add_systems(Last, save_stats.run_if(on_event::<AppExit>()));
+    }
+}
+
+// 是否为键盘玩家的棋盘 演示与 AI 不计入统计
+fn is_player_board(board: Entity, board_query: &Query<&Controller, With<Board>>) -> bool {
+    board_query
+        .get(board)
+        .is_ok_and(|controller| *controller == Controller::Keyboard)
+}
+
+fn count_throws(
+    mut events: EventReader<ThrowStarted>,
+    mut stats: ResMut<Stats>,
+    board_query: Query<&Controller, With<Board>>,
+) {
+    for event in events.read() {
+        if is_player_board(event.board, &board_query) {
+            stats.throws += 1;
+        }
+    }
+}
+
+fn count_removed_blocks(
+    mut events: EventReader<BlocksRemoved>,
+    mut stats: ResMut<Stats>,
+    board_query: Query<&Controller, With<Board>>,
+) {
+    for event in events.read() {
+        if !is_player_board(event.board, &board_query) {
+            continue;
+        }
+
+        for kind in event.kinds.iter() {
+            *stats.removed.entry(*kind).or_default() += 1;
+        }
+        stats.largest_removal = stats.largest_removal.max(event.count as u32);
+    }
+}
+
+fn count_lightning_rescues(
+    mut events: EventReader<LightningRescued>,
+    mut stats: ResMut<Stats>,
+    board_query: Query<&Controller, With<Board>>,
+) {
+    for LightningRescued { board } in events.read() {
+        if is_player_board(*board, &board_query) {
+            stats.lightning_rescues += 1;
+        }
+    }
+}
+
+// 过关 对战中落败的一方不计入
+fn count_stage_clears(
+    mut events: EventReader<StageCleared>,
+    mut stats: ResMut<Stats>,
+    board_query: Query<&Controller, With<Board>>,
+) {
+    for event in events.read() {
+        if is_player_board(event.board, &board_query) {
+            stats.stages_cleared += 1;
+        }
+    }
+}
+
+// 结算结束时时间奖励已计算完毕
+fn record_time_bonus(
+    mut events: EventReader<BoardTransition<SettlementState>>,
+    mut stats: ResMut<Stats>,
+    board_query: Query<&Controller, With<Board>>,
+    settlement_query: Query<&Settlement>,
+) {
+    for event in events.read().filter(|e| e.entered(SettlementState::End)) {
+        let Ok(settlement) = settlement_query.get(event.board) else {
+            continue;
+        };
+
+        if is_player_board(event.board, &board_query) {
+            stats.best_time_bonus = stats.best_time_bonus.max(settlement.time_bonus());
+        }
+    }
+}
+
+// 有键盘玩家的棋盘时累计游戏时长
+fn count_time_played(
+    time: Res<Time>,
+    mut stats: ResMut<Stats>,
+    board_query: Query<&Controller, With<Board>>,
+) {
+    if board_query.iter().any(|c| *c == Controller::Keyboard) {
+        stats.time_played += time.delta_seconds_f64();
+    }
+}
+
+fn save_stats(stats: Res<Stats>) {
+    if !stats.is_changed() {
+        return;
+    }
+
+    if let Err(e) = write_data(STATS_FILE, &stats.to_source()) {
+        warn!("failed to save statistics: {}", e);
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+
+use crate::block::BlockKind;
+use crate::locale::LocalizedText;
+use crate::menu::{despawn_screen, menu_root, spawn_back_button, spawn_menu_title, MenuActivated};
+use crate::state::GameState;
+use crate::stats::Stats;
+use crate::*;
+
+#[derive(Component)]
+struct StatsScreenItem;
+
+// 返回排行榜按钮
+#[derive(Component)]
+struct StatsBackButton;
+
+pub struct StatsScreenPlugin;
+
+impl Plugin for StatsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Statistics), setup_stats_screen)
+            .add_systems(
+                OnExit(GameState::Statistics),
+                despawn_screen::<StatsScreenItem>,
+            )
+            .add_systems(
+                Update,
+                handle_stats_back_button.run_if(in_state(GameState::Statistics)),
+            );
+    }
+}
+
+// 游戏时长 时:分:秒
+fn format_time_played(secs: f64) -> String {
+    let secs = secs as u64;
+    format!("{}:{:0>2}:{:0>2}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+// 统计行 标签与数值
+fn stats_rows(stats: &Stats) -> Vec<(LocalizedText, String)> {
+    let mut rows = vec![
+        (
+            LocalizedText::new("stats.time_played"),
+            format_time_played(stats.time_played),
+        ),
+        (
+            LocalizedText::new("stats.stages_cleared"),
+            stats.stages_cleared.to_string(),
+        ),
+        (LocalizedText::new("stats.throws"), stats.throws.to_string()),
+        (
+            LocalizedText::new("stats.removed"),
+            stats.total_removed().to_string(),
+        ),
+    ];
+
+    rows.extend(
+        BlockKind::ALL
+            .into_iter()
+            .filter(|kind| *kind != BlockKind::Lightning)
+            .map(|kind| {
+                (
+                    LocalizedText::new("stats.removed_kind")
+                        .with_args(vec![kind.letter().to_string()]),
+                    stats.removed(kind).to_string(),
+                )
+            }),
+    );
+
+    rows.extend([
+        (
+            LocalizedText::new("stats.largest_removal"),
+            stats.largest_removal.to_string(),
+        ),
+        (
+            LocalizedText::new("stats.lightning_rescues"),
+            stats.lightning_rescues.to_string(),
+        ),
+        (
+            LocalizedText::new("stats.best_time_bonus"),
+            stats.best_time_bonus.to_string(),
+        ),
+    ]);
+
+    rows
+}
+
+// 生成统计界面
+fn setup_stats_screen(mut commands: Commands, asset_server: Res<AssetServer>, stats: Res<Stats>) {
+    let text_style = TextStyle {
+        font: asset_server.load(FONT_PATH),
+        font_size: 24.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn((menu_root(8.0), StatsScreenItem))
+        .with_children(|parent| {
+            spawn_menu_title(parent, LocalizedText::new("stats.title"));
+
+            for (label, value) in stats_rows(&stats) {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(STATS_ROW_W),
+                            justify_content: JustifyContent::SpaceBetween,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn((TextBundle::from_section("", text_style.clone()), label));
+                        parent.spawn(TextBundle::from_section(value, text_style.clone()));
+                    });
+            }
+
+            spawn_back_button(parent, StatsBackButton);
+        });
+}
+
+// 返回排行榜界面
+fn handle_stats_back_button(
+    mut activated_events: EventReader<MenuActivated>,
+    button_query: Query<(), With<StatsBackButton>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in activated_events.read() {
+        if button_query.contains(event.0) {
+            next_state.set(GameState::HighScores);
+        }
+    }
+}
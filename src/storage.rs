@@ -0,0 +1,68 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+// 本地存档 文本文件 每行 `key = value` 以 # 开头为注释
+
+// 存档目录 可通过 SMART_BLOCK_DATA_DIR 指定
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("SMART_BLOCK_DATA_DIR") {
+        return PathBuf::from(dir);
+    }
+
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    base.join("smart-block")
+}
+
+// 读取存档 不存在或读取失败时返回 None
+pub fn read_data(name: &str) -> Option<String> {
+    fs::read_to_string(data_dir().join(name)).ok()
+}
+
+// 写入存档 先写临时文件再替换 避免写入中断损坏存档
+pub fn write_data(name: &str, contents: &str) -> io::Result<()> {
+    let dir = data_dir();
+    fs::create_dir_all(&dir)?;
+
+    let path = dir.join(name);
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(tmp, path)
+}
+
+// 删除存档 不存在视为成功
+pub fn remove_data(name: &str) -> io::Result<()> {
+    match fs::remove_file(data_dir().join(name)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+// 解析 `key = value` 保持文件中的顺序
+pub fn parse_key_values(source: &str) -> Vec<(String, String)> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+// 生成 `key = value` 文本 首行为注释
+pub fn format_key_values(
+    header: &str,
+    pairs: impl IntoIterator<Item = (String, String)>,
+) -> String {
+    let mut source = format!("# {}\n", header);
+    for (key, value) in pairs {
+        source.push_str(&format!("{} = {}\n", key, value));
+    }
+
+    source
+}
@@ -0,0 +1,174 @@
+use bevy::prelude::*;
+
+use crate::*;
+
+// 合成音效 方波与噪声 加包络与音高滑动 加载时生成 wav 数据
+// 没有素材的音效在这里定义参数
+
+// 波形
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Waveform {
+    // 方波 duty 为高电平占比
+    Square { duty: f32 },
+    // 噪声 按频率采样保持 频率越高越尖
+    Noise,
+}
+
+// 音效参数 时间单位为秒 频率单位为 Hz
+// 音高在整个时长内从 start_freq 指数滑动到 end_freq
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SynthParams {
+    pub wave: Waveform,
+    pub start_freq: f32,
+    pub end_freq: f32,
+    pub attack: f32,
+    pub sustain: f32,
+    pub release: f32,
+    pub volume: f32,
+}
+
+impl SynthParams {
+    pub fn duration(&self) -> f32 {
+        self.attack + self.sustain + self.release
+    }
+
+    // 整体升降若干半音
+    pub fn transpose(&self, semitones: f32) -> Self {
+        let ratio = 2f32.powf(semitones / 12.0);
+        Self {
+            start_freq: self.start_freq * ratio,
+            end_freq: self.end_freq * ratio,
+            ..*self
+        }
+    }
+
+    // 包络 线性起音 保持 线性释音
+    fn envelope(&self, t: f32) -> f32 {
+        if t < self.attack {
+            t / self.attack
+        } else if t < self.attack + self.sustain {
+            1.0
+        } else {
+            (1.0 - (t - self.attack - self.sustain) / self.release).max(0.0)
+        }
+    }
+
+    fn freq(&self, t: f32) -> f32 {
+        self.start_freq * (self.end_freq / self.start_freq).powf(t / self.duration())
+    }
+}
+
+// 撞墙
+pub const SFX_HIT_WALL: SynthParams = SynthParams {
+    wave: Waveform::Noise,
+    start_freq: 4000.0,
+    end_freq: 1200.0,
+    attack: 0.002,
+    sustain: 0.02,
+    release: 0.06,
+    volume: 0.5,
+};
+
+// 交换种类
+pub const SFX_SWAP: SynthParams = SynthParams {
+    wave: Waveform::Square { duty: 0.5 },
+    start_freq: 440.0,
+    end_freq: 880.0,
+    attack: 0.005,
+    sustain: 0.04,
+    release: 0.06,
+    volume: 0.3,
+};
+
+// 消除 按消除数升调
+pub const SFX_REMOVED: SynthParams = SynthParams {
+    wave: Waveform::Square { duty: 0.25 },
+    start_freq: 660.0,
+    end_freq: 990.0,
+    attack: 0.002,
+    sustain: 0.05,
+    release: 0.1,
+    volume: 0.3,
+};
+
+// 无法消除
+pub const SFX_STUCK: SynthParams = SynthParams {
+    wave: Waveform::Square { duty: 0.5 },
+    start_freq: 330.0,
+    end_freq: 110.0,
+    attack: 0.005,
+    sustain: 0.15,
+    release: 0.2,
+    volume: 0.3,
+};
+
+// 生成采样 单声道
+pub fn render(params: &SynthParams) -> Vec<i16> {
+    let rate = SYNTH_SAMPLE_RATE as f32;
+    let len = (params.duration() * rate).ceil() as usize;
+
+    let mut phase = 0.0f32;
+    // xorshift 噪声 固定种子 每次生成结果相同
+    let mut seed = 0x2545_f491u32;
+    let mut noise = 0.0f32;
+
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / rate;
+            phase += params.freq(t) / rate;
+            if phase >= 1.0 {
+                phase -= phase.floor();
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                noise = seed as f32 / u32::MAX as f32 * 2.0 - 1.0;
+            }
+
+            let wave = match params.wave {
+                Waveform::Square { duty } => {
+                    if phase < duty {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }
+                Waveform::Noise => noise,
+            };
+            let sample = wave * params.envelope(t) * params.volume;
+            (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+        })
+        .collect()
+}
+
+// 16 位单声道 wav
+pub fn wav_bytes(samples: &[i16]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let byte_rate = SYNTH_SAMPLE_RATE * 2;
+
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    // PCM 单声道
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&SYNTH_SAMPLE_RATE.to_le_bytes());
+    bytes.extend_from_slice(&byte_rate.to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    bytes
+}
+
+// 生成音效资源
+pub fn synthesize(params: &SynthParams, sources: &mut Assets<AudioSource>) -> Handle<AudioSource> {
+    sources.add(AudioSource {
+        bytes: wav_bytes(&render(params)).into(),
+    })
+}
@@ -0,0 +1,1364 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::audio::{AddAudioSource, Source};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::*;
+
+// 音轨模块 ProTracker MOD 与 FastTracker II XM 格式
+// 播放器实时混音 曲目结束后从 restart 位置无缝循环 可在播放中调整速度与静音声道
+// MOD 仅支持 31 个乐器并带声道标记的文件 XM 支持乐器 音量与声像包络及线性频率表
+
+// PAL Amiga 时钟 周期换算频率
+const AMIGA_CLOCK: f32 = 7_093_789.2;
+// XM 中 C-4 的采样频率
+const XM_C4_RATE: f32 = 8363.0;
+// MOD 每个 pattern 行数
+const MOD_ROWS: usize = 64;
+// XM 文件头标识
+const XM_ID: &[u8] = b"Extended Module: ";
+// XM 音符 1..=96 对应 C-0..B-7 97 为松键
+const XM_KEY_OFF: u8 = 97;
+// 周期上下限 对应 MOD 的 B-3 与 C-1 内部周期为 1/4 Amiga 周期
+const PERIOD_MIN: i32 = 113 * 4;
+const PERIOD_MAX: i32 = 856 * 4;
+// 线性频率表每半音的周期数 C-0 的周期
+const LINEAR_SEMITONE: f32 = 64.0;
+const LINEAR_PERIOD_MAX: i32 = 7680;
+// 淡出起始值
+const FADEOUT_MAX: i32 = 65536;
+// 正弦颤音表 半个周期
+const VIBRATO_TABLE: [i32; 32] = [
+    0, 24, 49, 74, 97, 120, 141, 161, 180, 197, 212, 224, 235, 244, 250, 253, 255, 253, 250, 244,
+    235, 224, 212, 197, 180, 161, 141, 120, 97, 74, 49, 24,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleFormat {
+    Mod,
+    Xm,
+}
+
+// 周期换算频率的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrequencyTable {
+    Amiga,
+    // XM 线性频率表 周期每减少 64 升高一个半音
+    Linear,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ModSample {
+    pub data: Vec<i16>,
+    // 微调 单位 1/128 半音
+    pub finetune: i8,
+    // 相对 C-4 的半音数 只有 XM 使用
+    pub relative_note: i8,
+    pub volume: u8,
+    // 触发时的声像 0..=255 MOD 为空 沿用声道声像
+    pub panning: Option<u8>,
+    pub loop_start: usize,
+    pub loop_len: usize,
+    // 来回循环
+    pub ping_pong: bool,
+}
+
+// 包络 点为 (tick, 0..=64) 按 tick 递增
+#[derive(Debug, Clone, Default)]
+pub struct Envelope {
+    pub points: Vec<(u16, u8)>,
+    pub sustain: Option<usize>,
+    // 循环起止点下标
+    pub loop_points: Option<(usize, usize)>,
+}
+
+impl Envelope {
+    // 位置处的线性插值
+    pub fn value(&self, pos: u16) -> f32 {
+        let Some(&(first_x, first_y)) = self.points.first() else {
+            return 64.0;
+        };
+        if pos <= first_x {
+            return first_y as f32;
+        }
+        for pair in self.points.windows(2) {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            if pos < x1 {
+                let t = pos.saturating_sub(x0) as f32 / x1.saturating_sub(x0).max(1) as f32;
+                return y0 as f32 + (y1 as f32 - y0 as f32) * t;
+            }
+        }
+        self.points.last().map_or(64.0, |(_, y)| *y as f32)
+    }
+
+    // 前进一 tick 按住时停在延音点 到达循环终点回到起点
+    pub fn advance(&self, pos: u16, key_on: bool) -> u16 {
+        let point = |index: Option<usize>| index.and_then(|i| self.points.get(i)).map(|p| p.0);
+        if key_on && point(self.sustain) == Some(pos) {
+            return pos;
+        }
+        if let Some((start, end)) = self.loop_points {
+            if let (Some(start), Some(end)) = (point(Some(start)), point(Some(end))) {
+                if pos >= end {
+                    return start;
+                }
+            }
+        }
+        pos.saturating_add(1)
+    }
+}
+
+// 乐器 按音符选择采样 MOD 每个乐器只有一个采样且没有包络
+#[derive(Debug, Clone, Default)]
+pub struct Instrument {
+    // 96 个音符对应的采样下标
+    pub keymap: Vec<Option<usize>>,
+    pub volume_envelope: Option<Envelope>,
+    pub panning_envelope: Option<Envelope>,
+    // 松键后每 tick 的淡出量
+    pub fadeout: u16,
+}
+
+impl Instrument {
+    // MOD 没有音符 使用第一个键位
+    pub fn sample(&self, key: u8) -> Option<usize> {
+        self.keymap.get(key.max(1) as usize - 1).copied().flatten()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ModNote {
+    // 乐器编号 从 1 开始 0 为不变
+    pub instrument: u8,
+    // MOD 音符周期
+    pub period: u16,
+    // XM 音符 0 为无音符
+    pub key: u8,
+    // XM 音量列
+    pub volume: u8,
+    pub effect: u8,
+    pub param: u8,
+}
+
+impl ModNote {
+    fn has_note(&self) -> bool {
+        self.period > 0 || (1..XM_KEY_OFF).contains(&self.key)
+    }
+
+    fn is_tone_portamento(&self) -> bool {
+        matches!(self.effect, 0x3 | 0x5) || self.volume >> 4 == 0xF
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModuleData {
+    pub title: String,
+    pub format: ModuleFormat,
+    pub frequency: FrequencyTable,
+    pub channels: usize,
+    pub speed: usize,
+    pub bpm: u32,
+    pub instruments: Vec<Instrument>,
+    pub samples: Vec<ModSample>,
+    pub orders: Vec<u8>,
+    pub restart: usize,
+    // 每个 pattern 为 行数 * channels 个音符
+    pub patterns: Vec<Vec<ModNote>>,
+}
+
+impl ModuleData {
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.starts_with(XM_ID) {
+            Self::parse_xm(bytes)
+        } else {
+            Self::parse_mod(bytes)
+        }
+    }
+
+    // 只支持 31 个乐器的格式 文件偏移 1080 处须有声道标记
+    fn parse_mod(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < 1084 {
+            return Err(String::from("file too short"));
+        }
+
+        let tag = &bytes[1080..1084];
+        let channels = match tag {
+            b"M.K." | b"M!K!" | b"FLT4" | b"4CHN" => 4,
+            b"6CHN" => 6,
+            b"8CHN" | b"OCTA" | b"CD81" | b"FLT8" => 8,
+            [a, b, b'C', b'H'] if a.is_ascii_digit() && b.is_ascii_digit() => {
+                ((a - b'0') * 10 + (b - b'0')) as usize
+            }
+            _ => return Err(format!("unsupported module tag {:?}", tag)),
+        };
+        if channels == 0 {
+            return Err(String::from("module has no channels"));
+        }
+
+        let title = read_name(&bytes[..20]);
+
+        let word = |offset: usize| u16::from_be_bytes([bytes[offset], bytes[offset + 1]]) as usize;
+        let mut samples = (0..31)
+            .map(|i| {
+                let offset = 20 + i * 30;
+                let loop_start = word(offset + 26) * 2;
+                let loop_len = word(offset + 28) * 2;
+                (
+                    word(offset + 22) * 2,
+                    ModSample {
+                        // -8..7 每级 1/8 半音
+                        finetune: (((bytes[offset + 24] & 0x0F) << 4) as i8 >> 4) * 16,
+                        volume: bytes[offset + 25].min(64),
+                        loop_start,
+                        loop_len: if loop_len > 2 { loop_len } else { 0 },
+                        ..default()
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let song_len = (bytes[950] as usize).clamp(1, 128);
+        let restart = bytes[951] as usize;
+        let orders = bytes[952..952 + song_len].to_vec();
+        let pattern_count = bytes[952..1080].iter().copied().max().unwrap_or(0) as usize + 1;
+
+        let pattern_size = MOD_ROWS * channels * 4;
+        let mut offset = 1084;
+        let mut patterns = Vec::with_capacity(pattern_count);
+        for _ in 0..pattern_count {
+            let data = bytes
+                .get(offset..offset + pattern_size)
+                .ok_or_else(|| String::from("pattern data truncated"))?;
+            patterns.push(
+                data.chunks_exact(4)
+                    .map(|b| ModNote {
+                        instrument: (b[0] & 0xF0) | (b[2] >> 4),
+                        period: (((b[0] & 0x0F) as u16) << 8) | b[1] as u16,
+                        effect: b[2] & 0x0F,
+                        param: b[3],
+                        ..default()
+                    })
+                    .collect(),
+            );
+            offset += pattern_size;
+        }
+
+        // 采样数据不足时截断
+        for (len, sample) in samples.iter_mut() {
+            let end = (offset + *len).min(bytes.len());
+            sample.data = bytes[offset.min(end)..end]
+                .iter()
+                .map(|b| (*b as i8 as i16) << 8)
+                .collect();
+            offset += *len;
+
+            if sample.loop_start + sample.loop_len > sample.data.len() {
+                sample.loop_len = sample.data.len().saturating_sub(sample.loop_start);
+            }
+        }
+
+        Ok(Self {
+            title,
+            format: ModuleFormat::Mod,
+            frequency: FrequencyTable::Amiga,
+            channels,
+            speed: 6,
+            bpm: 125,
+            instruments: (0..samples.len())
+                .map(|i| Instrument {
+                    keymap: vec![Some(i)],
+                    ..default()
+                })
+                .collect(),
+            samples: samples.into_iter().map(|(_, sample)| sample).collect(),
+            restart: if restart < orders.len() { restart } else { 0 },
+            orders,
+            patterns,
+        })
+    }
+
+    // FastTracker II 1.04 格式 pattern 为压缩格式 采样为差分编码
+    fn parse_xm(bytes: &[u8]) -> Result<Self, String> {
+        let reader = ByteReader::new(bytes);
+        let title = read_name(reader.bytes(17, 20)?);
+        let version = reader.u16(58)?;
+        if version < 0x0104 {
+            return Err(format!("unsupported xm version {:#06x}", version));
+        }
+
+        let header_size = reader.u32(60)? as usize;
+        let song_len = (reader.u16(64)? as usize).clamp(1, 256);
+        let restart = reader.u16(66)? as usize;
+        let channels = reader.u16(68)? as usize;
+        let pattern_count = reader.u16(70)? as usize;
+        let instrument_count = reader.u16(72)? as usize;
+        let frequency = if reader.u16(74)? & 1 == 1 {
+            FrequencyTable::Linear
+        } else {
+            FrequencyTable::Amiga
+        };
+        if !(1..=32).contains(&channels) {
+            return Err(format!("unsupported channel count {}", channels));
+        }
+        let orders = reader.bytes(80, song_len)?.to_vec();
+
+        let mut offset = 60 + header_size;
+        let mut patterns = Vec::with_capacity(pattern_count);
+        for _ in 0..pattern_count {
+            let pattern_header = reader.u32(offset)? as usize;
+            let rows = (reader.u16(offset + 5)? as usize).clamp(1, 256);
+            let packed_size = reader.u16(offset + 7)? as usize;
+            offset += pattern_header;
+
+            let mut notes = vec![ModNote::default(); rows * channels];
+            if packed_size > 0 {
+                let mut packed = ByteReader::new(reader.bytes(offset, packed_size)?);
+                for note in notes.iter_mut() {
+                    *note = packed.note()?;
+                }
+            }
+            patterns.push(notes);
+            offset += packed_size;
+        }
+
+        let mut instruments = Vec::with_capacity(instrument_count);
+        let mut samples = vec![];
+        for _ in 0..instrument_count {
+            let instrument_header = reader.u32(offset)? as usize;
+            let sample_count = reader.u16(offset + 27)? as usize;
+            if sample_count == 0 {
+                instruments.push(Instrument::default());
+                offset += instrument_header;
+                continue;
+            }
+
+            let first_sample = samples.len();
+            // 乐器内采样编号换算为模块采样下标
+            let keymap = reader
+                .bytes(offset + 33, 96)?
+                .iter()
+                .map(|n| ((*n as usize) < sample_count).then_some(first_sample + *n as usize))
+                .collect();
+            let volume_envelope = reader.envelope(offset, 129, 225, 227, 233)?;
+            let panning_envelope = reader.envelope(offset, 177, 226, 230, 234)?;
+            let fadeout = reader.u16(offset + 239)?;
+            let sample_header = reader.u32(offset + 29)? as usize;
+            offset += instrument_header;
+
+            let mut lengths = Vec::with_capacity(sample_count);
+            for _ in 0..sample_count {
+                let length = reader.u32(offset)? as usize;
+                let loop_start = reader.u32(offset + 4)? as usize;
+                let loop_len = reader.u32(offset + 8)? as usize;
+                let kind = reader.u8(offset + 14)?;
+                // 16 位采样的长度与循环以字节计
+                let width = if kind & 0x10 != 0 { 2 } else { 1 };
+                lengths.push((length, width));
+                samples.push(ModSample {
+                    data: vec![],
+                    volume: reader.u8(offset + 12)?.min(64),
+                    finetune: reader.u8(offset + 13)? as i8,
+                    panning: Some(reader.u8(offset + 15)?),
+                    relative_note: reader.u8(offset + 16)? as i8,
+                    loop_start: loop_start / width,
+                    loop_len: if kind & 3 != 0 { loop_len / width } else { 0 },
+                    ping_pong: kind & 3 == 2,
+                });
+                offset += sample_header;
+            }
+
+            for (sample, (length, width)) in samples[first_sample..].iter_mut().zip(lengths) {
+                let data = reader.bytes(offset, length)?;
+                sample.data = if width == 2 {
+                    let mut value = 0i16;
+                    data.chunks_exact(2)
+                        .map(|b| {
+                            value = value.wrapping_add(i16::from_le_bytes([b[0], b[1]]));
+                            value
+                        })
+                        .collect()
+                } else {
+                    let mut value = 0i8;
+                    data.iter()
+                        .map(|b| {
+                            value = value.wrapping_add(*b as i8);
+                            (value as i16) << 8
+                        })
+                        .collect()
+                };
+                offset += length;
+
+                if sample.loop_start + sample.loop_len > sample.data.len() {
+                    sample.loop_len = sample.data.len().saturating_sub(sample.loop_start);
+                }
+            }
+
+            instruments.push(Instrument {
+                keymap,
+                volume_envelope,
+                panning_envelope,
+                fadeout,
+            });
+        }
+
+        Ok(Self {
+            title,
+            format: ModuleFormat::Xm,
+            frequency,
+            channels,
+            speed: (reader.u16(76)? as usize).clamp(1, 31),
+            bpm: (reader.u16(78)? as u32).clamp(32, 255),
+            instruments,
+            samples,
+            restart: if restart < orders.len() { restart } else { 0 },
+            orders,
+            patterns,
+        })
+    }
+
+    // 音符触发时的周期 已计入采样微调与相对音高
+    fn note_period(&self, note: &ModNote, sample: Option<&ModSample>) -> Option<i32> {
+        let finetune = sample.map_or(0.0, |s| s.finetune as f32 / 128.0);
+        if note.period > 0 {
+            let period = note.period as f32 * 4.0 * 2f32.powf(-finetune / 12.0);
+            return Some(period.round() as i32);
+        }
+        if !note.has_note() {
+            return None;
+        }
+
+        // 相对 C-0 的半音数
+        let key = (note.key - 1) as f32 + sample.map_or(0.0, |s| s.relative_note as f32) + finetune;
+        let period = match self.frequency {
+            FrequencyTable::Linear => LINEAR_PERIOD_MAX as f32 - key * LINEAR_SEMITONE,
+            FrequencyTable::Amiga => {
+                AMIGA_CLOCK * 2.0 / (XM_C4_RATE * 2f32.powf((key - 48.0) / 12.0))
+            }
+        };
+        Some(period.round() as i32)
+    }
+
+    fn period_range(&self) -> (i32, i32) {
+        match (self.format, self.frequency) {
+            (ModuleFormat::Mod, _) => (PERIOD_MIN, PERIOD_MAX),
+            (_, FrequencyTable::Linear) => (1, LINEAR_PERIOD_MAX),
+            (_, FrequencyTable::Amiga) => (1, 32000),
+        }
+    }
+
+    fn frequency(&self, period: i32) -> f32 {
+        match self.frequency {
+            FrequencyTable::Amiga => AMIGA_CLOCK * 2.0 / period as f32,
+            FrequencyTable::Linear => {
+                XM_C4_RATE
+                    * 2f32.powf(
+                        (LINEAR_PERIOD_MAX as f32 - 48.0 * LINEAR_SEMITONE - period as f32)
+                            / (12.0 * LINEAR_SEMITONE),
+                    )
+            }
+        }
+    }
+
+    fn pattern_rows(&self, order: usize) -> usize {
+        self.orders
+            .get(order)
+            .and_then(|index| self.patterns.get(*index as usize))
+            .map_or(1, |pattern| (pattern.len() / self.channels).max(1))
+    }
+}
+
+fn read_name(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches('\0')
+        .trim()
+        .to_string()
+}
+
+// 小端读取 越界时返回错误
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn bytes(&self, offset: usize, len: usize) -> Result<&'a [u8], String> {
+        self.bytes
+            .get(offset..offset + len)
+            .ok_or_else(|| String::from("file truncated"))
+    }
+
+    fn u8(&self, offset: usize) -> Result<u8, String> {
+        Ok(self.bytes(offset, 1)?[0])
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, String> {
+        let b = self.bytes(offset, 2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, String> {
+        let b = self.bytes(offset, 4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    // 乐器头中的包络 参数为各字段相对乐器头的偏移
+    fn envelope(
+        &self,
+        offset: usize,
+        points: usize,
+        count: usize,
+        sustain: usize,
+        flags: usize,
+    ) -> Result<Option<Envelope>, String> {
+        let flags = self.u8(offset + flags)?;
+        if flags & 1 == 0 {
+            return Ok(None);
+        }
+
+        let count = (self.u8(offset + count)? as usize).min(12);
+        let points = (0..count)
+            .map(|i| {
+                let at = offset + points + i * 4;
+                Ok((self.u16(at)?, self.u16(at + 2)?.min(64) as u8))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        // 延音点后依次为循环起点与终点
+        let sustain_point = self.u8(offset + sustain)? as usize;
+        let loop_start = self.u8(offset + sustain + 1)? as usize;
+        let loop_end = self.u8(offset + sustain + 2)? as usize;
+        Ok(Some(Envelope {
+            points,
+            sustain: (flags & 2 != 0).then_some(sustain_point),
+            loop_points: (flags & 4 != 0).then_some((loop_start, loop_end)),
+        }))
+    }
+
+    // 读取下一个压缩音符 最高位为 1 时其余位标记后续存在的字段
+    fn note(&mut self) -> Result<ModNote, String> {
+        let first = self.u8(self.pos)?;
+        self.pos += 1;
+        let mask = if first & 0x80 != 0 {
+            first
+        } else {
+            self.pos -= 1;
+            0x1F
+        };
+
+        let mut fields = [0u8; 5];
+        for (bit, field) in fields.iter_mut().enumerate() {
+            if mask & (1 << bit) != 0 {
+                *field = self.u8(self.pos)?;
+                self.pos += 1;
+            }
+        }
+        let [key, instrument, volume, effect, param] = fields;
+        Ok(ModNote {
+            instrument,
+            key,
+            volume,
+            effect,
+            param,
+            ..default()
+        })
+    }
+}
+
+// 播放控制 播放中修改立即生效
+#[derive(Debug)]
+pub struct TrackerControls {
+    // 速度倍率 f32 位模式
+    tempo: AtomicU32,
+    // 静音声道位掩码
+    muted: AtomicU32,
+}
+
+impl Default for TrackerControls {
+    fn default() -> Self {
+        Self {
+            tempo: AtomicU32::new(1f32.to_bits()),
+            muted: AtomicU32::new(0),
+        }
+    }
+}
+
+impl TrackerControls {
+    pub fn tempo_scale(&self) -> f32 {
+        f32::from_bits(self.tempo.load(Ordering::Relaxed))
+    }
+
+    // 只改变速度 不改变音高
+    pub fn set_tempo_scale(&self, scale: f32) {
+        self.tempo
+            .store(scale.max(0.1).to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn muted_channels(&self) -> u32 {
+        self.muted.load(Ordering::Relaxed)
+    }
+
+    pub fn set_muted_channels(&self, mask: u32) {
+        self.muted.store(mask, Ordering::Relaxed);
+    }
+
+    pub fn is_muted(&self, channel: usize) -> bool {
+        channel < 32 && self.muted_channels() & (1 << channel) != 0
+    }
+}
+
+// 音轨资源 同一曲目可生成多个实例 各自拥有播放控制
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct TrackerModule {
+    pub data: Arc<ModuleData>,
+    pub controls: Arc<TrackerControls>,
+}
+
+impl TrackerModule {
+    pub fn new(data: ModuleData) -> Self {
+        Self {
+            data: Arc::new(data),
+            controls: default(),
+        }
+    }
+
+    // 共享曲目数据的新实例
+    pub fn instance(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            controls: default(),
+        }
+    }
+}
+
+impl Decodable for TrackerModule {
+    type DecoderItem = f32;
+    type Decoder = TrackerPlayer;
+
+    fn decoder(&self) -> Self::Decoder {
+        TrackerPlayer::new(self.data.clone(), self.controls.clone())
+    }
+}
+
+#[derive(Default)]
+pub struct TrackerModuleLoader;
+
+impl AssetLoader for TrackerModuleLoader {
+    type Asset = TrackerModule;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = vec![];
+            reader.read_to_end(&mut bytes).await?;
+
+            ModuleData::parse(&bytes)
+                .map(TrackerModule::new)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["mod", "xm"]
+    }
+}
+
+pub struct TrackerPlugin;
+
+impl Plugin for TrackerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<TrackerModule>()
+            .init_asset_loader::<TrackerModuleLoader>();
+    }
+}
+
+// 声道状态
+#[derive(Debug, Default, Clone)]
+struct Channel {
+    // 当前乐器与采样下标
+    instrument: Option<usize>,
+    sample: Option<usize>,
+    pos: f32,
+    // 来回循环时正在倒放
+    backwards: bool,
+    active: bool,
+    period: i32,
+    volume: i32,
+    // 声像 0..=255
+    panning: i32,
+    note: ModNote,
+    // 最近的 XM 音符 只写乐器时据此选择采样
+    key: u8,
+    // 已松键 包络越过延音点 带音量包络的乐器开始淡出
+    released: bool,
+    faded: i32,
+    volume_envelope_pos: u16,
+    panning_envelope_pos: u16,
+    // 本 tick 的包络值 乐器没有包络时为空
+    volume_envelope: Option<f32>,
+    panning_envelope: Option<f32>,
+    // 输出时的周期偏移 琶音与颤音使用
+    period_offset: i32,
+    arpeggio: i32,
+    porta_target: i32,
+    porta_speed: i32,
+    vibrato_pos: usize,
+    vibrato_speed: usize,
+    vibrato_depth: i32,
+    offset_memory: u8,
+}
+
+impl Channel {
+    fn slide_volume(&mut self, param: u8) {
+        let up = (param >> 4) as i32;
+        let down = (param & 0x0F) as i32;
+        self.volume = if up > 0 {
+            self.volume + up
+        } else {
+            self.volume - down
+        }
+        .clamp(0, 64);
+    }
+
+    fn slide_panning(&mut self, param: u8) {
+        let right = (param >> 4) as i32;
+        let left = (param & 0x0F) as i32;
+        self.panning = if right > 0 {
+            self.panning + right
+        } else {
+            self.panning - left
+        }
+        .clamp(0, 255);
+    }
+
+    fn slide_period(&mut self, delta: i32, (min, max): (i32, i32)) {
+        self.period = (self.period + delta).clamp(min, max);
+    }
+
+    fn tone_portamento(&mut self) {
+        if self.porta_target == 0 {
+            return;
+        }
+        if self.period < self.porta_target {
+            self.period = (self.period + self.porta_speed).min(self.porta_target);
+        } else {
+            self.period = (self.period - self.porta_speed).max(self.porta_target);
+        }
+    }
+
+    fn vibrato(&mut self) {
+        let value = VIBRATO_TABLE[self.vibrato_pos & 31] * self.vibrato_depth / 32;
+        self.period_offset = if self.vibrato_pos & 32 == 0 {
+            value
+        } else {
+            -value
+        };
+        self.vibrato_pos = (self.vibrato_pos + self.vibrato_speed) & 63;
+    }
+
+    // 乐器重新触发 包络从头开始
+    fn trigger_envelopes(&mut self) {
+        self.released = false;
+        self.faded = 0;
+        self.volume_envelope_pos = 0;
+        self.panning_envelope_pos = 0;
+    }
+
+    // 没有音量包络的乐器松键即静音
+    fn key_off(&mut self, instrument: Option<&Instrument>) {
+        self.released = true;
+        if instrument.is_none_or(|i| i.volume_envelope.is_none()) {
+            self.volume = 0;
+        }
+    }
+
+    fn update_envelopes(&mut self, instrument: Option<&Instrument>) {
+        let volume = instrument.and_then(|i| i.volume_envelope.as_ref());
+        let panning = instrument.and_then(|i| i.panning_envelope.as_ref());
+
+        self.volume_envelope = volume.map(|e| e.value(self.volume_envelope_pos));
+        self.panning_envelope = panning.map(|e| e.value(self.panning_envelope_pos));
+        if let Some(envelope) = volume {
+            self.volume_envelope_pos = envelope.advance(self.volume_envelope_pos, !self.released);
+        }
+        if let Some(envelope) = panning {
+            self.panning_envelope_pos = envelope.advance(self.panning_envelope_pos, !self.released);
+        }
+
+        if self.released && volume.is_some() {
+            let fadeout = instrument.map_or(0, |i| i.fadeout as i32);
+            self.faded = (self.faded + fadeout).min(FADEOUT_MAX);
+        }
+    }
+
+    // 最终音量 0..=1 计入包络与淡出
+    fn output_volume(&self) -> f32 {
+        self.volume as f32 / 64.0
+            * self.volume_envelope.map_or(1.0, |value| value / 64.0)
+            * (FADEOUT_MAX - self.faded) as f32
+            / FADEOUT_MAX as f32
+    }
+
+    // 最终声像 0..=1 包络在当前声像到边缘的范围内偏移
+    fn output_panning(&self) -> f32 {
+        let panning = self.panning as f32;
+        let offset = self.panning_envelope.map_or(0.0, |value| {
+            (value - 32.0) / 32.0 * (128.0 - (panning - 128.0).abs())
+        });
+        ((panning + offset) / 255.0).clamp(0.0, 1.0)
+    }
+}
+
+// 实时混音 双声道 MOD 为 Amiga 声像 XM 由乐器与包络决定声像
+pub struct TrackerPlayer {
+    data: Arc<ModuleData>,
+    controls: Arc<TrackerControls>,
+    channels: Vec<Channel>,
+    order: usize,
+    row: usize,
+    tick: usize,
+    speed: usize,
+    bpm: u32,
+    global_volume: i32,
+    // 距下一 tick 的采样数
+    tick_remaining: f32,
+    // 行结束后跳转的位置
+    jump: Option<(usize, usize)>,
+    frame: [f32; 2],
+    right: bool,
+}
+
+impl TrackerPlayer {
+    pub fn new(data: Arc<ModuleData>, controls: Arc<TrackerControls>) -> Self {
+        // MOD 左右右左 另一侧保留少量避免耳机中过于割裂 XM 默认居中
+        let channels = (0..data.channels)
+            .map(|index| Channel {
+                panning: match data.format {
+                    ModuleFormat::Mod if matches!(index % 4, 0 | 3) => 64,
+                    ModuleFormat::Mod => 191,
+                    ModuleFormat::Xm => 128,
+                },
+                ..default()
+            })
+            .collect();
+        let mut player = Self {
+            speed: data.speed,
+            bpm: data.bpm,
+            data,
+            controls,
+            channels,
+            order: 0,
+            row: 0,
+            tick: 0,
+            global_volume: 64,
+            tick_remaining: 0.0,
+            jump: None,
+            frame: [0.0; 2],
+            right: false,
+        };
+        player.play_row();
+        player.update_envelopes();
+        player.tick_remaining = player.tick_len();
+        player
+    }
+
+    fn tick_len(&self) -> f32 {
+        TRACKER_SAMPLE_RATE as f32 * 2.5 / (self.bpm as f32 * self.controls.tempo_scale())
+    }
+
+    // 新的一行 tick 0 的处理
+    fn play_row(&mut self) {
+        let data = self.data.clone();
+        let Some(pattern) = data
+            .orders
+            .get(self.order)
+            .and_then(|index| data.patterns.get(*index as usize))
+        else {
+            return;
+        };
+        let range = data.period_range();
+
+        for (index, channel) in self.channels.iter_mut().enumerate() {
+            let Some(&note) = pattern.get(self.row * data.channels + index) else {
+                continue;
+            };
+            channel.note = note;
+            channel.period_offset = 0;
+            channel.arpeggio = 0;
+
+            if note.has_note() && note.key > 0 {
+                channel.key = note.key;
+                // XM 新音符按键位换成乐器内对应的采样
+                if !note.is_tone_portamento() {
+                    if let Some(instrument) =
+                        channel.instrument.and_then(|i| data.instruments.get(i))
+                    {
+                        channel.sample = instrument.sample(note.key);
+                    }
+                }
+            }
+
+            if note.instrument > 0 {
+                let index = note.instrument as usize - 1;
+                if let Some(instrument) = data.instruments.get(index) {
+                    channel.instrument = Some(index);
+                    if let Some(sample) = instrument.sample(channel.key) {
+                        if let Some(def) = data.samples.get(sample) {
+                            channel.sample = Some(sample);
+                            channel.volume = def.volume as i32;
+                            if let Some(panning) = def.panning {
+                                channel.panning = panning as i32;
+                            }
+                        }
+                    }
+                    channel.trigger_envelopes();
+                }
+            }
+
+            let sample = channel.sample.and_then(|i| data.samples.get(i));
+            if let Some(period) = data.note_period(&note, sample) {
+                if note.is_tone_portamento() {
+                    channel.porta_target = period;
+                } else {
+                    channel.period = period;
+                    channel.pos = 0.0;
+                    channel.backwards = false;
+                    channel.active = channel.sample.is_some();
+                    channel.vibrato_pos = 0;
+                    channel.porta_target = 0;
+                }
+            }
+            if note.key == XM_KEY_OFF {
+                channel.key_off(channel.instrument.and_then(|i| data.instruments.get(i)));
+            }
+
+            // XM 音量列
+            let value = (note.volume & 0x0F) as i32;
+            match note.volume >> 4 {
+                0x1..=0x4 => channel.volume = note.volume as i32 - 0x10,
+                0x5 => channel.volume = 64,
+                0x8 => channel.volume = (channel.volume - value).max(0),
+                0x9 => channel.volume = (channel.volume + value).min(64),
+                0xA if value > 0 => channel.vibrato_speed = value as usize,
+                0xB if value > 0 => channel.vibrato_depth = value,
+                0xC => channel.panning = value * 17,
+                0xF if value > 0 => channel.porta_speed = value * 64,
+                _ => {}
+            }
+
+            let param = note.param;
+            let (x, y) = ((param >> 4) as i32, (param & 0x0F) as i32);
+            match note.effect {
+                0x3 if param > 0 => channel.porta_speed = param as i32 * 4,
+                0x4 => {
+                    if x > 0 {
+                        channel.vibrato_speed = x as usize;
+                    }
+                    if y > 0 {
+                        channel.vibrato_depth = y;
+                    }
+                }
+                0x8 => channel.panning = param as i32,
+                0x9 => {
+                    if param > 0 {
+                        channel.offset_memory = param;
+                    }
+                    if note.has_note() {
+                        channel.pos = channel.offset_memory as f32 * 256.0;
+                    }
+                }
+                0xB => self.jump = Some((param as usize, 0)),
+                0xC => channel.volume = (param as i32).min(64),
+                0xD => {
+                    let order = self.jump.map_or(self.order + 1, |(order, _)| order);
+                    self.jump = Some((order, (x * 10 + y) as usize));
+                }
+                0xE => match x {
+                    0x1 => channel.slide_period(-y * 4, range),
+                    0x2 => channel.slide_period(y * 4, range),
+                    0xA => channel.volume = (channel.volume + y).min(64),
+                    0xB => channel.volume = (channel.volume - y).max(0),
+                    0xC if y == 0 => channel.volume = 0,
+                    _ => {}
+                },
+                0xF if param > 0 => {
+                    if param < 32 {
+                        self.speed = param as usize;
+                    } else {
+                        self.bpm = param as u32;
+                    }
+                }
+                // XM 全局音量 Gxx
+                0x10 => self.global_volume = (param as i32).min(64),
+                // XM 松键 Kxx
+                0x14 if param == 0 => {
+                    channel.key_off(channel.instrument.and_then(|i| data.instruments.get(i)));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // tick 1 之后的持续效果
+    fn play_tick(&mut self) {
+        let data = self.data.clone();
+        let range = data.period_range();
+        let tick = self.tick as i32;
+        for channel in self.channels.iter_mut() {
+            let param = channel.note.param;
+            let (x, y) = ((param >> 4) as i32, (param & 0x0F) as i32);
+            channel.period_offset = 0;
+
+            let value = (channel.note.volume & 0x0F) as i32;
+            match channel.note.volume >> 4 {
+                0x6 => channel.volume = (channel.volume - value).max(0),
+                0x7 => channel.volume = (channel.volume + value).min(64),
+                0xB => channel.vibrato(),
+                0xD => channel.panning = (channel.panning - value).max(0),
+                0xE => channel.panning = (channel.panning + value).min(255),
+                0xF => channel.tone_portamento(),
+                _ => {}
+            }
+
+            match channel.note.effect {
+                0x0 if param > 0 => {
+                    channel.arpeggio = match tick % 3 {
+                        1 => x,
+                        2 => y,
+                        _ => 0,
+                    };
+                }
+                0x1 => channel.slide_period(-(param as i32) * 4, range),
+                0x2 => channel.slide_period(param as i32 * 4, range),
+                0x3 => channel.tone_portamento(),
+                0x4 => channel.vibrato(),
+                0x5 => {
+                    channel.tone_portamento();
+                    channel.slide_volume(param);
+                }
+                0x6 => {
+                    channel.vibrato();
+                    channel.slide_volume(param);
+                }
+                0xA => channel.slide_volume(param),
+                0xE if x == 0xC && y == tick => channel.volume = 0,
+                // XM 全局音量滑动 Hxy
+                0x11 => {
+                    self.global_volume = if x > 0 {
+                        self.global_volume + x
+                    } else {
+                        self.global_volume - y
+                    }
+                    .clamp(0, 64);
+                }
+                0x14 if param as i32 == tick => {
+                    channel.key_off(channel.instrument.and_then(|i| data.instruments.get(i)));
+                }
+                // XM 声像滑动 Pxy
+                0x19 => channel.slide_panning(param),
+                _ => {}
+            }
+        }
+    }
+
+    fn update_envelopes(&mut self) {
+        for channel in self.channels.iter_mut() {
+            let instrument = channel
+                .instrument
+                .and_then(|i| self.data.instruments.get(i));
+            channel.update_envelopes(instrument);
+        }
+    }
+
+    fn next_tick(&mut self) {
+        self.tick += 1;
+        if self.tick < self.speed {
+            self.play_tick();
+            self.update_envelopes();
+            return;
+        }
+
+        self.tick = 0;
+        match self.jump.take() {
+            Some((order, row)) => {
+                self.order = order;
+                self.row = row;
+            }
+            None => {
+                self.row += 1;
+                if self.row >= self.data.pattern_rows(self.order) {
+                    self.row = 0;
+                    self.order += 1;
+                }
+            }
+        }
+        // 结束后回到 restart 无缝循环
+        if self.order >= self.data.orders.len() {
+            self.order = self.data.restart;
+        }
+        // XM 的 pattern 行数不一 跳转行超出时取最后一行
+        self.row = self.row.min(self.data.pattern_rows(self.order) - 1);
+        self.play_row();
+        self.update_envelopes();
+    }
+
+    fn mix_frame(&mut self) -> [f32; 2] {
+        let (period_min, _) = self.data.period_range();
+        let mut frame = [0.0f32; 2];
+        for (index, channel) in self.channels.iter_mut().enumerate() {
+            if !channel.active || channel.period <= 0 {
+                continue;
+            }
+            let Some(sample) = channel.sample.and_then(|i| self.data.samples.get(i)) else {
+                continue;
+            };
+            if sample.data.is_empty() {
+                channel.active = false;
+                continue;
+            }
+
+            let period = (channel.period + channel.period_offset).max(period_min);
+            let pitch = 2f32.powf(channel.arpeggio as f32 / 12.0);
+            let step = self.data.frequency(period) * pitch / TRACKER_SAMPLE_RATE as f32;
+
+            let value = sample.data[(channel.pos as usize).min(sample.data.len() - 1)] as f32
+                / 32768.0
+                * channel.output_volume()
+                * self.global_volume as f32
+                / 64.0;
+            channel.pos += if channel.backwards { -step } else { step };
+            if sample.loop_len > 0 {
+                let loop_start = sample.loop_start as f32;
+                let loop_end = (sample.loop_start + sample.loop_len) as f32;
+                if !sample.ping_pong {
+                    while channel.pos >= loop_end {
+                        channel.pos -= sample.loop_len as f32;
+                    }
+                } else if channel.pos >= loop_end {
+                    // 来回循环 在两端反射
+                    channel.pos = (2.0 * loop_end - channel.pos).max(loop_start);
+                    channel.backwards = true;
+                } else if channel.backwards && channel.pos < loop_start {
+                    channel.pos = (2.0 * loop_start - channel.pos).min(loop_end - 1.0);
+                    channel.backwards = false;
+                }
+            } else if channel.pos >= sample.data.len() as f32 {
+                channel.active = false;
+            }
+
+            if self.controls.is_muted(index) {
+                continue;
+            }
+            let right = channel.output_panning();
+            frame[0] += value * (1.0 - right);
+            frame[1] += value * right;
+        }
+
+        let gain = TRACKER_GAIN / (self.channels.len() as f32 / 4.0).max(1.0);
+        frame.map(|value| (value * gain).clamp(-1.0, 1.0))
+    }
+}
+
+impl Iterator for TrackerPlayer {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.right {
+            self.right = false;
+            return Some(self.frame[1]);
+        }
+
+        self.tick_remaining -= 1.0;
+        if self.tick_remaining <= 0.0 {
+            self.next_tick();
+            self.tick_remaining += self.tick_len();
+        }
+        self.frame = self.mix_frame();
+        self.right = true;
+        Some(self.frame[0])
+    }
+}
+
+impl Source for TrackerPlayer {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    fn sample_rate(&self) -> u32 {
+        TRACKER_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 两个声道 一个 pattern 一个带音量包络的 16 位乐器
+    // 第 0 行 C-4 第 1 行松键
+    fn xm(linear: bool) -> Vec<u8> {
+        let mut bytes = XM_ID.to_vec();
+        bytes.extend([0; 20]);
+        bytes.push(0x1A);
+        bytes.extend([0; 20]);
+        bytes.extend(0x0104u16.to_le_bytes());
+        bytes.extend(276u32.to_le_bytes());
+        for value in [1u16, 0, 2, 1, 1, linear as u16, 6, 125] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend([0; 256]);
+
+        let packed = [
+            49, 1, 0, 0, 0, 0x80, 0x81, XM_KEY_OFF, 0x80, 0x80, 0x80, 0x80, 0x80,
+        ];
+        bytes.extend(9u32.to_le_bytes());
+        bytes.push(0);
+        bytes.extend(4u16.to_le_bytes());
+        bytes.extend((packed.len() as u16).to_le_bytes());
+        bytes.extend(packed);
+
+        let instrument = bytes.len();
+        bytes.extend(263u32.to_le_bytes());
+        bytes.extend([0; 23]);
+        bytes.extend(1u16.to_le_bytes());
+        bytes.extend(40u32.to_le_bytes());
+        bytes.extend([0; 96]);
+        for (x, y) in [(0u16, 64u16), (4, 32), (8, 0)] {
+            bytes.extend(x.to_le_bytes());
+            bytes.extend(y.to_le_bytes());
+        }
+        bytes.resize(instrument + 225, 0);
+        // 3 个音量包络点 延音点 1 开启包络与延音
+        bytes.extend([3, 0, 1, 0, 0, 0, 0, 0, 0b011, 0, 0, 0, 0, 0]);
+        bytes.extend(0x800u16.to_le_bytes());
+        bytes.resize(instrument + 263, 0);
+
+        for value in [16u32, 0, 16] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend([64, 0, 0x11, 128, 0, 0]);
+        bytes.extend([0; 22]);
+        bytes.extend(8192i16.to_le_bytes());
+        bytes.extend([0; 14]);
+        bytes
+    }
+
+    #[test]
+    fn parse_xm_reads_patterns_instruments_and_samples() {
+        let data = ModuleData::parse(&xm(true)).unwrap();
+        assert_eq!(data.format, ModuleFormat::Xm);
+        assert_eq!(data.frequency, FrequencyTable::Linear);
+        assert_eq!((data.channels, data.speed, data.bpm), (2, 6, 125));
+
+        let pattern = &data.patterns[0];
+        assert_eq!(pattern.len(), 8);
+        assert_eq!((pattern[0].key, pattern[0].instrument), (49, 1));
+        assert_eq!(pattern[2].key, XM_KEY_OFF);
+
+        let instrument = &data.instruments[0];
+        assert_eq!(instrument.sample(49), Some(0));
+        assert_eq!(instrument.fadeout, 0x800);
+        let envelope = instrument.volume_envelope.as_ref().unwrap();
+        assert_eq!(envelope.points, vec![(0, 64), (4, 32), (8, 0)]);
+        assert_eq!(envelope.sustain, Some(1));
+        assert!(instrument.panning_envelope.is_none());
+
+        // 差分解码后为常量 长度与循环按 16 位采样计
+        let sample = &data.samples[0];
+        assert_eq!(sample.data, vec![8192; 8]);
+        assert_eq!((sample.loop_start, sample.loop_len), (0, 8));
+        assert_eq!(sample.panning, Some(128));
+    }
+
+    #[test]
+    fn parse_xm_rejects_truncated_file() {
+        let bytes = xm(true);
+        assert!(ModuleData::parse(&bytes[..bytes.len() - 4]).is_err());
+    }
+
+    #[test]
+    fn c4_plays_at_xm_rate_on_both_tables() {
+        for linear in [true, false] {
+            let data = ModuleData::parse(&xm(linear)).unwrap();
+            let sample = data.samples.first();
+            let frequency = |key: u8| {
+                let note = ModNote { key, ..default() };
+                data.frequency(data.note_period(&note, sample).unwrap())
+            };
+            assert!((frequency(49) / XM_C4_RATE - 1.0).abs() < 0.001);
+            assert!((frequency(61) / frequency(49) - 2.0).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn envelope_holds_at_sustain_until_released() {
+        let envelope = Envelope {
+            points: vec![(0, 64), (4, 32), (8, 0)],
+            sustain: Some(1),
+            loop_points: None,
+        };
+        assert_eq!(envelope.value(2), 48.0);
+        assert_eq!(envelope.value(20), 0.0);
+        assert_eq!(envelope.advance(4, true), 4);
+        assert_eq!(envelope.advance(4, false), 5);
+
+        let looped = Envelope {
+            loop_points: Some((0, 1)),
+            ..envelope
+        };
+        assert_eq!(looped.advance(4, false), 0);
+    }
+
+    #[test]
+    fn key_off_releases_envelope_and_fades_out() {
+        let data = Arc::new(ModuleData::parse(&xm(true)).unwrap());
+        let mut player = TrackerPlayer::new(data, default());
+        assert!(player.channels[0].active);
+
+        // 停在延音点
+        for _ in 0..5 {
+            player.next_tick();
+        }
+        assert_eq!(player.channels[0].volume_envelope, Some(32.0));
+        assert!(!player.channels[0].released);
+
+        // 第 1 行松键后包络继续 音量逐渐淡出
+        player.next_tick();
+        assert!(player.channels[0].released);
+        let volume = player.channels[0].output_volume();
+        player.next_tick();
+        assert!(player.channels[0].output_volume() < volume);
+        assert!(player.channels[0].faded > 0);
+    }
+
+    #[test]
+    fn parse_mod_maps_each_sample_to_an_instrument() {
+        let data = ModuleData::parse(include_bytes!("../assets/audio/theme.mod")).unwrap();
+        assert_eq!(data.format, ModuleFormat::Mod);
+        assert_eq!(data.instruments.len(), 31);
+        assert_eq!(data.instruments[4].sample(0), Some(4));
+        assert!(data
+            .patterns
+            .iter()
+            .all(|p| p.len() == MOD_ROWS * data.channels));
+    }
+}
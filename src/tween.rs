@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+
+// 补间动画 在一段时间内按缓动曲线把位置 缩放 颜色过渡到目标值
+// 结束时对齐终点 移除组件并发出 TweenCompleted
+
+// 缓动曲线 输入输出均为 0-1 进度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Ease {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicOut,
+    // 略微越过终点再回弹
+    BackOut,
+}
+
+impl Ease {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Ease::Linear => t,
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Ease::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Ease::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Ease::BackOut => {
+                let c1 = 1.70158;
+                let c3 = c1 + 1.0;
+                1.0 + c3 * (t - 1.0).powi(3) + c1 * (t - 1.0).powi(2)
+            }
+        }
+    }
+}
+
+// 补间目标 颜色作用于精灵 文字与 UI 背景
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TweenTarget {
+    Translation { from: Vec3, to: Vec3 },
+    // 沿三次贝塞尔曲线移动
+    Path([Vec3; 4]),
+    Scale { from: Vec3, to: Vec3 },
+    Color { from: Color, to: Color },
+    // 只改变透明度 保留原有颜色
+    Alpha { from: f32, to: f32 },
+}
+
+// 补间组件 同一实体可同时过渡多个目标 共用时长与缓动曲线
+#[derive(Component, Debug, Clone)]
+pub struct Tween {
+    targets: Vec<TweenTarget>,
+    timer: Timer,
+    ease: Ease,
+    despawn: bool,
+}
+
+impl Tween {
+    pub fn new(duration: f32, ease: Ease) -> Self {
+        Self {
+            targets: vec![],
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+            ease,
+            despawn: false,
+        }
+    }
+
+    pub fn with(mut self, target: TweenTarget) -> Self {
+        self.targets.push(target);
+        self
+    }
+
+    // 结束后销毁实体及其子实体
+    pub fn despawn_on_complete(mut self) -> Self {
+        self.despawn = true;
+        self
+    }
+
+    // 当前缓动后的进度
+    pub fn progress(&self) -> f32 {
+        self.ease.apply(self.timer.fraction())
+    }
+}
+
+// 补间结束
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TweenCompleted {
+    pub entity: Entity,
+}
+
+pub struct TweenPlugin;
+
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TweenCompleted>()
+            .add_systems(Update, animate_tweens);
+    }
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let [r0, g0, b0, a0] = from.as_rgba_f32();
+    let [r1, g1, b1, a1] = to.as_rgba_f32();
+    Color::rgba(
+        r0 + (r1 - r0) * t,
+        g0 + (g1 - g0) * t,
+        b0 + (b1 - b0) * t,
+        a0 + (a1 - a0) * t,
+    )
+}
+
+impl TweenTarget {
+    fn is_color(&self) -> bool {
+        matches!(self, TweenTarget::Color { .. } | TweenTarget::Alpha { .. })
+    }
+
+    // 位置与缩放
+    fn apply_transform(&self, transform: &mut Transform, t: f32) {
+        match *self {
+            TweenTarget::Translation { from, to } => transform.translation = from.lerp(to, t),
+            TweenTarget::Path(points) => {
+                transform.translation = CubicBezier::new([points]).to_curve().position(t)
+            }
+            TweenTarget::Scale { from, to } => transform.scale = from.lerp(to, t),
+            TweenTarget::Color { .. } | TweenTarget::Alpha { .. } => {}
+        }
+    }
+
+    // 颜色与透明度
+    fn apply_color(&self, color: &mut Color, t: f32) {
+        match *self {
+            TweenTarget::Color { from, to } => *color = lerp_color(from, to, t),
+            TweenTarget::Alpha { from, to } => {
+                color.set_a(from + (to - from) * t);
+            }
+            _ => {}
+        }
+    }
+}
+
+// 推进补间 并把当前值写入对应组件
+#[allow(clippy::type_complexity)]
+fn animate_tweens(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut completed_events: EventWriter<TweenCompleted>,
+    mut query: Query<(
+        Entity,
+        &mut Tween,
+        Option<&mut Transform>,
+        Option<&mut Sprite>,
+        Option<&mut Text>,
+        Option<&mut BackgroundColor>,
+    )>,
+) {
+    for (entity, mut tween, mut transform, mut sprite, mut text, mut background) in query.iter_mut()
+    {
+        let finished = tween.timer.tick(time.delta()).finished();
+        // 结束时取终点值 避免浮点误差
+        let t = if finished { 1.0 } else { tween.progress() };
+
+        for target in tween.targets.iter() {
+            if !target.is_color() {
+                if let Some(transform) = transform.as_mut() {
+                    target.apply_transform(transform, t);
+                }
+                continue;
+            }
+            if let Some(sprite) = sprite.as_mut() {
+                target.apply_color(&mut sprite.color, t);
+            }
+            if let Some(text) = text.as_mut() {
+                for section in text.sections.iter_mut() {
+                    target.apply_color(&mut section.style.color, t);
+                }
+            }
+            if let Some(background) = background.as_mut() {
+                target.apply_color(&mut background.0, t);
+            }
+        }
+
+        if finished {
+            commands.entity(entity).remove::<Tween>();
+            if tween.despawn {
+                commands.entity(entity).despawn_recursive();
+            }
+            completed_events.send(TweenCompleted { entity });
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::block::{block_bundle, grid_from_blocks, Block, BlockKind};
+use crate::board::{Board, InBoard};
+use crate::events::BlocksRemoved;
+use crate::resources::GlobalTextAtlas;
+use crate::rules::{cell_to_pos, garbage_count};
+use crate::state::{
+    apply_board_transitions, BlockGroupState, BoardState, BoardTransition, GameMode, GameState,
+    HandBlockState, SettlementState,
+};
+
+// 本地双人对战 一次消除多个方块时给对手送垃圾方块 先通关者赢得本局
+
+// 待落下的垃圾方块数
+#[derive(Component, Default)]
+pub struct PendingGarbage(pub u32);
+
+// 本局胜者
+#[derive(Component)]
+pub struct RoundWinner;
+
+pub struct VersusPlugin;
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::GameInit), clear_round_winner)
+            .add_systems(
+                PreUpdate,
+                decide_round_winner
+                    .after(apply_board_transitions::<SettlementState>)
+                    .run_if(is_versus_mode),
+            )
+            .add_systems(
+                Update,
+                (send_garbage, drop_garbage)
+                    .chain()
+                    .run_if(is_versus_mode)
+                    .run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+// 是否为对战模式
+pub fn is_versus_mode(mode: Res<GameMode>) -> bool {
+    mode.is_versus()
+}
+
+// 消除多个方块 给其他棋盘增加垃圾方块
+fn send_garbage(
+    mut events: EventReader<BlocksRemoved>,
+    mut board_query: Query<(Entity, &mut PendingGarbage)>,
+) {
+    for event in events.read() {
+        let count = garbage_count(event.count);
+        if count == 0 {
+            continue;
+        }
+
+        for (board, mut pending) in board_query.iter_mut() {
+            if board != event.board {
+                pending.0 += count;
+            }
+        }
+    }
+}
+
+// 手里方块静止且方块组不在下落时落下垃圾方块
+#[allow(clippy::type_complexity)]
+fn drop_garbage(
+    mut commands: Commands,
+    handle: Res<GlobalTextAtlas>,
+    mut board_query: Query<(
+        Entity,
+        &mut PendingGarbage,
+        &BoardState<HandBlockState>,
+        &BoardState<BlockGroupState>,
+        &BoardState<SettlementState>,
+    )>,
+    block_query: Query<(&Transform, &TextureAtlas, &InBoard), With<Block>>,
+) {
+    let mut rng = rand::thread_rng();
+    let kinds = BlockKind::ALL
+        .into_iter()
+        .filter(|kind| *kind != BlockKind::Lightning)
+        .collect::<Vec<_>>();
+
+    for (board, mut pending, hand_state, group_state, settlement_state) in board_query.iter_mut() {
+        if pending.0 == 0
+            || !hand_state.is(HandBlockState::Idle)
+            || !group_state.is(BlockGroupState::Static)
+            || !settlement_state.is(SettlementState::Not)
+        {
+            continue;
+        }
+
+        let garbage = (0..pending.0)
+            .map(|_| kinds[rng.gen_range(0..kinds.len())].atlas_index())
+            .collect::<Vec<_>>();
+        pending.0 = 0;
+
+        let mut grid = grid_from_blocks(board, &block_query);
+        let cells = grid.drop_garbage(&garbage);
+
+        commands.entity(board).with_children(|parent| {
+            for (cell, index) in cells.iter().zip(garbage) {
+                parent.spawn(block_bundle(&handle, board, cell_to_pos(*cell), index));
+            }
+        });
+    }
+}
+
+// 先进入结算的棋盘获胜 其他棋盘随之结算
+fn decide_round_winner(
+    mut commands: Commands,
+    mut events: EventReader<BoardTransition<SettlementState>>,
+    mut board_query: Query<(
+        Entity,
+        &mut Board,
+        &mut BoardState<SettlementState>,
+        Has<RoundWinner>,
+    )>,
+) {
+    for event in events.read().filter(|e| e.entered(SettlementState::Start)) {
+        if board_query.iter().any(|(_, _, _, winner)| winner) {
+            continue;
+        }
+
+        for (board, mut b, mut state, _) in board_query.iter_mut() {
+            if board == event.board {
+                b.wins += 1;
+                commands.entity(board).insert(RoundWinner);
+            } else if state.is(SettlementState::Not) {
+                state.set(SettlementState::Start);
+            }
+        }
+
+        // 同一帧只决出一名胜者
+        break;
+    }
+}
+
+// 新的一局清除胜者
+fn clear_round_winner(mut commands: Commands, board_query: Query<Entity, With<RoundWinner>>) {
+    for board in board_query.iter() {
+        commands.entity(board).remove::<RoundWinner>();
+    }
+}
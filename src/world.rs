@@ -1,5 +1,4 @@
-use bevy::audio::PlaybackMode;
-use bevy::math::{vec2, vec3};
+use bevy::math::vec3;
 use bevy::prelude::*;
 #[allow(unused)]
 use rand::Rng;
@@ -7,33 +6,23 @@ use rand::Rng;
 use crate::state::{GameState, PlayerState};
 use crate::*;
 
-use crate::animation::AnimationTimer;
-use crate::block::{Block, HandBlock};
+use crate::animation::SpriteAnimation;
+use crate::block::HandBlock;
+use crate::board::{spawn_boards, Board, InBoard};
 use crate::player::{Ladder, Player};
-use crate::resources::{GlobalAudio, GlobalTextAtlas};
+use crate::resources::GlobalTextAtlas;
+use crate::rules::{cell_to_pos, terrain_cells, Terrain};
 use crate::wall::{Ground, Wall};
 
 #[derive(Component)]
 pub struct GameEntity;
 
-// 背景音乐是否播放
-#[derive(Resource)]
-struct BGMPlaying(bool);
-impl Default for BGMPlaying {
-    fn default() -> Self {
-        Self(false)
-    }
-}
-
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<BGMPlaying>()
-            // 进入主菜单 开始bgm
-            .add_systems(OnEnter(GameState::MainMenu), spawn_bgm)
-            // 初始化游戏
-            .add_systems(OnEnter(GameState::GameInit), init_world)
+        // 初始化游戏
+        app.add_systems(OnEnter(GameState::GameInit), init_world.after(spawn_boards))
             // 退出游戏 销毁所有
             .add_systems(OnExit(GameState::InGame), despawn_all_game_entities);
     }
@@ -43,13 +32,23 @@ impl Plugin for WorldPlugin {
 pub fn init_world(
     mut commands: Commands,
     handle: ResMut<GlobalTextAtlas>,
+    board_query: Query<Entity, With<Board>>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
-    let mut rng = rand::thread_rng();
+    for board in board_query.iter() {
+        commands
+            .entity(board)
+            .with_children(|parent| spawn_board_content(parent, board, &handle));
+    }
+
+    next_state.set(GameState::InGame);
+}
 
+// 生成棋盘内容 坐标相对棋盘
+fn spawn_board_content(parent: &mut ChildBuilder, board: Entity, handle: &GlobalTextAtlas) {
     // 生成玩家
     let (x, y) = PLAYER_INIT_POS;
-    commands.spawn((
+    parent.spawn((
         SpriteSheetBundle {
             texture: handle.image.clone().unwrap(),
             atlas: TextureAtlas {
@@ -62,13 +61,14 @@ pub fn init_world(
         },
         Player,
         PlayerState::default(),
-        AnimationTimer(Timer::from_seconds(0.2, TimerMode::Once)),
+        SpriteAnimation::new("player.idle"),
         GameEntity,
+        InBoard(board),
     ));
 
     // 生成梯子
     for i in 0..LADDER_NUM {
-        commands.spawn((
+        parent.spawn((
             SpriteSheetBundle {
                 texture: handle.image.clone().unwrap(),
                 atlas: TextureAtlas {
@@ -81,6 +81,7 @@ pub fn init_world(
             },
             GameEntity,
             Ladder,
+            InBoard(board),
         ));
     }
 
@@ -88,7 +89,7 @@ pub fn init_world(
     let hand_block_index = HAND_BLOCK_INDEX; // rng.gen_range(BLOCK_DISPLAY_RANGE); // HAND_BLOCK_INDEX; // 闪电是15
 
     // 生成手上方块
-    commands.spawn((
+    parent.spawn((
         SpriteSheetBundle {
             texture: handle.image.clone().unwrap(),
             atlas: TextureAtlas {
@@ -104,89 +105,28 @@ pub fn init_world(
             ..HandBlock::default()
         },
         GameEntity,
+        InBoard(board),
     ));
 
-    // 生成周围墙面
-    for i in 1..=25 {
-        for j in 0..=14 {
-            if j == 0 || j == 14 {
-                commands.spawn((
-                    SpriteSheetBundle {
-                        texture: handle.image.clone().unwrap(),
-                        atlas: TextureAtlas {
-                            layout: handle.layout.clone().unwrap(),
-                            index: 6,
-                        },
-                        transform: Transform::from_translation(vec3(
-                            -(WW + STEP_SIZE as f32) / 2.0 + (i * STEP_SIZE) as f32,
-                            (WH - STEP_SIZE as f32) / 2.0 - (j * STEP_SIZE) as f32,
-                            1.0,
-                        ))
-                        .with_scale(Vec3::splat(SPRITE_SCALE_FACTOR)),
-                        ..default()
-                    },
-                    // Wall,
-                    Ground,
-                    GameEntity,
-                ));
-            } else if i == 1 || i >= 19 {
-                commands.spawn((
-                    SpriteSheetBundle {
-                        texture: handle.image.clone().unwrap(),
-                        atlas: TextureAtlas {
-                            layout: handle.layout.clone().unwrap(),
-                            index: 6,
-                        },
-                        transform: Transform::from_translation(vec3(
-                            -(WW + STEP_SIZE as f32) / 2.0 + (i * STEP_SIZE) as f32,
-                            (WH - STEP_SIZE as f32) / 2.0 - (j * STEP_SIZE) as f32,
-                            1.0,
-                        ))
-                        .with_scale(Vec3::splat(SPRITE_SCALE_FACTOR)),
-                        ..default()
-                    },
-                    Wall,
-                    GameEntity,
-                ));
-            }
-        }
-    }
-
-    // 测试障碍墙
-    let wall_vec2 = vec![
-        // 墙体
-        [1, 1, 1, 1],
-        [1, 1, 1, 0],
-        [1, 1, 0, 0],
-        [1, 0, 0, 0],
-    ];
+    // 生成地形
+    for (cell, terrain) in terrain_cells() {
+        let pos = cell_to_pos(cell);
+        let bundle = SpriteSheetBundle {
+            texture: handle.image.clone().unwrap(),
+            atlas: TextureAtlas {
+                layout: handle.layout.clone().unwrap(),
+                index: 6,
+            },
+            transform: Transform::from_translation(pos.extend(1.0))
+                .with_scale(Vec3::splat(SPRITE_SCALE_FACTOR)),
+            ..default()
+        };
 
-    for j in 0..wall_vec2.len() {
-        for i in 0..wall_vec2[j].len() {
-            if wall_vec2[i][j] == 1 {
-                commands.spawn((
-                    SpriteSheetBundle {
-                        texture: handle.image.clone().unwrap(),
-                        atlas: TextureAtlas {
-                            layout: handle.layout.clone().unwrap(),
-                            index: 6,
-                        },
-                        transform: Transform::from_translation(vec3(
-                            -(WW + STEP_SIZE as f32) / 2.0 + ((i + 2) * STEP_SIZE) as f32,
-                            (WH - STEP_SIZE as f32) / 2.0 - ((j + 1) * STEP_SIZE) as f32,
-                            1.0,
-                        ))
-                        .with_scale(Vec3::splat(SPRITE_SCALE_FACTOR)),
-                        ..default()
-                    },
-                    Wall,
-                    GameEntity,
-                ));
-            }
-        }
+        match terrain {
+            Terrain::Ground => parent.spawn((bundle, Ground, GameEntity, InBoard(board))),
+            Terrain::Wall => parent.spawn((bundle, Wall, GameEntity, InBoard(board))),
+        };
     }
-
-    next_state.set(GameState::InGame);
 }
 
 pub fn despawn_all_game_entities(
@@ -197,27 +137,3 @@ pub fn despawn_all_game_entities(
         commands.entity(entity).despawn_recursive();
     }
 }
-
-// 生成BGM
-fn spawn_bgm(
-    audio_handles: Res<GlobalAudio>,
-    mut commands: Commands,
-    mut bgm_playing: Local<BGMPlaying>,
-) {
-    if bgm_playing.0 {
-        return;
-    }
-
-    if let Some(bgm) = audio_handles.background_music.clone() {
-        commands.spawn(AudioBundle {
-            source: bgm,
-            settings: PlaybackSettings {
-                mode: PlaybackMode::Loop,
-                ..default()
-            },
-            ..default()
-        });
-
-        bgm_playing.0 = true;
-    }
-}